@@ -0,0 +1,446 @@
+//! Archive of every fetched news item with full text search.
+//!
+//! Items are archived once they are published by a [`Newsletter`](crate::newsletter::Newsletter),
+//! whether they were posted to any channel or not. `/search` looks them up
+//! through an FTS5 index over titles and contents.
+use crate::{subscriptions, Error, PoiseContext};
+use chrono::{DateTime, NaiveDate, Utc};
+use poise::{
+    serenity_prelude::{Colour, CreateEmbed},
+    CreateReply,
+};
+
+/// Max results of `/search`.
+pub const MAX_SEARCH_RESULTS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedItem {
+    /// Newsletter source, e.g. `poe1`.
+    pub source: String,
+    pub item_id: String,
+    pub title: String,
+    pub url: String,
+    pub published_at: DateTime<Utc>,
+    /// Markdown of the item, empty if it could not be fetched.
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub source: String,
+    pub title: String,
+    pub url: String,
+    pub published_at: DateTime<Utc>,
+    /// Part of the title or content around the matched words, which are in bold.
+    pub snippet: String,
+}
+
+/// FTS5 query matching all words of the user query, in any order.
+/// Words are quoted, so FTS5 operators and punctuation in the query are taken literally.
+pub fn fts_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// `since` of `/search`: a date like `2025-04-17`.
+fn parse_since(since: &str) -> Result<DateTime<Utc>, Error> {
+    NaiveDate::parse_from_str(since.trim(), "%Y-%m-%d")
+        .map(|date| date.and_time(Default::default()).and_utc())
+        .map_err(|_| format!("Invalid date {since}, expected YYYY-MM-DD").into())
+}
+
+/// Search all archived news and patch notes
+#[poise::command(slash_command)]
+pub async fn search(
+    ctx: PoiseContext<'_>,
+    #[description = "Words to find, e.g. \"chaos inoculation\""] query: String,
+    #[description = "Only news of this newsletter: poe1, poe2, epoch, diablo or a feed"]
+    #[autocomplete = "subscriptions::autocomplete_newsletter"]
+    game: Option<String>,
+    #[description = "Only news published since this date, e.g. 2025-04-17"] since: Option<String>,
+) -> Result<(), Error> {
+    let Some(fts_query) = fts_query(&query) else {
+        return Err("Search query is empty".into());
+    };
+    let since = since.as_deref().map(parse_since).transpose()?;
+    ctx.defer().await?;
+
+    let conn = ctx.data().db.connect()?;
+    let results = db_layer::search(
+        &conn,
+        &fts_query,
+        game.as_deref(),
+        since,
+        MAX_SEARCH_RESULTS,
+    )
+    .await?;
+
+    ctx.send(CreateReply::default().embed(create_results_embed(&query, &results)))
+        .await?;
+    Ok(())
+}
+
+fn create_results_embed(query: &str, results: &[SearchResult]) -> CreateEmbed {
+    let embed = CreateEmbed::new()
+        .title(format!("Search: {query}"))
+        .colour(Colour::BLUE);
+    if results.is_empty() {
+        return embed.description("Nothing found");
+    }
+
+    let mut description = String::new();
+    for (i, result) in results.iter().enumerate() {
+        let entry = format!(
+            "**{}. [{}]({})**\n{} · <t:{}:d>\n> {}\n\n",
+            i + 1,
+            escape_link_text(&result.title),
+            result.url,
+            result.source,
+            result.published_at.timestamp(),
+            result.snippet.replace('\n', " ")
+        );
+        if description.chars().count() + entry.chars().count() > crate::EMBED_DESCRIPTION_MAX_CHARS
+        {
+            break;
+        }
+        description += &entry;
+    }
+    embed.description(description)
+}
+
+/// Text of a markdown link, brackets escaped so that they don't end it.
+fn escape_link_text(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+pub mod db_layer {
+    use super::{ArchivedItem, SearchResult};
+    use chrono::{DateTime, Utc};
+    use libsql::{params, Connection, Error as LibsqlError, Row};
+
+    pub const CREATE_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS news_archive (
+        source TEXT NOT NULL,
+        item_id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        url TEXT NOT NULL,
+        published_at TEXT NOT NULL,
+        content TEXT NOT NULL DEFAULT '',
+        archived_at TEXT DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (source, item_id)
+    ) STRICT;"#;
+
+    /// Index over titles and contents of [`CREATE_IF_NOT_EXISTS`], kept in sync by triggers.
+    pub const CREATE_FTS_IF_NOT_EXISTS: &str = r#"
+    CREATE VIRTUAL TABLE IF NOT EXISTS news_archive_fts USING fts5(
+        title,
+        content,
+        content = 'news_archive',
+        content_rowid = 'rowid'
+    );"#;
+
+    pub const CREATE_TRIGGERS_IF_NOT_EXISTS: [&str; 3] = [
+        r#"
+    CREATE TRIGGER IF NOT EXISTS news_archive_insert AFTER INSERT ON news_archive BEGIN
+        INSERT INTO news_archive_fts (rowid, title, content)
+            VALUES (new.rowid, new.title, new.content);
+    END;"#,
+        r#"
+    CREATE TRIGGER IF NOT EXISTS news_archive_delete AFTER DELETE ON news_archive BEGIN
+        INSERT INTO news_archive_fts (news_archive_fts, rowid, title, content)
+            VALUES ('delete', old.rowid, old.title, old.content);
+    END;"#,
+        r#"
+    CREATE TRIGGER IF NOT EXISTS news_archive_update AFTER UPDATE ON news_archive BEGIN
+        INSERT INTO news_archive_fts (news_archive_fts, rowid, title, content)
+            VALUES ('delete', old.rowid, old.title, old.content);
+        INSERT INTO news_archive_fts (rowid, title, content)
+            VALUES (new.rowid, new.title, new.content);
+    END;"#,
+    ];
+
+    pub async fn ensure_schema_exists(conn: &Connection) -> Result<(), LibsqlError> {
+        conn.execute(CREATE_IF_NOT_EXISTS, ()).await?;
+        conn.execute(CREATE_FTS_IF_NOT_EXISTS, ()).await?;
+        for trigger in CREATE_TRIGGERS_IF_NOT_EXISTS {
+            conn.execute(trigger, ()).await?;
+        }
+        Ok(())
+    }
+
+    /// Saves the item, replacing title, url and content if it was already archived.
+    /// Empty content doesn't replace the archived one.
+    pub async fn save_item(conn: &Connection, item: &ArchivedItem) -> Result<(), String> {
+        conn.execute(
+            "INSERT INTO
+                news_archive (source, item_id, title, url, published_at, content)
+                VALUES
+                    (?, ?, ?, ?, ?, ?)
+                ON CONFLICT (source, item_id) DO UPDATE SET
+                    title = excluded.title,
+                    url = excluded.url,
+                    content = CASE
+                        WHEN excluded.content = '' THEN news_archive.content
+                        ELSE excluded.content
+                    END",
+            params![
+                item.source.as_str(),
+                item.item_id.as_str(),
+                item.title.as_str(),
+                item.url.as_str(),
+                item.published_at.to_rfc3339(),
+                item.content.as_str()
+            ],
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "DB execute failed for save_item [{} - {}]: {}",
+                item.source, item.item_id, e
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Best matches of the FTS5 query, see [`fts_query`](super::fts_query).
+    /// Title matches weigh more than content matches.
+    pub async fn search(
+        conn: &Connection,
+        fts_query: &str,
+        source: Option<&str>,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>, String> {
+        let mut rows = conn
+            .query(
+                "SELECT
+                    a.source,
+                    a.title,
+                    a.url,
+                    a.published_at,
+                    snippet(news_archive_fts, -1, '**', '**', '…', 24)
+                FROM
+                    news_archive_fts
+                    JOIN news_archive a ON a.rowid = news_archive_fts.rowid
+                WHERE
+                    news_archive_fts MATCH ?1
+                    AND (?2 IS NULL OR a.source = ?2)
+                    AND (?3 IS NULL OR a.published_at >= ?3)
+                ORDER BY
+                    bm25(news_archive_fts, 5.0, 1.0)
+                LIMIT ?4",
+                params![
+                    fts_query,
+                    source,
+                    since.map(|since| since.to_rfc3339()),
+                    limit as i64
+                ],
+            )
+            .await
+            .map_err(|e| format!("DB query failed for search [{}]: {}", fts_query, e))?;
+
+        let mut results = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| format!("Failed to get next row: {}", e))?
+        {
+            results.push(result_from_row(&row)?);
+        }
+
+        Ok(results)
+    }
+
+    fn result_from_row(row: &Row) -> Result<SearchResult, String> {
+        let source: String = row
+            .get(0)
+            .map_err(|e| format!("Failed to get source: {}", e))?;
+        let title: String = row
+            .get(1)
+            .map_err(|e| format!("Failed to get title: {}", e))?;
+        let url: String = row
+            .get(2)
+            .map_err(|e| format!("Failed to get url: {}", e))?;
+        let published_at: String = row
+            .get(3)
+            .map_err(|e| format!("Failed to get published_at: {}", e))?;
+        let snippet: String = row
+            .get(4)
+            .map_err(|e| format!("Failed to get snippet: {}", e))?;
+
+        Ok(SearchResult {
+            source,
+            title,
+            url,
+            published_at: DateTime::parse_from_rfc3339(&published_at)
+                .map_err(|e| format!("Failed to parse published_at {published_at}: {e}"))?
+                .to_utc(),
+            snippet,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_every_word_of_query() {
+        assert_eq!(
+            fts_query("chaos  inoculation").as_deref(),
+            Some(r#""chaos" "inoculation""#)
+        );
+        assert_eq!(
+            fts_query(r#"3.26 "OR" -bleed"#).as_deref(),
+            Some(r#""3.26" """OR""" "-bleed""#)
+        );
+        assert_eq!(fts_query("   "), None);
+    }
+
+    #[test]
+    fn escapes_brackets_of_link_text() {
+        assert_eq!(
+            escape_link_text("[PoE2] 0.2.0e Patch Notes"),
+            r"\[PoE2\] 0.2.0e Patch Notes"
+        );
+    }
+
+    #[test]
+    fn parses_since_date() {
+        assert_eq!(
+            parse_since("2025-04-17").unwrap(),
+            "2025-04-17T00:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert!(parse_since("last week").is_err());
+    }
+}
+
+#[cfg(test)]
+mod db_layer_tests {
+    use super::{db_layer::*, fts_query, ArchivedItem};
+    use chrono::{DateTime, Utc};
+    use libsql::{Builder, Connection};
+
+    async fn memory_db_client() -> Connection {
+        let db = Builder::new_local(":memory:").build().await.unwrap();
+        let conn = db.connect().unwrap();
+        ensure_schema_exists(&conn).await.unwrap();
+        conn
+    }
+
+    fn item(
+        source: &str,
+        id: &str,
+        title: &str,
+        published_at: &str,
+        content: &str,
+    ) -> ArchivedItem {
+        ArchivedItem {
+            source: source.to_owned(),
+            item_id: id.to_owned(),
+            title: title.to_owned(),
+            url: format!("https://example.com/{id}"),
+            published_at: published_at.parse().unwrap(),
+            content: content.to_owned(),
+        }
+    }
+
+    async fn search_titles(
+        conn: &Connection,
+        query: &str,
+        source: Option<&str>,
+        since: Option<&str>,
+    ) -> Vec<String> {
+        let since = since.map(|since| since.parse::<DateTime<Utc>>().unwrap());
+        search(conn, &fts_query(query).unwrap(), source, since, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|result| result.title)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn searches_ranked_matches_with_filters() {
+        let conn = memory_db_client().await;
+        for item in [
+            item(
+                "poe2",
+                "1",
+                "0.2.0e Patch Notes",
+                "2025-04-17T16:00:00Z",
+                "- Chaos Inoculation now additionally makes you immune to bleeding.",
+            ),
+            item(
+                "poe1",
+                "2",
+                "Chaos Inoculation changes",
+                "2024-03-26T09:00:00Z",
+                "Chaos Inoculation keystone is reworked.",
+            ),
+            item(
+                "epoch",
+                "3",
+                "Last Epoch Patch 1.2.3 Notes",
+                "2025-05-08T16:00:00Z",
+                "Fixed a bug with Volatile Reversal.",
+            ),
+        ] {
+            save_item(&conn, &item).await.unwrap();
+        }
+
+        assert_eq!(
+            search_titles(&conn, "inoculation chaos", None, None).await,
+            ["Chaos Inoculation changes", "0.2.0e Patch Notes"]
+        );
+        assert_eq!(
+            search_titles(&conn, "inoculation", Some("poe2"), None).await,
+            ["0.2.0e Patch Notes"]
+        );
+        assert_eq!(
+            search_titles(&conn, "inoculation", None, Some("2025-01-01T00:00:00Z")).await,
+            ["0.2.0e Patch Notes"]
+        );
+        assert!(search_titles(&conn, "inoculation reversal", None, None)
+            .await
+            .is_empty());
+
+        let results = search(&conn, &fts_query("reversal").unwrap(), None, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(results[0].url, "https://example.com/3");
+        assert_eq!(
+            results[0].published_at,
+            "2025-05-08T16:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert!(results[0].snippet.contains("**Reversal**"));
+    }
+
+    #[tokio::test]
+    async fn updates_archived_item() {
+        let conn = memory_db_client().await;
+        let mut hotfix = item("poe2", "1", "0.2.0e Hotfix", "2025-04-17T16:00:00Z", "");
+        save_item(&conn, &hotfix).await.unwrap();
+        assert!(search_titles(&conn, "warlock", None, None).await.is_empty());
+
+        hotfix.content = "Warlock minions no longer get stuck.".to_owned();
+        save_item(&conn, &hotfix).await.unwrap();
+        assert_eq!(
+            search_titles(&conn, "warlock", None, None).await,
+            ["0.2.0e Hotfix"]
+        );
+
+        // Empty content keeps the archived one.
+        hotfix.content.clear();
+        hotfix.title = "0.2.0e Hotfix 2".to_owned();
+        save_item(&conn, &hotfix).await.unwrap();
+        assert_eq!(
+            search_titles(&conn, "warlock", None, None).await,
+            ["0.2.0e Hotfix 2"]
+        );
+    }
+}
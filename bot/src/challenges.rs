@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use crate::{channel::AppChannel, SerenityContext};
+use crate::{channel::AppChannel, time, SerenityContext};
 use chrono::Timelike;
 use poe_challenge_extractor::{load_history, TierEntry};
 
@@ -29,7 +29,8 @@ pub async fn start_daily_summarizer(ctx: &SerenityContext) {
     use tokio::time::sleep;
 
     loop {
-        let now = chrono::Local::now();
+        let timezone = time::default_display_timezone();
+        let now = chrono::Utc::now().with_timezone(&timezone);
 
         if now.hour() == 22 && now.minute() == 0 {
             if let Some(entry) = get_last_entry() {
@@ -38,14 +39,14 @@ pub async fn start_daily_summarizer(ctx: &SerenityContext) {
                     format!(
                         "Daily summary: <@407169521988665345> {} tiers remaining ({} - no maps completed for {} hours)",
                         entry.remaining,
-                        entry.datetime_moscow(),
+                        entry.datetime_in(timezone),
                         hours_stale
                     )
                 } else {
                     format!(
                         "Daily summary: <@407169521988665345> {} tiers remaining ({})",
                         entry.remaining,
-                        entry.datetime_moscow()
+                        entry.datetime_in(timezone)
                     )
                 };
                 AppChannel::Poe1.say(ctx, &message).await;
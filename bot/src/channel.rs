@@ -1,4 +1,15 @@
-use poise::serenity_prelude::{CacheHttp, ChannelId};
+use poise::serenity_prelude::{self as serenity, CacheHttp, ChannelId, GuildId};
+
+/// Guild of [`AppChannel`] channels, looked up from the general channel.
+pub async fn home_guild_id(ctx: impl CacheHttp) -> Result<GuildId, serenity::Error> {
+    AppChannel::General
+        .id()
+        .to_channel(ctx)
+        .await?
+        .guild()
+        .map(|channel| channel.guild_id)
+        .ok_or(serenity::Error::Other("General channel is not in a guild"))
+}
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
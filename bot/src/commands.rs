@@ -1,10 +1,11 @@
 use crate::{
-    channel::AppChannel,
-    newsletter::{NewsItem, Newsletter},
-    time::fmt,
+    newsletter::{save_published_ids, NewsItem, Newsletter},
+    subscriptions,
+    time::{self, fmt},
     PoiseContext,
 };
-use poise::{serenity_prelude::ChannelId, CreateReply};
+use chrono_tz::Tz;
+use poise::CreateReply;
 use std::time::Duration;
 
 pub type CommandError = Box<dyn std::error::Error + Send + Sync>;
@@ -71,7 +72,7 @@ pub async fn fresh_news(
     Ok(())
 }
 
-#[derive(poise::ChoiceParameter)]
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
 pub enum NewsletterChoice {
     #[name = "poe1"]
     Poe1,
@@ -83,12 +84,24 @@ pub enum NewsletterChoice {
     Diablo,
 }
 
+impl NewsletterChoice {
+    /// [`Newsletter::source`] of the chosen newsletter.
+    pub fn source(&self) -> &'static str {
+        match self {
+            NewsletterChoice::Poe1 => "poe1",
+            NewsletterChoice::Poe2 => "poe2",
+            NewsletterChoice::LastEpoch => "epoch",
+            NewsletterChoice::Diablo => "diablo",
+        }
+    }
+}
+
 /// Post latest news from all newsletters
 #[poise::command(slash_command)]
 pub async fn news(
     ctx: PoiseContext<'_>,
     #[description = "Number of minutes to look back"] mins: u64,
-    #[description = "Should all news items be posted in channels"] post: bool,
+    #[description = "Should all news items be posted in subscribed channels"] post: bool,
 ) -> Result<(), CommandError> {
     ctx.defer().await?;
     ctx.reply(format!(
@@ -101,11 +114,12 @@ pub async fn news(
     .await?;
     let n = &ctx.data().newsletters;
     let stale_time = Duration::from_mins(mins);
+    let timezone = time::author_timezone(ctx).await;
 
-    let p1 = news_per_newsletter(ctx, stale_time, &n.poe1, AppChannel::Poe1, post);
-    let p2 = news_per_newsletter(ctx, stale_time, &n.poe2, AppChannel::Poe2, post);
-    let e = news_per_newsletter(ctx, stale_time, &n.epoch, AppChannel::LastEpoch, post);
-    let d = news_per_newsletter(ctx, stale_time, &n.diablo, AppChannel::Diablo, post);
+    let p1 = news_per_newsletter(ctx, stale_time, &n.poe1, post, timezone);
+    let p2 = news_per_newsletter(ctx, stale_time, &n.poe2, post, timezone);
+    let e = news_per_newsletter(ctx, stale_time, &n.epoch, post, timezone);
+    let d = news_per_newsletter(ctx, stale_time, &n.diablo, post, timezone);
 
     let (poe1_msg, poe2_msg, epoch_msg, diablo_msg) = tokio::join!(p1, p2, e, d);
 
@@ -134,17 +148,18 @@ pub async fn news(
     Ok(())
 }
 
-/// Returns aggregated message about posts and posts each one if post param is true.
-async fn news_per_newsletter<N, C>(
+/// Returns aggregated message about posts and posts each one
+/// to subscribed channels if post param is true.
+/// Dates are shown in `timezone`.
+async fn news_per_newsletter<N>(
     ctx: PoiseContext<'_>,
     stale_time: Duration,
     newsletter: &N,
-    channel: C,
     post: bool,
+    timezone: Tz,
 ) -> String
 where
     N: Newsletter,
-    C: Into<ChannelId>,
 {
     let items = match newsletter.fetch_fresh(stale_time).await {
         Ok(mut items) => {
@@ -156,17 +171,36 @@ where
         }
     };
 
+    let routes = match post {
+        true => match subscriptions::load_routes(ctx.data(), newsletter.source()).await {
+            Ok(routes) => routes,
+            Err(err) => return format!("Could not load subscribed channels. {err}"),
+        },
+        false => Vec::new(),
+    };
+    let conn = match post {
+        true => match ctx.data().db.connect() {
+            Ok(conn) => Some(conn),
+            Err(err) => return format!("DB connection error: {err}"),
+        },
+        false => None,
+    };
+
     let mut messages: Vec<String> = Vec::new();
-    let channel_id = channel.into();
     for item in items {
-        if post {
-            item.post_to_discord(ctx.serenity_context(), channel_id)
-                .await;
+        if let Some(conn) = &conn {
+            // Remembered like in the poll loop, so that it does not post the item again.
+            if subscriptions::post_to_routes(ctx.serenity_context(), &routes, &item)
+                .await
+                .is_some()
+            {
+                save_published_ids(conn, newsletter.source(), &item.published_ids()).await;
+            }
         }
 
         messages.push(format!(
             "\t{}: {}",
-            fmt(item.timestamp(), true),
+            fmt(item.timestamp(), timezone),
             item.title(),
         ));
     }
@@ -3,7 +3,11 @@ use std::{collections::HashMap, time::Duration};
 use ea_live_updates::{LiveUpdate, LiveUpdatesThread};
 use poise::serenity_prelude::{ChannelId, Colour, CreateEmbed, CreateEmbedAuthor, CreateMessage};
 
-use crate::{Data, SerenityContext};
+use crate::{
+    health,
+    watches::{self, Alert},
+    Data, SerenityContext,
+};
 
 pub async fn spin_ea_live_updates_loop(
     ctx: &SerenityContext,
@@ -60,7 +64,10 @@ pub async fn publish_new_ea_live_updates(
     live_updates_thread: LiveUpdatesThread,
     channel_id: &ChannelId,
 ) {
-    let ea_updates = match ea_live_updates::get_live_updates(live_updates_thread).await {
+    let result = ea_live_updates::get_live_updates(live_updates_thread).await;
+    let source = format!("live updates {}", live_updates_thread.title());
+    health::record(ctx, data, &source, &result).await;
+    let ea_updates = match result {
         Ok(updates) => updates,
         Err(err) => {
             println!("Could not get ea live updates. {live_updates_thread:#?} {err}");
@@ -79,6 +86,16 @@ pub async fn publish_new_ea_live_updates(
         .await
         .unwrap_or_else(|err| eprintln!("publish_new_updates Error: {err}"));
 
+    for update in &not_seen_updates {
+        let alert = Alert {
+            source: "live updates",
+            title: &update.heading,
+            url: Some(update.thread.url()),
+            body: Some(&update.content),
+        };
+        watches::notify(ctx, data, &alert).await;
+    }
+
     for u in not_seen_updates {
         published.insert(u.clone());
     }
@@ -0,0 +1,436 @@
+//! Health of newsletters and watchers.
+//!
+//! Every fetch is recorded per source. `/status` shows the records, and the Dev channel
+//! is alerted when a source keeps failing or keeps returning nothing.
+use crate::{channel::AppChannel, Data, Error, PoiseContext, SerenityContext};
+use chrono::{DateTime, TimeDelta, Utc};
+use poise::{
+    serenity_prelude::{Colour, CreateEmbed},
+    CreateReply,
+};
+use std::collections::BTreeMap;
+
+/// Default for [`max_failures`].
+pub const MAX_FAILURES: u32 = 3;
+/// Default for [`max_empty_period`].
+pub const MAX_EMPTY_HOURS: i64 = 24;
+
+/// Consecutive failures before the Dev channel is alerted.
+/// Configured with `HEALTH_MAX_FAILURES` env, defaults to [`MAX_FAILURES`].
+pub fn max_failures() -> u32 {
+    std::env::var("HEALTH_MAX_FAILURES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(MAX_FAILURES)
+}
+
+/// How long a source may return zero items before the Dev channel is alerted.
+/// Configured with `HEALTH_MAX_EMPTY_HOURS` env, defaults to [`MAX_EMPTY_HOURS`].
+pub fn max_empty_period() -> TimeDelta {
+    let hours = std::env::var("HEALTH_MAX_EMPTY_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .unwrap_or(MAX_EMPTY_HOURS);
+    TimeDelta::hours(hours)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceHealth {
+    /// First time the source was recorded, since bot start.
+    pub first_seen: DateTime<Utc>,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_error: Option<(DateTime<Utc>, String)>,
+    pub consecutive_failures: u32,
+    /// Items returned by the last successful fetch.
+    pub items_seen: usize,
+    /// Last time a fetch returned at least one item.
+    pub last_nonempty: Option<DateTime<Utc>>,
+    /// Where the last fetch got its items from, if the source has several, e.g. `api ×3, forum ×1`.
+    pub served_by: Option<String>,
+    /// Alerted about emptiness, until the source returns items again.
+    empty_alerted: bool,
+    /// Alerted about failures, until the source succeeds again.
+    failing_alerted: bool,
+}
+
+impl SourceHealth {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            first_seen: now,
+            last_success: None,
+            last_error: None,
+            consecutive_failures: 0,
+            items_seen: 0,
+            last_nonempty: None,
+            served_by: None,
+            empty_alerted: false,
+            failing_alerted: false,
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.consecutive_failures == 0 && !self.empty_alerted && !self.failing_alerted
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthAlert {
+    Failing {
+        failures: u32,
+        error: String,
+    },
+    Empty {
+        since: DateTime<Utc>,
+    },
+    /// Page or response no longer looks as expected. Retrying won't help.
+    LayoutChanged {
+        error: String,
+    },
+    Recovered,
+}
+
+impl HealthAlert {
+    pub fn message(&self, source: &str) -> String {
+        match self {
+            HealthAlert::Failing { failures, error } => {
+                format!("❌ **{source}** failed {failures} times in a row: `{error}`")
+            }
+            HealthAlert::Empty { since } => format!(
+                "⚠️ **{source}** returned no items since <t:{}:f>. Did the layout change?",
+                since.timestamp()
+            ),
+            HealthAlert::LayoutChanged { error } => {
+                format!("🧩 **{source}** layout changed: `{error}`")
+            }
+            HealthAlert::Recovered => format!("✅ **{source}** is back to normal"),
+        }
+    }
+}
+
+/// Records of all sources by name.
+#[derive(Debug, Default)]
+pub struct Health {
+    pub sources: BTreeMap<String, SourceHealth>,
+    /// Unresolved layout errors by part of the source, e.g. `poe1 en/news`.
+    pub layout_errors: BTreeMap<String, String>,
+}
+
+impl Health {
+    pub fn record_success(
+        &mut self,
+        source: &str,
+        items: usize,
+        now: DateTime<Utc>,
+        max_empty_period: TimeDelta,
+    ) -> Option<HealthAlert> {
+        let health = self.entry(source, now);
+        health.last_success = Some(now);
+        health.consecutive_failures = 0;
+        health.items_seen = items;
+        if items > 0 {
+            health.last_nonempty = Some(now);
+        }
+
+        let was_failing = std::mem::take(&mut health.failing_alerted);
+        let empty_since = health.last_nonempty.unwrap_or(health.first_seen);
+        match (now - empty_since >= max_empty_period, health.empty_alerted) {
+            (true, false) => {
+                health.empty_alerted = true;
+                Some(HealthAlert::Empty { since: empty_since })
+            }
+            (false, true) => {
+                health.empty_alerted = false;
+                Some(HealthAlert::Recovered)
+            }
+            (false, false) if was_failing => Some(HealthAlert::Recovered),
+            _ => None,
+        }
+    }
+
+    pub fn record_failure(
+        &mut self,
+        source: &str,
+        error: String,
+        now: DateTime<Utc>,
+        max_failures: u32,
+    ) -> Option<HealthAlert> {
+        let health = self.entry(source, now);
+        health.consecutive_failures += 1;
+        health.last_error = Some((now, error.clone()));
+
+        match health.consecutive_failures >= max_failures && !health.failing_alerted {
+            true => {
+                health.failing_alerted = true;
+                Some(HealthAlert::Failing {
+                    failures: health.consecutive_failures,
+                    error,
+                })
+            }
+            false => None,
+        }
+    }
+
+    /// Forgets the source, e.g. a thread that is no longer watched.
+    pub fn remove(&mut self, source: &str) {
+        self.sources.remove(source);
+    }
+
+    pub fn record_served(&mut self, source: &str, served_by: String) {
+        self.entry(source, Utc::now()).served_by = Some(served_by);
+    }
+
+    /// Records whether `key` was parsed fine (`None`) or had a layout error.
+    /// Alerts once per layout error, and again when the layout is fine.
+    pub fn record_layout(&mut self, key: &str, error: Option<String>) -> Option<HealthAlert> {
+        match error {
+            Some(error) => match self.layout_errors.contains_key(key) {
+                true => None,
+                false => {
+                    self.layout_errors.insert(key.to_owned(), error.clone());
+                    Some(HealthAlert::LayoutChanged { error })
+                }
+            },
+            None => self
+                .layout_errors
+                .remove(key)
+                .map(|_| HealthAlert::Recovered),
+        }
+    }
+
+    fn entry(&mut self, source: &str, now: DateTime<Utc>) -> &mut SourceHealth {
+        self.sources
+            .entry(source.to_owned())
+            .or_insert_with(|| SourceHealth::new(now))
+    }
+}
+
+/// Records the fetch result of the source and alerts the Dev channel if needed.
+pub async fn record<T, E: std::fmt::Debug>(
+    ctx: &SerenityContext,
+    data: &Data,
+    source: &str,
+    result: &Result<Vec<T>, E>,
+) {
+    let now = Utc::now();
+    let alert = {
+        let mut health = data.health.lock().await;
+        match result {
+            Ok(items) => health.record_success(source, items.len(), now, max_empty_period()),
+            Err(err) => health.record_failure(source, format!("{err:?}"), now, max_failures()),
+        }
+    };
+
+    if let Some(alert) = alert {
+        AppChannel::Dev.say(ctx, &alert.message(source)).await;
+    }
+}
+
+/// Records layout state of `key` and alerts the Dev channel about new layout errors.
+pub async fn record_layout(ctx: &SerenityContext, data: &Data, key: &str, error: Option<String>) {
+    let alert = data.health.lock().await.record_layout(key, error);
+    if let Some(alert) = alert {
+        AppChannel::Dev.say(ctx, &alert.message(key)).await;
+    }
+}
+
+/// Show health of newsletters and watchers
+#[poise::command(slash_command)]
+pub async fn status(ctx: PoiseContext<'_>) -> Result<(), Error> {
+    let embed = create_status_embed(&*ctx.data().health.lock().await);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+fn create_status_embed(health: &Health) -> CreateEmbed {
+    let all_healthy =
+        health.sources.values().all(SourceHealth::is_healthy) && health.layout_errors.is_empty();
+    let embed = CreateEmbed::new()
+        .title("Sources status")
+        .color(match all_healthy {
+            true => Colour::DARK_GREEN,
+            false => Colour::RED,
+        });
+
+    if health.sources.is_empty() {
+        return embed.description("Nothing was fetched yet");
+    }
+
+    let embed = match health.layout_errors.is_empty() {
+        true => embed,
+        false => embed.description(
+            health
+                .layout_errors
+                .iter()
+                .map(|(key, error)| format!("🧩 **{key}** layout changed: `{error}`"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+    };
+
+    // Embeds are limited to 25 fields.
+    embed.fields(health.sources.iter().take(25).map(|(source, health)| {
+        let icon = match health.is_healthy() {
+            true => "✅",
+            false => "❌",
+        };
+        let timestamp = |time: Option<DateTime<Utc>>| match time {
+            Some(time) => format!("<t:{}:R>", time.timestamp()),
+            None => "never".to_owned(),
+        };
+
+        let mut value = format!(
+            "Last success: {}\nItems seen: {}\nFailures in a row: {}",
+            timestamp(health.last_success),
+            health.items_seen,
+            health.consecutive_failures
+        );
+        if let Some(served_by) = &health.served_by {
+            value += &format!("\nServed by: {served_by}");
+        }
+        if let Some((time, error)) = &health.last_error {
+            let error: String = error.chars().take(200).collect();
+            value += &format!("\nLast error: {} `{error}`", timestamp(Some(*time)));
+        }
+
+        (format!("{icon} {source}"), value, true)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn alerts_once_after_max_failures_and_on_recovery() {
+        let mut health = Health::default();
+        let now = date("2025-04-17T16:00:00Z");
+
+        assert_eq!(health.record_failure("poe1", "503".into(), now, 3), None);
+        assert_eq!(health.record_failure("poe1", "503".into(), now, 3), None);
+        assert_eq!(
+            health.record_failure("poe1", "timeout".into(), now, 3),
+            Some(HealthAlert::Failing {
+                failures: 3,
+                error: "timeout".into()
+            })
+        );
+        assert_eq!(health.record_failure("poe1", "503".into(), now, 3), None);
+        assert!(!health.sources["poe1"].is_healthy());
+
+        assert_eq!(
+            health.record_success("poe1", 3, now, TimeDelta::hours(24)),
+            Some(HealthAlert::Recovered)
+        );
+        let poe1 = &health.sources["poe1"];
+        assert!(poe1.is_healthy());
+        assert_eq!(poe1.items_seen, 3);
+        assert_eq!(poe1.last_success, Some(now));
+        assert_eq!(poe1.last_error, Some((now, "503".to_owned())));
+    }
+
+    #[test]
+    fn alerts_once_when_empty_for_too_long() {
+        let mut health = Health::default();
+        let start = date("2025-04-17T16:00:00Z");
+        let max_empty = TimeDelta::hours(24);
+
+        assert_eq!(health.record_success("epoch", 0, start, max_empty), None);
+        assert_eq!(
+            health.record_success("epoch", 0, start + TimeDelta::hours(12), max_empty),
+            None
+        );
+        assert_eq!(
+            health.record_success("epoch", 0, start + TimeDelta::hours(24), max_empty),
+            Some(HealthAlert::Empty { since: start })
+        );
+        assert_eq!(
+            health.record_success("epoch", 0, start + TimeDelta::hours(25), max_empty),
+            None
+        );
+        assert_eq!(
+            health.record_success("epoch", 2, start + TimeDelta::hours(26), max_empty),
+            Some(HealthAlert::Recovered)
+        );
+    }
+
+    #[test]
+    fn alerts_about_failures_of_a_source_already_alerted_as_empty() {
+        let mut health = Health::default();
+        let start = date("2025-04-17T16:00:00Z");
+        let max_empty = TimeDelta::hours(24);
+        let later = start + TimeDelta::hours(25);
+
+        assert_eq!(health.record_success("epoch", 0, start, max_empty), None);
+        assert_eq!(
+            health.record_success("epoch", 0, later, max_empty),
+            Some(HealthAlert::Empty { since: start })
+        );
+        assert_eq!(health.record_failure("epoch", "503".into(), later, 2), None);
+        assert_eq!(
+            health.record_failure("epoch", "503".into(), later, 2),
+            Some(HealthAlert::Failing {
+                failures: 2,
+                error: "503".into()
+            })
+        );
+        // Back, but still empty.
+        assert_eq!(health.record_success("epoch", 0, later, max_empty), None);
+        assert!(!health.sources["epoch"].is_healthy());
+        assert_eq!(
+            health.record_success("epoch", 1, later, max_empty),
+            Some(HealthAlert::Recovered)
+        );
+        assert!(health.sources["epoch"].is_healthy());
+    }
+
+    #[test]
+    fn alerts_empty_source_when_it_stops_failing() {
+        let mut health = Health::default();
+        let start = date("2025-04-17T16:00:00Z");
+        let max_empty = TimeDelta::hours(24);
+
+        assert_eq!(health.record_success("epoch", 0, start, max_empty), None);
+        health.record_failure("epoch", "503".into(), start, 1);
+        assert!(!health.sources["epoch"].is_healthy());
+
+        assert_eq!(
+            health.record_success("epoch", 0, start + TimeDelta::hours(25), max_empty),
+            Some(HealthAlert::Empty { since: start })
+        );
+        assert!(!health.sources["epoch"].is_healthy());
+    }
+
+    #[test]
+    fn alerts_once_per_layout_error() {
+        let mut health = Health::default();
+
+        assert_eq!(health.record_layout("poe1 en/news", None), None);
+        assert_eq!(
+            health.record_layout("poe1 en/news", Some("3 rows found, 0 parsed".into())),
+            Some(HealthAlert::LayoutChanged {
+                error: "3 rows found, 0 parsed".into()
+            })
+        );
+        assert_eq!(
+            health.record_layout("poe1 en/news", Some("3 rows found, 0 parsed".into())),
+            None
+        );
+        assert_eq!(
+            health.record_layout("poe1 ru/news", Some("no thread rows found".into())),
+            Some(HealthAlert::LayoutChanged {
+                error: "no thread rows found".into()
+            })
+        );
+        assert_eq!(
+            health.record_layout("poe1 en/news", None),
+            Some(HealthAlert::Recovered)
+        );
+        assert_eq!(health.record_layout("poe1 en/news", None), None);
+        assert_eq!(health.layout_errors.len(), 1);
+    }
+}
@@ -3,6 +3,12 @@ use std::{future::Future, time::Duration};
 use tokio::time::Interval;
 
 pub const INTERVAL_MINS: i64 = 10;
+/// Default for [`catch_up_max_age`].
+pub const CATCH_UP_MAX_AGE_HOURS: i64 = 24;
+/// Default for [`edit_watch_period`].
+pub const EDIT_WATCH_HOURS: i64 = 48;
+/// Default for [`translation_timeout`].
+pub const TRANSLATION_TIMEOUT_MINS: i64 = 60;
 
 pub fn is_within_last_minutes(minutes: i64, timestamp: DateTime<Utc>) -> bool {
     timestamp >= Utc::now() - TimeDelta::minutes(minutes)
@@ -12,8 +18,34 @@ pub fn interval() -> Interval {
     tokio::time::interval(Duration::from_mins(INTERVAL_MINS as u64))
 }
 
-pub fn is_fresh(timestamp: DateTime<Utc>) -> bool {
-    is_within_last_minutes(INTERVAL_MINS, timestamp)
+/// How far back news missed while the bot was offline are posted.
+/// Configured with `CATCH_UP_MAX_AGE_HOURS` env, defaults to [`CATCH_UP_MAX_AGE_HOURS`].
+pub fn catch_up_max_age() -> TimeDelta {
+    let hours = std::env::var("CATCH_UP_MAX_AGE_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .unwrap_or(CATCH_UP_MAX_AGE_HOURS);
+    TimeDelta::hours(hours)
+}
+
+/// How long published threads are re-checked for edits.
+/// Configured with `EDIT_WATCH_HOURS` env, defaults to [`EDIT_WATCH_HOURS`].
+pub fn edit_watch_period() -> TimeDelta {
+    let hours = std::env::var("EDIT_WATCH_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .unwrap_or(EDIT_WATCH_HOURS);
+    TimeDelta::hours(hours)
+}
+
+/// How long a PoE announcement waits for its translation before being posted alone.
+/// Configured with `TRANSLATION_TIMEOUT_MINS` env, defaults to [`TRANSLATION_TIMEOUT_MINS`].
+pub fn translation_timeout() -> TimeDelta {
+    let minutes = std::env::var("TRANSLATION_TIMEOUT_MINS")
+        .ok()
+        .and_then(|minutes| minutes.parse().ok())
+        .unwrap_or(TRANSLATION_TIMEOUT_MINS);
+    TimeDelta::minutes(minutes)
 }
 
 #[allow(unused)]
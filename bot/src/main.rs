@@ -5,12 +5,14 @@ use poise::serenity_prelude::{self as serenity};
 use std::{collections::HashSet, sync::Arc};
 
 mod announce;
+mod archive;
 mod challenges;
 mod channel;
 mod commands;
 #[allow(unused)]
 mod ea_live_updates;
 pub mod emoji;
+mod health;
 mod interval;
 mod message;
 mod message_handler;
@@ -18,8 +20,11 @@ mod newsletter;
 pub mod poe_teasers;
 mod ready_handler;
 mod status;
+mod subscriptions;
+mod thread_watches;
 pub mod time;
 mod unused;
+mod watches;
 
 pub const EMBED_DESCRIPTION_MAX_CHARS: usize = 4096;
 pub const EMBED_DESCRIPTION_CUSTOM_MAX_CHARS: usize = 1000;
@@ -52,6 +57,7 @@ pub struct Data {
     pub db: Arc<DbClient>,
     pub published_live_updates: Arc<Mutex<HashSet<LiveUpdate>>>,
     pub newsletters: Arc<newsletters::AppNewsletters>,
+    pub health: Arc<Mutex<health::Health>>,
 }
 
 #[tokio::main]
@@ -84,10 +90,39 @@ async fn main() {
                 poe_teasers::db_layer::ensure_schema_exists(&conn)
                     .await
                     .expect("Failed to ensure database schema exists.");
+                newsletter::db_layer::ensure_schema_exists(&conn)
+                    .await
+                    .expect("Failed to ensure database schema exists.");
+                newsletter::edits::db_layer::ensure_schema_exists(&conn)
+                    .await
+                    .expect("Failed to ensure database schema exists.");
+                subscriptions::db_layer::ensure_schema_exists(&conn)
+                    .await
+                    .expect("Failed to ensure database schema exists.");
+                watches::db_layer::ensure_schema_exists(&conn)
+                    .await
+                    .expect("Failed to ensure database schema exists.");
+                thread_watches::db_layer::ensure_schema_exists(&conn)
+                    .await
+                    .expect("Failed to ensure database schema exists.");
+                archive::db_layer::ensure_schema_exists(&conn)
+                    .await
+                    .expect("Failed to ensure database schema exists.");
+                time::db_layer::ensure_schema_exists(&conn)
+                    .await
+                    .expect("Failed to ensure database schema exists.");
+                // Not seeded without the guild, retried on the next start.
+                match channel::home_guild_id(ctx).await {
+                    Ok(guild_id) => subscriptions::db_layer::seed_default_routes(&conn, guild_id)
+                        .await
+                        .expect("Failed to seed default newsletter routes."),
+                    Err(err) => eprintln!("Could not look up the home guild, default routes are not seeded: {err}"),
+                }
                 Ok(Data {
                     db: Arc::new(db),
                     published_live_updates: Default::default(),
-                    newsletters: Arc::new(newsletters::AppNewsletters::new())
+                    newsletters: Arc::new(newsletters::AppNewsletters::new()),
+                    health: Default::default(),
                 })
             })
         })
@@ -99,6 +134,14 @@ async fn main() {
               newsletter::last_epoch::epoch_thread(),
               commands::fresh_news(),
               commands::news(),
+              subscriptions::subscribe(),
+              subscriptions::unsubscribe(),
+              watches::watch(),
+              thread_watches::watch_thread(),
+              thread_watches::unwatch_thread(),
+              health::status(),
+              archive::search(),
+              time::timezone(),
             ],
             ..Default::default()
         })
@@ -112,13 +155,14 @@ async fn main() {
 
 pub mod newsletters {
     use last_epoch_forum::Subforum as LastEpochSubforum;
-    use poe_forum::{Subforum, WebsiteLanguage};
+    use poe_forum::{Subforum, ThreadSource, WebsiteLanguage};
 
     use crate::{
         newsletter::{
-            diablo::DiabloNewsletter, last_epoch::LastEpochNewsletter, poe::PoeNewsletter,
+            diablo::DiabloNewsletter, feed::FeedNewsletter, last_epoch::LastEpochNewsletter,
+            poe::PoeNewsletter, Newsletter,
         },
-        time::Timezone,
+        time,
     };
 
     pub struct AppNewsletters {
@@ -126,6 +170,40 @@ pub mod newsletters {
         pub poe2: PoeNewsletter,
         pub epoch: LastEpochNewsletter,
         pub diablo: DiabloNewsletter,
+        /// RSS/Atom feeds from `FEEDS` env.
+        pub feeds: Vec<FeedNewsletter>,
+    }
+
+    /// PoE website languages from `POE_LANGUAGES` env, e.g. `en ru de`. Defaults to EN and RU.
+    fn poe_languages() -> Vec<WebsiteLanguage> {
+        let languages: Vec<WebsiteLanguage> = std::env::var("POE_LANGUAGES")
+            .unwrap_or_default()
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|code| !code.is_empty())
+            .filter_map(|code| match code.parse() {
+                Ok(lang) => Some(lang),
+                Err(e) => {
+                    eprintln!("Invalid POE_LANGUAGES entry: {e}");
+                    None
+                }
+            })
+            .collect();
+        match languages.is_empty() {
+            true => vec![WebsiteLanguage::En, WebsiteLanguage::Ru],
+            false => languages,
+        }
+    }
+
+    /// PoE Forum Reader API from `POE_API_URL` env, tried before scraping the forum.
+    /// `POE_API_TIMEZONE` is the timezone of the API deployment, if it doesn't return UTC.
+    fn poe_api() -> Option<ThreadSource> {
+        let base_url = std::env::var("POE_API_URL")
+            .ok()
+            .filter(|url| !url.trim().is_empty())?;
+        Some(ThreadSource::Api {
+            base_url,
+            timezone: time::env_timezone("POE_API_TIMEZONE"),
+        })
     }
 
     impl Default for AppNewsletters {
@@ -136,24 +214,27 @@ pub mod newsletters {
 
     impl AppNewsletters {
         pub fn new() -> Self {
+            let languages = poe_languages();
             let poe1 = PoeNewsletter::new(
-                vec![
-                    (WebsiteLanguage::En, Subforum::News),
-                    (WebsiteLanguage::Ru, Subforum::News),
-                    (WebsiteLanguage::En, Subforum::PatchNotes),
-                    (WebsiteLanguage::Ru, Subforum::PatchNotes),
-                ],
-                Timezone::Moscow,
+                "poe1",
+                [Subforum::News, Subforum::PatchNotes]
+                    .into_iter()
+                    .flat_map(|subforum| languages.iter().map(move |lang| (*lang, subforum)))
+                    .collect(),
+                time::poe_forum_timezone(),
+                poe_api(),
             );
 
             let poe2 = PoeNewsletter::new(
+                "poe2",
                 vec![
                     (WebsiteLanguage::En, Subforum::EarlyAccessPatchNotesEn),
                     (WebsiteLanguage::Ru, Subforum::EarlyAccessPatchNotesRu),
                     (WebsiteLanguage::En, Subforum::EarlyAccessAnnouncementsEn),
                     (WebsiteLanguage::Ru, Subforum::EarlyAccessAnnouncementsRu),
                 ],
-                Timezone::Moscow,
+                time::poe_forum_timezone(),
+                poe_api(),
             );
 
             let epoch = LastEpochNewsletter::new(vec![
@@ -167,7 +248,21 @@ pub mod newsletters {
                 poe2,
                 epoch,
                 diablo: DiabloNewsletter,
+                feeds: FeedNewsletter::from_env(),
             }
         }
+
+        /// [`Newsletter::source`] of every newsletter.
+        pub fn sources(&self) -> Vec<&str> {
+            [
+                self.poe1.source(),
+                self.poe2.source(),
+                self.epoch.source(),
+                self.diablo.source(),
+            ]
+            .into_iter()
+            .chain(self.feeds.iter().map(|feed| feed.source()))
+            .collect()
+        }
     }
 }
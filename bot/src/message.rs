@@ -1,16 +1,44 @@
 use crate::SerenityContext;
-use poise::serenity_prelude::{ChannelId, CreateEmbed, CreateMessage, CreateThread};
+use poise::serenity_prelude::{
+    self as serenity, ChannelId, ChannelType, CreateEmbed, CreateMessage, CreateThread,
+};
+use std::ops::Range;
+
+/// Max embeds of a Discord message.
+pub const MESSAGE_MAX_EMBEDS: usize = 10;
+/// Max chars of all embeds of a Discord message.
+pub const MESSAGE_MAX_CHARS: usize = 6000;
+/// Max chars of an image alt text shown under the image.
+const IMAGE_ALT_MAX_CHARS: usize = 200;
 
 pub struct MessageWithThreadedDetails {
     pub message: CreateMessage,
     pub thread_name: String,
     pub details_content: Option<String>,
+    /// Images of the details content, posted as embeds.
+    pub details_images: Vec<DetailsImage>,
+}
+
+/// Image posted as an embed after the details text that precedes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetailsImage {
+    pub url: String,
+    pub alt: Option<String>,
+    /// Bytes of the details content the image replaces, e.g. its markdown link.
+    pub range: Range<usize>,
 }
 
 impl MessageWithThreadedDetails {
-    pub async fn send(self, ctx: &SerenityContext, channel_id: ChannelId) {
+    /// Sends the summary message and returns the details thread, if it was created.
+    /// Fails only if the summary itself could not be sent, problems with the details thread are logged.
+    pub async fn send(
+        self,
+        ctx: &SerenityContext,
+        channel_id: ChannelId,
+    ) -> Result<Option<ChannelId>, serenity::Error> {
         match channel_id.send_message(ctx, self.message).await {
             Ok(initial_message) => {
+                let mut thread_id = None;
                 if let Some(details_content) = self.details_content {
                     match initial_message
                         .channel_id
@@ -22,32 +50,15 @@ impl MessageWithThreadedDetails {
                         .await
                     {
                         Ok(thread_channel) => {
-                            let detail_messages = create_details_message(&details_content);
-                            let total_parts = detail_messages.len();
-                            for (index, thread_message_part) in
-                                detail_messages.into_iter().enumerate()
-                            {
-                                match thread_channel.send_message(ctx, thread_message_part).await {
-                                    Ok(_) => {
-                                        println!(
-                                            "Successfully sent detail part {}/{} to thread '{}' (from initial message {}).",
-                                            index + 1,
-                                            total_parts,
-                                            self.thread_name,
-                                            initial_message.id
-                                        );
-                                    }
-                                    Err(e) => {
-                                        eprintln!(
-                                            "Failed to send detail part {}/{} to thread '{}': {:?}",
-                                            index + 1,
-                                            total_parts,
-                                            self.thread_name,
-                                            e
-                                        );
-                                    }
-                                }
-                            }
+                            thread_id = Some(thread_channel.id);
+                            send_details(
+                                ctx,
+                                thread_channel.id,
+                                &self.thread_name,
+                                &details_content,
+                                &self.details_images,
+                            )
+                            .await;
                         }
                         Err(e) => {
                             eprintln!(
@@ -57,36 +68,259 @@ impl MessageWithThreadedDetails {
                         }
                     }
                 }
+                Ok(thread_id)
             }
             Err(e) => {
                 eprintln!(
                     "Failed to send initial embed to dev channel {}: {:?}",
                     channel_id, e
                 );
+                Err(e)
             }
         }
     }
 }
 
-pub fn create_details_message(content: &str) -> Vec<CreateMessage> {
-    let mut text_fragments: Vec<String> = Vec::new();
-    let mut current_fragment = String::new();
+/// Creates a thread without a starter message in the channel and sends details to it.
+/// Used when the summary message already has a thread, e.g. for a second translation.
+pub async fn create_details_thread(
+    ctx: &SerenityContext,
+    channel_id: ChannelId,
+    thread_name: &str,
+    details_content: &str,
+    details_images: &[DetailsImage],
+) -> Option<ChannelId> {
+    match channel_id
+        .create_thread(
+            ctx,
+            CreateThread::new(thread_name).kind(ChannelType::PublicThread),
+        )
+        .await
+    {
+        Ok(thread_channel) => {
+            send_details(
+                ctx,
+                thread_channel.id,
+                thread_name,
+                details_content,
+                details_images,
+            )
+            .await;
+            Some(thread_channel.id)
+        }
+        Err(e) => {
+            eprintln!(
+                "Failed to create Discord thread '{}' in channel {}: {:?}",
+                thread_name, channel_id, e
+            );
+            None
+        }
+    }
+}
 
-    for ch in content.chars() {
-        if current_fragment.len() == crate::EMBED_DESCRIPTION_MAX_CHARS {
-            text_fragments.push(current_fragment);
-            current_fragment = String::new();
+async fn send_details(
+    ctx: &SerenityContext,
+    thread_id: ChannelId,
+    thread_name: &str,
+    details_content: &str,
+    details_images: &[DetailsImage],
+) {
+    let detail_messages = create_details_message(details_content, details_images);
+    let total_parts = detail_messages.len();
+    for (index, thread_message_part) in detail_messages.into_iter().enumerate() {
+        match thread_id.send_message(ctx, thread_message_part).await {
+            Ok(_) => {
+                println!(
+                    "Successfully sent detail part {}/{} to thread '{}'.",
+                    index + 1,
+                    total_parts,
+                    thread_name,
+                );
+            }
+            Err(e) => {
+                eprintln!(
+                    "Failed to send detail part {}/{} to thread '{}': {:?}",
+                    index + 1,
+                    total_parts,
+                    thread_name,
+                    e
+                );
+            }
         }
-        current_fragment.push(ch);
     }
+}
 
-    // After the loop, add the last fragment if it has content
-    if !current_fragment.is_empty() {
-        text_fragments.push(current_fragment);
+/// Embed of a details message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DetailsEmbed<'a> {
+    Text(String),
+    Image(&'a DetailsImage),
+}
+
+impl DetailsEmbed<'_> {
+    fn create(&self) -> CreateEmbed {
+        match self {
+            DetailsEmbed::Text(text) => CreateEmbed::new().description(text),
+            DetailsEmbed::Image(image) => {
+                let embed = CreateEmbed::new().image(&image.url);
+                match &image.alt {
+                    Some(alt) => {
+                        embed.description(alt.chars().take(IMAGE_ALT_MAX_CHARS).collect::<String>())
+                    }
+                    None => embed,
+                }
+            }
+        }
     }
 
-    text_fragments
+    /// Chars counted towards [`MESSAGE_MAX_CHARS`].
+    fn chars(&self) -> usize {
+        match self {
+            DetailsEmbed::Text(text) => text.chars().count(),
+            DetailsEmbed::Image(image) => image
+                .alt
+                .as_ref()
+                .map_or(0, |alt| alt.chars().count().min(IMAGE_ALT_MAX_CHARS)),
+        }
+    }
+}
+
+pub fn create_details_message(content: &str, images: &[DetailsImage]) -> Vec<CreateMessage> {
+    layout_details(content, images)
         .into_iter()
-        .map(|content| CreateMessage::new().embed(CreateEmbed::new().description(content)))
+        .map(|embeds| {
+            CreateMessage::new().embeds(embeds.iter().map(DetailsEmbed::create).collect())
+        })
         .collect()
 }
+
+/// Embeds of each details message. Text is split into embeds of at most
+/// [`crate::EMBED_DESCRIPTION_MAX_CHARS`] at markdown boundaries, embeds are added
+/// to the last message until it has [`MESSAGE_MAX_EMBEDS`] or [`MESSAGE_MAX_CHARS`],
+/// so every image follows the text before it.
+pub fn layout_details<'a>(content: &str, images: &'a [DetailsImage]) -> Vec<Vec<DetailsEmbed<'a>>> {
+    let mut images: Vec<&DetailsImage> = images
+        .iter()
+        .filter(|image| {
+            content.is_char_boundary(image.range.start) && content.is_char_boundary(image.range.end)
+        })
+        .collect();
+    images.sort_by_key(|image| image.range.start);
+
+    let mut messages: Vec<Vec<DetailsEmbed>> = Vec::new();
+    let mut cursor = 0;
+    for image in images {
+        // Overlapping ranges, e.g. two images in one link.
+        let start = image.range.start.max(cursor);
+        for text in split_text(&content[cursor..start]) {
+            push_embed(&mut messages, DetailsEmbed::Text(text));
+        }
+        push_embed(&mut messages, DetailsEmbed::Image(image));
+        cursor = image.range.end.max(cursor);
+    }
+    for text in split_text(&content[cursor..]) {
+        push_embed(&mut messages, DetailsEmbed::Text(text));
+    }
+
+    messages
+}
+
+fn push_embed<'a>(messages: &mut Vec<Vec<DetailsEmbed<'a>>>, embed: DetailsEmbed<'a>) {
+    match messages.last_mut() {
+        Some(embeds)
+            if embeds.len() < MESSAGE_MAX_EMBEDS
+                && embeds.iter().map(DetailsEmbed::chars).sum::<usize>() + embed.chars()
+                    <= MESSAGE_MAX_CHARS =>
+        {
+            embeds.push(embed)
+        }
+        _ => messages.push(vec![embed]),
+    }
+}
+
+fn split_text(text: &str) -> Vec<String> {
+    markdown::split::split(text, crate::EMBED_DESCRIPTION_MAX_CHARS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        layout_details, DetailsEmbed, DetailsImage, MESSAGE_MAX_CHARS, MESSAGE_MAX_EMBEDS,
+    };
+
+    fn image(content: &str, link: &str) -> DetailsImage {
+        let start = content.find(link).unwrap();
+        DetailsImage {
+            url: link.to_owned(),
+            alt: None,
+            range: start..start + link.len(),
+        }
+    }
+
+    #[test]
+    fn posts_images_next_to_their_text() {
+        let content = "Intro\n\n[Image](a.png)\n\nMiddle\n\n[Image](b.png)\n\nOutro";
+        let images = [
+            image(content, "[Image](a.png)"),
+            image(content, "[Image](b.png)"),
+        ];
+
+        assert_eq!(
+            layout_details(content, &images),
+            vec![vec![
+                DetailsEmbed::Text("Intro".to_owned()),
+                DetailsEmbed::Image(&images[0]),
+                DetailsEmbed::Text("Middle".to_owned()),
+                DetailsEmbed::Image(&images[1]),
+                DetailsEmbed::Text("Outro".to_owned()),
+            ]]
+        );
+    }
+
+    #[test]
+    fn starts_new_message_when_chars_are_full() {
+        let paragraph = "Текст. ".repeat(400);
+        let content = format!("{paragraph}\n\n{paragraph}\n\n{paragraph}");
+
+        let messages = layout_details(&content, &[]);
+        assert_eq!(messages.len(), 2);
+        for embeds in &messages {
+            let chars: usize = embeds
+                .iter()
+                .map(|embed| match embed {
+                    DetailsEmbed::Text(text) => text.chars().count(),
+                    DetailsEmbed::Image(_) => 0,
+                })
+                .sum();
+            assert!(chars <= MESSAGE_MAX_CHARS);
+        }
+        assert_eq!(
+            messages[0],
+            vec![DetailsEmbed::Text(paragraph.trim().to_owned()); 2]
+        );
+        assert_eq!(messages[1].len(), 1);
+    }
+
+    #[test]
+    fn starts_new_message_when_embeds_are_full() {
+        let content = "[Image](a.png)".repeat(MESSAGE_MAX_EMBEDS + 1);
+        let images: Vec<DetailsImage> = (0..=MESSAGE_MAX_EMBEDS)
+            .map(|i| {
+                let start = i * "[Image](a.png)".len();
+                DetailsImage {
+                    url: "a.png".to_owned(),
+                    alt: None,
+                    range: start..start + "[Image](a.png)".len(),
+                }
+            })
+            .collect();
+
+        let messages = layout_details(&content, &images);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].len(), MESSAGE_MAX_EMBEDS);
+        assert_eq!(
+            messages[1],
+            vec![DetailsEmbed::Image(&images[MESSAGE_MAX_EMBEDS])]
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use chrono::{DateTime, Utc};
+use libsql::{params, Connection, Error as LibsqlError};
+use std::collections::HashSet;
+
+pub const CREATE_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS published_news_items (
+        source TEXT NOT NULL,
+        item_id TEXT NOT NULL,
+        published_at TEXT DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (source, item_id)
+    ) STRICT;"#;
+
+pub const CREATE_CURSORS_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS newsletter_cursors (
+        source TEXT PRIMARY KEY NOT NULL,
+        last_poll_at TEXT NOT NULL
+    ) STRICT;"#;
+
+pub async fn ensure_schema_exists(conn: &Connection) -> Result<(), LibsqlError> {
+    conn.execute(CREATE_IF_NOT_EXISTS, ()).await?;
+    conn.execute(CREATE_CURSORS_IF_NOT_EXISTS, ()).await?;
+    Ok(())
+}
+
+/// Loads ids of all items that were already published for given newsletter source.
+pub async fn load_published_item_ids(
+    conn: &Connection,
+    source: &str,
+) -> Result<HashSet<String>, String> {
+    let mut rows = conn
+        .query(
+            "SELECT
+                item_id
+            FROM
+                published_news_items
+            WHERE
+                source = ?",
+            params![source],
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "DB query failed for load_published_item_ids [{}]: {}",
+                source, e
+            )
+        })?;
+
+    let mut ids = HashSet::new();
+    while let Some(row) = rows
+        .next()
+        .await
+        .map_err(|e| format!("Failed to get next row: {}", e))?
+    {
+        match row.get_value(0) {
+            Ok(libsql::Value::Text(id)) => {
+                ids.insert(id);
+            }
+            Ok(_) => {
+                return Err(format!(
+                    "Unexpected data type for item_id in DB row for source: {}",
+                    source
+                ))
+            }
+            Err(e) => {
+                return Err(format!(
+                    "Failed to get value from row for source {}: {}",
+                    source, e
+                ))
+            }
+        }
+    }
+
+    Ok(ids)
+}
+
+pub async fn save_published_item_id(
+    conn: &Connection,
+    source: &str,
+    item_id: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO
+             published_news_items (source, item_id)
+             VALUES
+                (?, ?)",
+        params![source, item_id],
+    )
+    .await
+    .map_err(|e| {
+        format!(
+            "DB execute failed for save_published_item_id [{} - {}]: {}",
+            source, item_id, e
+        )
+    })?;
+
+    Ok(())
+}
+
+/// Loads the time of the last successful poll of given newsletter source.
+pub async fn load_last_poll(
+    conn: &Connection,
+    source: &str,
+) -> Result<Option<DateTime<Utc>>, String> {
+    let mut rows = conn
+        .query(
+            "SELECT
+                last_poll_at
+            FROM
+                newsletter_cursors
+            WHERE
+                source = ?",
+            params![source],
+        )
+        .await
+        .map_err(|e| format!("DB query failed for load_last_poll [{}]: {}", source, e))?;
+
+    let Some(row) = rows
+        .next()
+        .await
+        .map_err(|e| format!("Failed to get next row: {}", e))?
+    else {
+        return Ok(None);
+    };
+
+    match row.get_value(0) {
+        Ok(libsql::Value::Text(last_poll_at)) => last_poll_at
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("Invalid last_poll_at for source {}: {}", source, e)),
+        Ok(_) => Err(format!(
+            "Unexpected data type for last_poll_at in DB row for source: {}",
+            source
+        )),
+        Err(e) => Err(format!(
+            "Failed to get value from row for source {}: {}",
+            source, e
+        )),
+    }
+}
+
+pub async fn save_last_poll(
+    conn: &Connection,
+    source: &str,
+    last_poll_at: DateTime<Utc>,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO
+             newsletter_cursors (source, last_poll_at)
+             VALUES
+                (?, ?)
+             ON CONFLICT (source) DO UPDATE SET last_poll_at = excluded.last_poll_at",
+        params![source, last_poll_at.to_rfc3339()],
+    )
+    .await
+    .map_err(|e| format!("DB execute failed for save_last_poll [{}]: {}", source, e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libsql::Builder;
+
+    async fn memory_db_client() -> Connection {
+        let db = Builder::new_local(":memory:").build().await.unwrap();
+        db.connect().unwrap()
+    }
+
+    #[tokio::test]
+    async fn ensure_schema_exists_is_idempotent() {
+        let conn = memory_db_client().await;
+        ensure_schema_exists(&conn).await.unwrap();
+        ensure_schema_exists(&conn).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn save_and_load_published_item_ids() {
+        let conn = memory_db_client().await;
+        ensure_schema_exists(&conn).await.unwrap();
+
+        assert!(load_published_item_ids(&conn, "poe1")
+            .await
+            .unwrap()
+            .is_empty());
+
+        let url = "https://www.pathofexile.com/forum/view-thread/1";
+        save_published_item_id(&conn, "poe1", url).await.unwrap();
+        // Saving twice must not fail or duplicate.
+        save_published_item_id(&conn, "poe1", url).await.unwrap();
+        save_published_item_id(&conn, "diablo", "42").await.unwrap();
+
+        assert_eq!(
+            load_published_item_ids(&conn, "poe1").await.unwrap(),
+            HashSet::from([url.to_owned()])
+        );
+        assert_eq!(
+            load_published_item_ids(&conn, "diablo").await.unwrap(),
+            HashSet::from(["42".to_owned()])
+        );
+        assert!(load_published_item_ids(&conn, "poe2")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn save_and_load_last_poll() {
+        let conn = memory_db_client().await;
+        ensure_schema_exists(&conn).await.unwrap();
+
+        assert_eq!(load_last_poll(&conn, "epoch").await.unwrap(), None);
+
+        let first: DateTime<Utc> = "2025-04-17T16:01:00Z".parse().unwrap();
+        let second: DateTime<Utc> = "2025-04-17T16:11:00Z".parse().unwrap();
+
+        save_last_poll(&conn, "epoch", first).await.unwrap();
+        assert_eq!(load_last_poll(&conn, "epoch").await.unwrap(), Some(first));
+
+        save_last_poll(&conn, "epoch", second).await.unwrap();
+        assert_eq!(load_last_poll(&conn, "epoch").await.unwrap(), Some(second));
+        assert_eq!(load_last_poll(&conn, "diablo").await.unwrap(), None);
+    }
+}
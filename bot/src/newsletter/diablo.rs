@@ -1,11 +1,12 @@
 use crate::{
-    newsletter::{NewsItem, Newsletter},
+    newsletter::{NewsItem, Newsletter, Posted},
     SerenityContext,
 };
 use chrono::{DateTime, Utc};
 use diablo::{DiabloPost, PostKind};
 use poise::serenity_prelude::{
-    CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage, Timestamp,
+    self as serenity, ChannelId, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage,
+    Timestamp,
 };
 
 pub struct DiabloNewsletter;
@@ -14,6 +15,10 @@ impl Newsletter for DiabloNewsletter {
     type Item = DiabloPost;
     type Error = diablo::Error;
 
+    fn source(&self) -> &str {
+        "diablo"
+    }
+
     async fn fetch_impl(&self) -> Result<Vec<Self::Item>, Self::Error> {
         let posts = diablo::fetch_posts().await?;
         Ok(posts
@@ -24,12 +29,29 @@ impl Newsletter for DiabloNewsletter {
 }
 
 impl NewsItem for DiabloPost {
-    async fn post_to_discord<C>(&self, ctx: &SerenityContext, channel: C)
+    async fn post_to_discord<C>(
+        &self,
+        ctx: &SerenityContext,
+        channel: C,
+    ) -> Result<Posted, serenity::Error>
     where
-        C: Into<poise::serenity_prelude::ChannelId>,
+        C: Into<ChannelId>,
     {
         let message = CreateMessage::new().embed(create_summary_embed(self));
-        let _ = channel.into().send_message(ctx, message).await;
+        channel.into().send_message(ctx, message).await?;
+        Ok(Posted {
+            thread: None,
+            body: Some(self.description.clone()),
+            ..Default::default()
+        })
+    }
+
+    fn id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
     }
 
     fn timestamp(&self) -> DateTime<Utc> {
@@ -39,6 +61,14 @@ impl NewsItem for DiabloPost {
     fn title(&self) -> String {
         self.title.clone()
     }
+
+    async fn content(&self) -> Option<String> {
+        Some(self.description.clone())
+    }
+
+    fn language_key(&self) -> Option<&'static str> {
+        Some("en")
+    }
 }
 
 pub fn create_summary_embed(post: &DiabloPost) -> CreateEmbed {
@@ -0,0 +1,500 @@
+//! Tracking of edits to already published threads.
+//!
+//! Content of a freshly published thread is remembered together with the Discord threads
+//! created for it. For [`interval::edit_watch_period`] the content is re-fetched and
+//! every change is posted as a line diff into those Discord threads.
+use crate::{interval, Data, SerenityContext, EMBED_DESCRIPTION_MAX_CHARS};
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude::{ChannelId, CreateEmbed, CreateMessage};
+use similar::{ChangeTag, TextDiff};
+use std::future::Future;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub source: String,
+    pub item_id: String,
+    pub content_hash: String,
+    pub content: String,
+    /// Publication time of the item, snapshots are watched for [`interval::edit_watch_period`] since then.
+    pub posted_at: DateTime<Utc>,
+    /// Discord threads with details of the item.
+    pub threads: Vec<ChannelId>,
+}
+
+impl Snapshot {
+    pub fn new(
+        source: &str,
+        item_id: &str,
+        content: String,
+        posted_at: DateTime<Utc>,
+        threads: Vec<ChannelId>,
+    ) -> Self {
+        Self {
+            source: source.to_owned(),
+            item_id: item_id.to_owned(),
+            content_hash: content_hash(&content),
+            content,
+            posted_at,
+            threads,
+        }
+    }
+}
+
+/// Stable 64-bit FNV-1a hash of the content, as hex.
+pub fn content_hash(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// Added and removed lines, prefixed with `+` and `-`. None if no line has changed.
+pub fn line_diff(old: &str, new: &str) -> Option<String> {
+    let diff = TextDiff::from_lines(old, new);
+    let lines = diff
+        .iter_all_changes()
+        .filter_map(|change| {
+            let sign = match change.tag() {
+                ChangeTag::Delete => '-',
+                ChangeTag::Insert => '+',
+                ChangeTag::Equal => return None,
+            };
+            let line = change.value().trim_end_matches(['\r', '\n']);
+            match line.trim().is_empty() {
+                true => None,
+                false => Some(format!("{sign} {line}")),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    match lines.is_empty() {
+        true => None,
+        false => Some(lines.join("\n")),
+    }
+}
+
+/// Splits the diff into embeds with `diff` code blocks.
+pub fn create_diff_messages(diff: &str) -> Vec<CreateMessage> {
+    const FENCE_OPEN: &str = "```diff\n";
+    const FENCE_CLOSE: &str = "\n```";
+    let max_chars = EMBED_DESCRIPTION_MAX_CHARS - FENCE_OPEN.len() - FENCE_CLOSE.len();
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for line in diff.lines() {
+        let line: String = line.chars().take(max_chars).collect();
+        if !current.is_empty() && current.chars().count() + 1 + line.chars().count() > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&line);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut embed =
+                CreateEmbed::new().description(format!("{FENCE_OPEN}{chunk}{FENCE_CLOSE}"));
+            if index == 0 {
+                embed = embed.title("✏️ The post was edited");
+            }
+            CreateMessage::new().embed(embed)
+        })
+        .collect()
+}
+
+/// Remembers content of the published item to watch it for edits.
+pub async fn watch(data: &Data, snapshot: &Snapshot) {
+    let result = match data.db.connect() {
+        Ok(conn) => db_layer::save_snapshot(&conn, snapshot).await,
+        Err(e) => Err(format!("DB connection error: {e}")),
+    };
+    if let Err(err) = result {
+        eprintln!(
+            "Failed to save snapshot of {} for {}: {err}",
+            snapshot.item_id, snapshot.source
+        );
+    }
+}
+
+/// Re-fetches content of every watched item of the source and posts diffs of edited ones.
+/// Items older than [`interval::edit_watch_period`] are forgotten.
+pub async fn recheck<F, Fut>(ctx: &SerenityContext, data: &Data, source: &str, fetch_content: F)
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Option<String>>,
+{
+    let conn = match data.db.connect() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("DB connection error in edits::recheck [{source}]: {e}");
+            return;
+        }
+    };
+
+    let watch_since = Utc::now() - interval::edit_watch_period();
+    if let Err(err) = db_layer::delete_snapshots_before(&conn, source, watch_since).await {
+        eprintln!("Failed to forget old snapshots for {source}: {err}");
+    }
+
+    let snapshots = match db_layer::load_snapshots(&conn, source).await {
+        Ok(snapshots) => snapshots,
+        Err(err) => {
+            eprintln!("Failed to load snapshots for {source}: {err}");
+            return;
+        }
+    };
+
+    for snapshot in snapshots {
+        let Some(content) = fetch_content(snapshot.item_id.clone()).await else {
+            continue;
+        };
+
+        let content_hash = content_hash(&content);
+        if content_hash == snapshot.content_hash {
+            continue;
+        }
+
+        if let Some(diff) = line_diff(&snapshot.content, &content) {
+            for thread in &snapshot.threads {
+                for message in create_diff_messages(&diff) {
+                    if let Err(err) = thread.send_message(ctx, message).await {
+                        eprintln!(
+                            "Failed to post diff of {} to thread {thread}: {err}",
+                            snapshot.item_id
+                        );
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Err(err) =
+            db_layer::update_content(&conn, source, &snapshot.item_id, &content_hash, &content)
+                .await
+        {
+            eprintln!(
+                "Failed to update snapshot of {} for {source}: {err}",
+                snapshot.item_id
+            );
+        }
+    }
+}
+
+pub mod db_layer {
+    use super::Snapshot;
+    use chrono::{DateTime, Utc};
+    use libsql::{params, Connection, Error as LibsqlError};
+    use poise::serenity_prelude::ChannelId;
+
+    pub const CREATE_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS item_snapshots (
+        source TEXT NOT NULL,
+        item_id TEXT NOT NULL,
+        content_hash TEXT NOT NULL,
+        content TEXT NOT NULL,
+        posted_at TEXT NOT NULL,
+        PRIMARY KEY (source, item_id)
+    ) STRICT;"#;
+
+    pub const CREATE_THREADS_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS item_snapshot_threads (
+        source TEXT NOT NULL,
+        item_id TEXT NOT NULL,
+        thread_id INTEGER NOT NULL,
+        PRIMARY KEY (source, item_id, thread_id)
+    ) STRICT;"#;
+
+    pub async fn ensure_schema_exists(conn: &Connection) -> Result<(), LibsqlError> {
+        conn.execute(CREATE_IF_NOT_EXISTS, ()).await?;
+        conn.execute(CREATE_THREADS_IF_NOT_EXISTS, ()).await?;
+        Ok(())
+    }
+
+    /// Saves the snapshot, replacing its content and adding new threads if it already exists.
+    pub async fn save_snapshot(conn: &Connection, snapshot: &Snapshot) -> Result<(), String> {
+        conn.execute(
+            "INSERT INTO
+                 item_snapshots (source, item_id, content_hash, content, posted_at)
+                 VALUES
+                    (?, ?, ?, ?, ?)
+                 ON CONFLICT (source, item_id) DO UPDATE SET
+                    content_hash = excluded.content_hash,
+                    content = excluded.content",
+            params![
+                snapshot.source.as_str(),
+                snapshot.item_id.as_str(),
+                snapshot.content_hash.as_str(),
+                snapshot.content.as_str(),
+                snapshot.posted_at.to_rfc3339()
+            ],
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "DB execute failed for save_snapshot [{} - {}]: {}",
+                snapshot.source, snapshot.item_id, e
+            )
+        })?;
+
+        for thread in &snapshot.threads {
+            conn.execute(
+                "INSERT OR IGNORE INTO
+                     item_snapshot_threads (source, item_id, thread_id)
+                     VALUES
+                        (?, ?, ?)",
+                params![
+                    snapshot.source.as_str(),
+                    snapshot.item_id.as_str(),
+                    thread.get() as i64
+                ],
+            )
+            .await
+            .map_err(|e| {
+                format!(
+                    "DB execute failed for save_snapshot thread [{} - {}]: {}",
+                    snapshot.source, snapshot.item_id, e
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads all snapshots of given newsletter source with their threads.
+    pub async fn load_snapshots(conn: &Connection, source: &str) -> Result<Vec<Snapshot>, String> {
+        let mut rows = conn
+            .query(
+                "SELECT
+                    s.item_id, s.content_hash, s.content, s.posted_at, t.thread_id
+                FROM
+                    item_snapshots s
+                    LEFT JOIN item_snapshot_threads t
+                        ON t.source = s.source AND t.item_id = s.item_id
+                WHERE
+                    s.source = ?
+                ORDER BY
+                    s.posted_at, s.item_id",
+                params![source],
+            )
+            .await
+            .map_err(|e| format!("DB query failed for load_snapshots [{}]: {}", source, e))?;
+
+        let mut snapshots: Vec<Snapshot> = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| format!("Failed to get next row: {}", e))?
+        {
+            let item_id: String = row
+                .get(0)
+                .map_err(|e| format!("Failed to get item_id for source {}: {}", source, e))?;
+            let thread_id: Option<i64> = row
+                .get(4)
+                .map_err(|e| format!("Failed to get thread_id for source {}: {}", source, e))?;
+
+            if snapshots.last().is_none_or(|last| last.item_id != item_id) {
+                let posted_at: String = row
+                    .get(3)
+                    .map_err(|e| format!("Failed to get posted_at for source {}: {}", source, e))?;
+                snapshots.push(Snapshot {
+                    source: source.to_owned(),
+                    item_id,
+                    content_hash: row.get(1).map_err(|e| {
+                        format!("Failed to get content_hash for source {}: {}", source, e)
+                    })?,
+                    content: row.get(2).map_err(|e| {
+                        format!("Failed to get content for source {}: {}", source, e)
+                    })?,
+                    posted_at: posted_at
+                        .parse::<DateTime<Utc>>()
+                        .map_err(|e| format!("Invalid posted_at for source {}: {}", source, e))?,
+                    threads: Vec::new(),
+                });
+            }
+
+            if let (Some(snapshot), Some(thread_id)) = (snapshots.last_mut(), thread_id) {
+                snapshot.threads.push(ChannelId::new(thread_id as u64));
+            }
+        }
+
+        Ok(snapshots)
+    }
+
+    pub async fn update_content(
+        conn: &Connection,
+        source: &str,
+        item_id: &str,
+        content_hash: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        conn.execute(
+            "UPDATE
+                 item_snapshots
+             SET
+                 content_hash = ?, content = ?
+             WHERE
+                 source = ? AND item_id = ?",
+            params![content_hash, content, source, item_id],
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "DB execute failed for update_content [{} - {}]: {}",
+                source, item_id, e
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Forgets snapshots of items posted before given time.
+    pub async fn delete_snapshots_before(
+        conn: &Connection,
+        source: &str,
+        before: DateTime<Utc>,
+    ) -> Result<(), String> {
+        let before = before.to_rfc3339();
+        conn.execute(
+            "DELETE FROM
+                 item_snapshot_threads
+             WHERE
+                 source = ?1 AND item_id IN (
+                     SELECT item_id FROM item_snapshots WHERE source = ?1 AND posted_at < ?2
+                 )",
+            params![source, before.as_str()],
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "DB execute failed for delete_snapshots_before [{}]: {}",
+                source, e
+            )
+        })?;
+
+        conn.execute(
+            "DELETE FROM
+                 item_snapshots
+             WHERE
+                 source = ? AND posted_at < ?",
+            params![source, before.as_str()],
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "DB execute failed for delete_snapshots_before [{}]: {}",
+                source, e
+            )
+        })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_hash_is_stable() {
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_eq!(content_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(
+            content_hash("- Fixed a bug"),
+            content_hash("- Fixed a bug.")
+        );
+    }
+
+    #[test]
+    fn diffs_lines() {
+        let old = "# 3.25.1b\n\n- Fixed a crash.\n- Fixed a bug with Flameblast.\n";
+        let new = "# 3.25.1b\n\n- Fixed a crash.\n- Fixed a bug with Flameblast and Incinerate.\n- Fixed a rare crash on login.\n";
+
+        assert_eq!(
+            line_diff(old, new).unwrap(),
+            "- - Fixed a bug with Flameblast.\n+ - Fixed a bug with Flameblast and Incinerate.\n+ - Fixed a rare crash on login."
+        );
+        assert_eq!(line_diff(old, old), None);
+        // Blank lines alone are not worth a message.
+        assert_eq!(line_diff(old, &format!("{old}\n\n")), None);
+    }
+
+    #[test]
+    fn splits_long_diff_into_code_blocks() {
+        let line = format!("+ {}", "a".repeat(98));
+        let diff = vec![line.as_str(); 100].join("\n");
+
+        let messages = create_diff_messages(&diff);
+        assert_eq!(messages.len(), 3);
+    }
+}
+
+#[cfg(test)]
+mod db_layer_tests {
+    use super::{db_layer::*, Snapshot};
+    use chrono::{DateTime, Utc};
+    use libsql::{Builder, Connection};
+    use poise::serenity_prelude::ChannelId;
+
+    async fn memory_db_client() -> Connection {
+        let db = Builder::new_local(":memory:").build().await.unwrap();
+        db.connect().unwrap()
+    }
+
+    fn date(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn save_update_and_forget_snapshots() {
+        let conn = memory_db_client().await;
+        ensure_schema_exists(&conn).await.unwrap();
+        ensure_schema_exists(&conn).await.unwrap();
+
+        let url = "https://www.pathofexile.com/forum/view-thread/3740562";
+        let snapshot = Snapshot::new(
+            "poe1",
+            url,
+            "- Fixed a crash.".to_owned(),
+            date("2025-04-17T16:01:00Z"),
+            vec![ChannelId::new(1), ChannelId::new(2)],
+        );
+        save_snapshot(&conn, &snapshot).await.unwrap();
+        // Saving again must not duplicate threads.
+        save_snapshot(&conn, &snapshot).await.unwrap();
+
+        let other = Snapshot::new(
+            "poe1",
+            "https://www.pathofexile.com/forum/view-thread/3740999",
+            "News".to_owned(),
+            date("2025-04-19T10:00:00Z"),
+            vec![ChannelId::new(3)],
+        );
+        save_snapshot(&conn, &other).await.unwrap();
+
+        assert_eq!(
+            load_snapshots(&conn, "poe1").await.unwrap(),
+            vec![snapshot.clone(), other.clone()]
+        );
+        assert!(load_snapshots(&conn, "poe2").await.unwrap().is_empty());
+
+        update_content(&conn, "poe1", url, "hash", "- Fixed a crash.\n- New line.")
+            .await
+            .unwrap();
+        let updated = &load_snapshots(&conn, "poe1").await.unwrap()[0];
+        assert_eq!(updated.content_hash, "hash");
+        assert_eq!(updated.content, "- Fixed a crash.\n- New line.");
+        assert_eq!(updated.threads, snapshot.threads);
+
+        delete_snapshots_before(&conn, "poe1", date("2025-04-18T00:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(load_snapshots(&conn, "poe1").await.unwrap(), vec![other]);
+    }
+}
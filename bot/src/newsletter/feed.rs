@@ -0,0 +1,136 @@
+use crate::{
+    newsletter::{NewsItem, Newsletter, Posted},
+    SerenityContext, EMBED_DESCRIPTION_CUSTOM_MAX_CHARS,
+};
+use chrono::{DateTime, Utc};
+use feed::FeedItem;
+use poise::serenity_prelude::{
+    self as serenity, ChannelId, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage,
+    Timestamp,
+};
+
+/// Newsletter of any RSS 2.0 or Atom feed.
+pub struct FeedNewsletter {
+    /// Used as [`Newsletter::source`], e.g. `le-devblog`.
+    pub name: String,
+    pub url: String,
+}
+
+impl FeedNewsletter {
+    pub fn new(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            url: url.into(),
+        }
+    }
+
+    /// Reads feeds from `FEEDS` env: whitespace separated `name=url` pairs.
+    pub fn from_env() -> Vec<Self> {
+        std::env::var("FEEDS")
+            .map(|feeds| Self::parse_list(&feeds))
+            .unwrap_or_default()
+    }
+
+    pub fn parse_list(s: &str) -> Vec<Self> {
+        s.split_whitespace()
+            .filter_map(|pair| match pair.split_once('=') {
+                Some((name, url)) if !name.is_empty() && !url.is_empty() => {
+                    Some(Self::new(name, url))
+                }
+                _ => {
+                    eprintln!("Invalid FEEDS entry, expected name=url: {pair}");
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl Newsletter for FeedNewsletter {
+    type Item = FeedItem;
+    type Error = feed::Error;
+
+    fn source(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch_impl(&self) -> Result<Vec<Self::Item>, Self::Error> {
+        feed::fetch_feed(&self.url).await
+    }
+}
+
+impl NewsItem for FeedItem {
+    async fn post_to_discord<C>(
+        &self,
+        ctx: &SerenityContext,
+        channel: C,
+    ) -> Result<Posted, serenity::Error>
+    where
+        C: Into<ChannelId>,
+    {
+        let message = CreateMessage::new().embed(create_summary_embed(self));
+        channel.into().send_message(ctx, message).await?;
+        Ok(Posted {
+            thread: None,
+            body: self.summary.clone(),
+            ..Default::default()
+        })
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.published
+    }
+
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    async fn content(&self) -> Option<String> {
+        self.summary.clone()
+    }
+}
+
+pub fn create_summary_embed(item: &FeedItem) -> CreateEmbed {
+    let mut embed = CreateEmbed::new().title(&item.title).url(&item.url).field(
+        "Posted date",
+        format!("<t:{}>", item.published.timestamp()),
+        true,
+    );
+
+    if let Some(author) = &item.author {
+        embed = embed.author(CreateEmbedAuthor::new(author));
+    }
+
+    if let Some(summary) = &item.summary {
+        embed = embed.description(truncate(summary, EMBED_DESCRIPTION_CUSTOM_MAX_CHARS));
+    }
+
+    if let Some(image_url) = &item.image_url {
+        embed = embed.image(image_url);
+    }
+
+    if let Some(feed_title) = &item.feed_title {
+        embed = embed.footer(CreateEmbedFooter::new(format!("{feed_title} 📡")));
+    }
+
+    if let Ok(timestamp) = Timestamp::from_millis(item.published.timestamp_millis()) {
+        embed = embed.timestamp(timestamp);
+    }
+
+    embed
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    match s.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", &s[..index]),
+        None => s.to_owned(),
+    }
+}
@@ -1,45 +1,100 @@
 use crate::{
     message::MessageWithThreadedDetails,
-    newsletter::{NewsItem, Newsletter},
+    newsletter::{fetch_subforums, merge_subforum_results, NewsItem, Newsletter, Posted},
     Error, PoiseContext, SerenityContext,
 };
+use chrono::{DateTime, TimeDelta, Utc};
 use last_epoch_forum::NewsThreadInfo;
 pub use last_epoch_forum::Subforum;
 use poise::serenity_prelude::{
-    CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage, Timestamp,
+    self as serenity, ChannelId, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage,
+    Timestamp,
 };
+use std::{collections::HashSet, sync::Mutex};
 use unicode_segmentation::UnicodeSegmentation;
 
+/// Pinned threads older than this stay at the top of the list long after they were
+/// news, e.g. the about topic of the category, and are skipped.
+const STALE_PINNED_DAYS: i64 = 7;
+
 pub struct LastEpochNewsletter {
     pub subforums: Vec<Subforum>,
+    /// Whether every subforum was fetched by the last fetch.
+    complete: Mutex<bool>,
 }
 
 impl LastEpochNewsletter {
     pub fn new(subforums: Vec<Subforum>) -> Self {
-        Self { subforums }
+        Self {
+            subforums,
+            complete: Mutex::default(),
+        }
     }
 }
 
 impl Newsletter for LastEpochNewsletter {
     type Item = NewsThreadInfo;
-    type Error = reqwest::Error;
+    type Error = last_epoch_forum::Error;
+
+    fn source(&self) -> &str {
+        "epoch"
+    }
 
     async fn fetch_impl(&self) -> Result<Vec<Self::Item>, Self::Error> {
-        let mut all = Vec::new();
-        for subforum in &self.subforums {
-            let items = last_epoch_forum::fetch_subforum_threads_list(*subforum).await?;
-            all.extend(items);
-        }
-        Ok(all)
+        let results = fetch_subforums(
+            &self.subforums,
+            last_epoch_forum::fetch_subforum_threads_list,
+        )
+        .await;
+        *self.complete.lock().unwrap() = results.iter().all(|(_, result)| result.is_ok());
+        merge_subforum_results(self.source(), results)
+    }
+
+    fn fetch_complete(&self) -> bool {
+        *self.complete.lock().unwrap()
+    }
+
+    /// Skips stale pinned threads.
+    fn prepare(
+        &self,
+        items: Vec<Self::Item>,
+        _published: &HashSet<String>,
+        now: DateTime<Utc>,
+    ) -> Vec<Self::Item> {
+        items
+            .into_iter()
+            .filter(|thread| !is_stale_pinned(thread, now))
+            .collect()
     }
 }
 
+fn is_stale_pinned(thread: &NewsThreadInfo, now: DateTime<Utc>) -> bool {
+    thread.is_pinned && now - thread.datetime > TimeDelta::days(STALE_PINNED_DAYS)
+}
+
 impl NewsItem for NewsThreadInfo {
-    async fn post_to_discord<C>(&self, ctx: &SerenityContext, channel: C)
+    async fn post_to_discord<C>(
+        &self,
+        ctx: &SerenityContext,
+        channel: C,
+    ) -> Result<Posted, serenity::Error>
     where
-        C: Into<poise::serenity_prelude::ChannelId>,
+        C: Into<ChannelId>,
     {
-        create_message(self).send(ctx, channel.into()).await
+        let thread = create_message(self).send(ctx, channel.into()).await?;
+        Ok(Posted {
+            thread,
+            body: self.content.clone(),
+            ..Default::default()
+        })
+    }
+
+    fn id(&self) -> String {
+        self.url.clone()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
     }
 
     fn timestamp(&self) -> chrono::DateTime<chrono::Utc> {
@@ -49,6 +104,22 @@ impl NewsItem for NewsThreadInfo {
     fn title(&self) -> String {
         self.title.clone()
     }
+
+    async fn content(&self) -> Option<String> {
+        self.content.clone()
+    }
+
+    fn subforum_key(&self) -> Option<&'static str> {
+        Some(match self.subforum {
+            Subforum::News => "news",
+            Subforum::DeveloperBlogs => "developer-blogs",
+            Subforum::PatchNotes => "patch-notes",
+        })
+    }
+
+    fn language_key(&self) -> Option<&'static str> {
+        Some("en")
+    }
 }
 
 pub fn create_message(thread: &NewsThreadInfo) -> MessageWithThreadedDetails {
@@ -56,6 +127,7 @@ pub fn create_message(thread: &NewsThreadInfo) -> MessageWithThreadedDetails {
         message: CreateMessage::new().embed(create_summary_embed(thread)),
         thread_name: thread.title.clone(),
         details_content: thread.content.clone(),
+        details_images: Vec::new(),
     }
 }
 
@@ -102,21 +174,29 @@ pub async fn epoch_thread(
     #[description = "Select a subforum"] subforum: SubforumSlash,
     #[description = "Nth thread to fetch"]
     #[min = 1]
-    #[max = 3]
+    #[max = 8]
     nth: usize,
 ) -> Result<(), Error> {
     ctx.defer_ephemeral().await?;
-    match last_epoch_forum::fetch_subforum_threads_list(subforum.into()).await {
-        Ok(threads) => {
-            if let Some(thread) = threads.into_iter().nth(nth - 1) {
+    let options = last_epoch_forum::FetchOptions {
+        depth: nth,
+        ..Default::default()
+    };
+    match last_epoch_forum::fetch_threads(subforum.into(), options).await {
+        Ok(threads) => match threads.into_iter().nth(nth - 1) {
+            Some(Ok(thread)) => {
                 create_message(&thread)
                     .send(ctx.serenity_context(), ctx.channel_id())
-                    .await;
+                    .await?;
                 ctx.say("Done !").await?;
-            } else {
+            }
+            Some(Err(err)) => {
+                ctx.say(format!("Failed to fetch thread {err}")).await?;
+            }
+            None => {
                 ctx.say("Not found").await?;
             }
-        }
+        },
         Err(err) => {
             ctx.say(format!("Failed to fetch threads {err}")).await?;
         }
@@ -144,3 +224,59 @@ impl From<SubforumSlash> for Subforum {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{is_stale_pinned, LastEpochNewsletter};
+    use crate::newsletter::Newsletter;
+    use chrono::{DateTime, Utc};
+    use last_epoch_forum::{NewsThreadInfo, Subforum};
+    use std::collections::HashSet;
+
+    fn thread(datetime: &str, is_pinned: bool) -> NewsThreadInfo {
+        NewsThreadInfo {
+            url: "https://forum.lastepoch.com/t/thread/1".to_owned(),
+            title: "Thread".to_owned(),
+            datetime: datetime.parse().unwrap(),
+            content: None,
+            author: None,
+            subforum: Subforum::News,
+            is_pinned,
+        }
+    }
+
+    #[test]
+    fn skips_only_old_pinned_threads() {
+        let now: DateTime<Utc> = "2025-05-20T12:00:00Z".parse().unwrap();
+
+        assert!(is_stale_pinned(&thread("2022-03-07T10:00:00Z", true), now));
+        assert!(!is_stale_pinned(&thread("2025-05-18T10:00:00Z", true), now));
+        assert!(!is_stale_pinned(
+            &thread("2022-03-07T10:00:00Z", false),
+            now
+        ));
+    }
+
+    #[test]
+    fn skips_stale_pinned_threads_of_category_list() {
+        let json = std::fs::read_to_string("tests/fixtures/last_epoch_category.json").unwrap();
+        let category = discourse::parse_category(&json).unwrap();
+        let client = last_epoch_forum::client();
+        let threads: Vec<NewsThreadInfo> = category
+            .topics()
+            .iter()
+            .map(|topic| {
+                last_epoch_forum::list_thread_info(&client, &category, topic, Subforum::News)
+            })
+            .collect();
+        let now: DateTime<Utc> = "2025-04-18T12:00:00Z".parse().unwrap();
+
+        let newsletter = LastEpochNewsletter::new(vec![Subforum::News]);
+        let prepared = newsletter.prepare(threads.clone(), &HashSet::new(), now);
+
+        assert_eq!(prepared.len(), threads.len() - 1);
+        assert!(prepared.iter().all(|thread| !thread.is_pinned));
+        assert!(!prepared.contains(&threads[0]));
+        assert_eq!(threads[0].title, "About the News category");
+    }
+}
@@ -1,6 +1,12 @@
+pub mod db_layer;
 pub mod diablo;
+pub mod edits;
+pub mod feed;
 pub mod last_epoch;
 pub mod poe;
+pub mod translations;
 mod utils;
 
-pub use utils::{NewsItem, Newsletter};
+pub use utils::{
+    fetch_subforums, merge_subforum_results, save_published_ids, NewsItem, Newsletter, Posted,
+};
@@ -1,97 +1,459 @@
 use crate::{
-    message::MessageWithThreadedDetails,
-    newsletter::{NewsItem, Newsletter},
-    time::Timezone,
-    SerenityContext,
+    health, interval,
+    message::{self, DetailsImage, MessageWithThreadedDetails},
+    newsletter::{
+        edits::{self, Snapshot},
+        fetch_subforums, merge_subforum_results,
+        translations::{self, Announcement},
+        NewsItem, Newsletter, Posted,
+    },
+    Data, SerenityContext,
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use futures::future::join_all;
+use poe_forum::{
+    post::{MediaKind, PostDetails},
+    FailoverSource, NewsThreadInfo, Served, SourceKind, Subforum, ThreadSource, WebsiteLanguage,
 };
-use poe_forum::{post::PostDetails, NewsThreadInfo, Subforum, ThreadSource, WebsiteLanguage};
 use poise::serenity_prelude::{
-    CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage, Timestamp,
+    self as serenity, ChannelId, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage,
+    Timestamp,
+};
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Mutex,
 };
+use tokio::sync::OnceCell;
 use unicode_segmentation::UnicodeSegmentation;
 
 pub struct PoeNewsletter {
+    pub source: &'static str,
     pub subforums: Vec<(WebsiteLanguage, Subforum)>,
-    pub timezone: Timezone,
+    /// Timezone of the forum dates.
+    pub timezone: Tz,
+    /// API tried before scraping the forum, if configured.
+    api: Option<FailoverSource>,
+    /// Layout state of every subforum seen by the last fetch, see [`Newsletter::report_fetch`].
+    layouts: Mutex<BTreeMap<String, Option<String>>>,
+    /// Sources that served the subforums in the last fetch.
+    served: Mutex<Vec<SourceKind>>,
+    /// Whether every subforum was fetched by the last fetch.
+    complete: Mutex<bool>,
 }
 
 impl PoeNewsletter {
-    pub fn new(subforums: Vec<(WebsiteLanguage, Subforum)>, timezone: Timezone) -> Self {
+    /// Scrapes the forum in `timezone`. With `api`, the forum is only scraped
+    /// when the API fails or returns stale threads.
+    pub fn new(
+        source: &'static str,
+        subforums: Vec<(WebsiteLanguage, Subforum)>,
+        timezone: Tz,
+        api: Option<ThreadSource>,
+    ) -> Self {
+        let forum = ThreadSource::Forum { timezone };
         Self {
+            source,
             subforums,
             timezone,
+            api: api.map(|api| FailoverSource::new(api, forum)),
+            layouts: Mutex::default(),
+            served: Mutex::default(),
+            complete: Mutex::default(),
+        }
+    }
+
+    async fn fetch_subforum(
+        &self,
+        lang: WebsiteLanguage,
+        subforum: Subforum,
+    ) -> Result<Served, poe_forum::Error> {
+        match &self.api {
+            Some(api) => api.fetch(lang, subforum).await,
+            None => {
+                let forum = ThreadSource::Forum {
+                    timezone: self.timezone,
+                };
+                let page = poe_forum::fetch_subforum_threads_list(lang, subforum, &forum).await?;
+                Ok(Served {
+                    threads: page.threads,
+                    skipped: page.skipped,
+                    source: forum.kind(),
+                    failover: None,
+                })
+            }
         }
     }
 }
 
+/// Which sources served the subforums, e.g. `api ×3, forum ×1`.
+pub fn served_summary(served: &[SourceKind]) -> Option<String> {
+    let mut counts: Vec<(SourceKind, usize)> = Vec::new();
+    for kind in served {
+        match counts.iter_mut().find(|(counted, _)| counted == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*kind, 1)),
+        }
+    }
+    let summary: Vec<String> = counts
+        .iter()
+        .map(|(kind, count)| format!("{kind} ×{count}"))
+        .collect();
+    (!summary.is_empty()).then(|| summary.join(", "))
+}
+
 impl Newsletter for PoeNewsletter {
-    type Item = NewsThreadInfo;
-    type Error = reqwest::Error;
+    type Item = Announcement;
+    type Error = poe_forum::Error;
+
+    fn source(&self) -> &str {
+        self.source
+    }
 
     async fn fetch_impl(&self) -> Result<Vec<Self::Item>, Self::Error> {
-        let mut all = Vec::new();
-        let source = ThreadSource::Forum {
-            time_offset: self.timezone.offset(),
-        };
-        // TODO: switch to API when ready
-        // let source = if std::env::var("POE_DEV").is_ok() {
-        //     ThreadSource::Api {
-        //         base_url: "http://localhost:3000".into(),
-        //         time_offset: chrono::FixedOffset::east_opt(10800),
-        //     }
-        // } else {
-        //     ThreadSource::Api {
-        //         base_url: "https://poe-patch-notes.shonya3.workers.dev".into(),
-        //         time_offset: None,
-        //     }
-        // };
-        for (lang, subforum) in &self.subforums {
-            let items = poe_forum::fetch_subforum_threads_list(*lang, *subforum, &source).await?;
-            all.extend(items);
+        let results = fetch_subforums(&self.subforums, |(lang, subforum)| {
+            self.fetch_subforum(lang, subforum)
+        })
+        .await;
+
+        let mut served = Vec::new();
+        for ((lang, subforum), result) in &results {
+            if let Ok(result) = result {
+                if let Some(reason) = &result.failover {
+                    eprintln!(
+                        "{}: {lang}/{subforum} served by {} ({reason})",
+                        self.source, result.source
+                    );
+                }
+                served.push(result.source);
+            }
+        }
+        *self.served.lock().unwrap() = served;
+
+        let mut layouts = self.layouts.lock().unwrap();
+        for ((lang, subforum), result) in &results {
+            let key = format!("{} {lang}/{subforum}", self.source);
+            match result {
+                // Skipped rows are reported, the rest of the subforum is still posted.
+                Ok(served) => layouts.insert(
+                    key,
+                    served
+                        .skipped
+                        .as_ref()
+                        .map(|err| format!("row skipped: {err}")),
+                ),
+                Err(err @ poe_forum::Error::Layout(_)) => {
+                    layouts.insert(key, Some(err.to_string()))
+                }
+                // Says nothing about the layout.
+                Err(_) => continue,
+            };
+        }
+        drop(layouts);
+
+        *self.complete.lock().unwrap() = results.iter().all(|(_, result)| result.is_ok());
+        let results: Vec<_> = results
+            .into_iter()
+            .map(|(subforum, result)| (subforum, result.map(|served| served.threads)))
+            .collect();
+
+        let threads = merge_subforum_results(self.source, results)?;
+        Ok(threads.into_iter().map(Announcement::single).collect())
+    }
+
+    /// Pairs unpublished translations. Unpaired threads wait for their translation
+    /// until [`interval::translation_timeout`].
+    fn prepare(
+        &self,
+        items: Vec<Self::Item>,
+        published: &HashSet<String>,
+        now: DateTime<Utc>,
+    ) -> Vec<Self::Item> {
+        let threads = items
+            .into_iter()
+            .flat_map(|announcement| announcement.versions)
+            .filter(|thread| !published.contains(&thread.url))
+            .collect();
+        let timeout = interval::translation_timeout();
+
+        translations::pair(threads)
+            .into_iter()
+            .filter(|announcement| {
+                !translations::awaits_translation(announcement, &self.subforums, now, timeout)
+            })
+            .collect()
+    }
+
+    fn fetch_complete(&self) -> bool {
+        *self.complete.lock().unwrap()
+    }
+
+    /// Alerts about subforums with a changed layout, once per layout error.
+    async fn report_fetch(&self, ctx: &SerenityContext, data: &Data) {
+        let served = served_summary(&self.served.lock().unwrap());
+        if let Some(served) = served {
+            data.health.lock().await.record_served(self.source, served);
+        }
+
+        let layouts = std::mem::take(&mut *self.layouts.lock().unwrap());
+        for (key, error) in layouts {
+            health::record_layout(ctx, data, &key, error).await;
+        }
+    }
+
+    /// Remembers the post content of every version to watch the threads for edits.
+    async fn on_published(&self, data: &Data, item: &Self::Item, posted: &[Posted]) {
+        for version in &item.versions {
+            let posted_versions: Vec<&Posted> = posted
+                .iter()
+                .filter_map(|posted| match version.url == item.id() {
+                    true => Some(posted),
+                    false => posted
+                        .versions
+                        .iter()
+                        .find(|(id, _)| *id == version.url)
+                        .map(|(_, posted)| posted),
+                })
+                .collect();
+
+            let threads: Vec<ChannelId> = posted_versions
+                .iter()
+                .filter_map(|posted| posted.thread)
+                .collect();
+            if threads.is_empty() {
+                continue;
+            }
+
+            if let Some(content) = posted_versions
+                .iter()
+                .find_map(|posted| posted.body.clone())
+            {
+                let snapshot = Snapshot::new(
+                    self.source,
+                    &version.url,
+                    content,
+                    version.posted_date,
+                    threads,
+                );
+                edits::watch(data, &snapshot).await;
+            }
         }
-        Ok(all)
+    }
+
+    /// Posts diffs of edited threads, e.g. with added hotfix lines.
+    async fn recheck_published(&self, ctx: &SerenityContext, data: &Data) {
+        edits::recheck(ctx, data, self.source, |url| async move {
+            fetch_post_details(&url)
+                .await
+                .map(|details| details.content)
+        })
+        .await;
+    }
+}
+
+/// Post details of every version of an [`Announcement`], in the order of its versions.
+/// Fetched once and shared by every channel the announcement is posted to.
+#[derive(Debug, Clone, Default)]
+pub struct PostDetailsCache(OnceCell<Vec<Option<PostDetails>>>);
+
+/// Cached details don't make announcements different.
+impl PartialEq for PostDetailsCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for PostDetailsCache {}
+
+impl Announcement {
+    /// Post details of every version, fetched on first use.
+    pub async fn post_details(&self) -> &[Option<PostDetails>] {
+        self.details
+            .0
+            .get_or_init(|| {
+                join_all(
+                    self.versions
+                        .iter()
+                        .map(|thread| fetch_post_details(&thread.url)),
+                )
+            })
+            .await
     }
 }
 
-impl NewsItem for NewsThreadInfo {
-    async fn post_to_discord<C>(&self, ctx: &SerenityContext, channel: C)
+impl NewsItem for Announcement {
+    /// Posts one summary for all versions, with a details thread for each of them.
+    async fn post_to_discord<C>(
+        &self,
+        ctx: &SerenityContext,
+        channel: C,
+    ) -> Result<Posted, serenity::Error>
     where
-        C: Into<poise::serenity_prelude::ChannelId>,
+        C: Into<ChannelId>,
     {
-        create_message(self).await.send(ctx, channel.into()).await;
+        let channel_id = channel.into();
+        let post_details = self.post_details().await;
+        let primary = post_details.first().and_then(Option::as_ref);
+
+        let message = MessageWithThreadedDetails {
+            message: CreateMessage::new().embed(create_summary_embed(self, post_details)),
+            thread_name: thread_name(self, self.primary()),
+            details_content: primary.map(|post| post.content.clone()),
+            details_images: primary.map(details_images).unwrap_or_default(),
+        };
+        let body = message.details_content.clone();
+        let thread = message.send(ctx, channel_id).await?;
+
+        let mut versions = Vec::new();
+        for (version, details) in self.versions.iter().zip(post_details).skip(1) {
+            let body = details.as_ref().map(|post| post.content.clone());
+            let thread = match (&body, details) {
+                (Some(body), Some(details)) => {
+                    message::create_details_thread(
+                        ctx,
+                        channel_id,
+                        &thread_name(self, version),
+                        body,
+                        &details_images(details),
+                    )
+                    .await
+                }
+                _ => None,
+            };
+            versions.push((
+                version.url.clone(),
+                Posted {
+                    thread,
+                    body,
+                    ..Default::default()
+                },
+            ));
+        }
+
+        Ok(Posted {
+            thread,
+            body,
+            versions,
+        })
+    }
+
+    fn id(&self) -> String {
+        self.primary().url.clone()
+    }
+
+    fn published_ids(&self) -> Vec<String> {
+        self.versions
+            .iter()
+            .map(|thread| thread.url.clone())
+            .collect()
+    }
+
+    fn url(&self) -> String {
+        self.primary().url.clone()
     }
+
     fn timestamp(&self) -> chrono::DateTime<chrono::Utc> {
-        self.posted_date
+        self.versions
+            .iter()
+            .map(|thread| thread.posted_date)
+            .min()
+            .unwrap_or(self.primary().posted_date)
     }
 
     fn title(&self) -> String {
-        self.title.clone()
+        self.primary().title.clone()
+    }
+
+    async fn content(&self) -> Option<String> {
+        self.post_details()
+            .await
+            .first()
+            .and_then(Option::as_ref)
+            .map(|details| details.content.clone())
+    }
+
+    fn fetches_content(&self) -> bool {
+        true
+    }
+
+    fn subforum_key(&self) -> Option<&'static str> {
+        Some(subforum_key(self.primary().subforum))
+    }
+
+    fn language_key(&self) -> Option<&'static str> {
+        Some(language_key(self.primary().lang))
+    }
+
+    fn language_keys(&self) -> Vec<&'static str> {
+        self.versions
+            .iter()
+            .map(|thread| language_key(thread.lang))
+            .collect()
     }
 }
 
-pub async fn create_message(thread: &NewsThreadInfo) -> MessageWithThreadedDetails {
-    let post_details = http::text(&thread.url)
-        .await
-        .ok()
-        .and_then(|html| poe_forum::get_post_details(&html));
+fn subforum_key(subforum: Subforum) -> &'static str {
+    match subforum {
+        Subforum::News
+        | Subforum::EarlyAccessAnnouncementsEn
+        | Subforum::EarlyAccessAnnouncementsRu => "news",
+        Subforum::PatchNotes
+        | Subforum::EarlyAccessPatchNotesEn
+        | Subforum::EarlyAccessPatchNotesRu => "patch-notes",
+    }
+}
 
-    MessageWithThreadedDetails {
-        message: CreateMessage::new().embed(create_summary_embed(thread, post_details.as_ref())),
-        thread_name: thread.title.clone(),
-        details_content: post_details.map(|post| post.content),
+fn language_key(lang: WebsiteLanguage) -> &'static str {
+    lang.code()
+}
+
+/// Images of the post, videos and attachments stay links of the details.
+fn details_images(details: &PostDetails) -> Vec<DetailsImage> {
+    details
+        .media
+        .iter()
+        .filter(|media| media.kind == MediaKind::Image)
+        .map(|media| DetailsImage {
+            url: media.url.clone(),
+            alt: media.alt.clone(),
+            range: media.position..media.position + media.link_len,
+        })
+        .collect()
+}
+
+/// Title of the thread, with language of the version if the announcement has several.
+fn thread_name(announcement: &Announcement, version: &NewsThreadInfo) -> String {
+    match announcement.is_paired() {
+        true => format!("[{}] {}", lang_str(version.lang), version.title),
+        false => version.title.clone(),
     }
 }
 
+pub async fn fetch_post_details(url: &str) -> Option<PostDetails> {
+    http::text(url)
+        .await
+        .ok()
+        .and_then(|html| poe_forum::get_post_details(&html, url))
+}
+
+/// Summary of the primary version, linking every other version.
+/// `post_details` are in the order of [`Announcement::versions`].
 pub fn create_summary_embed(
-    thread: &NewsThreadInfo,
-    post_details: Option<&PostDetails>,
+    announcement: &Announcement,
+    post_details: &[Option<PostDetails>],
 ) -> CreateEmbed {
+    let thread = announcement.primary();
     let site_url = thread
         .url
         .rsplit('/')
         .next()
         .map(|id| format!("https://poe-patch-notes.shonya3.workers.dev/thread/{id}"))
         .unwrap_or_else(|| thread.url.clone());
+    let langs: Vec<WebsiteLanguage> = announcement
+        .versions
+        .iter()
+        .map(|thread| thread.lang)
+        .collect();
 
     let mut embed = CreateEmbed::new()
         .title(&thread.title)
@@ -102,7 +464,7 @@ pub fn create_summary_embed(
             true,
         )
         .footer(CreateEmbedFooter::new(subforum_title(
-            thread.lang,
+            &langs,
             thread.subforum,
         )));
 
@@ -114,22 +476,43 @@ pub fn create_summary_embed(
         embed = embed.timestamp(timestamp);
     }
 
-    if let Some(details) = post_details {
+    if let Some(Some(details)) = post_details.first() {
         embed = embed.field(
             "Words",
             details.content.unicode_words().count().to_string(),
             true,
         );
+    }
 
-        if let Some(image_src) = &details.image_src {
-            embed = embed.image(image_src);
-        }
+    if announcement.is_paired() {
+        let links: Vec<String> = announcement
+            .versions
+            .iter()
+            .map(|thread| {
+                format!(
+                    "**{}** [{}]({})",
+                    lang_str(thread.lang),
+                    thread.title,
+                    thread.url
+                )
+            })
+            .collect();
+        embed = embed.field("Versions", links.join("\n"), false);
+    }
+
+    if let Some(image_src) = post_details
+        .iter()
+        .flatten()
+        .find_map(|details| details.image_src.as_ref())
+    {
+        embed = embed.image(image_src);
     }
 
     embed
 }
 
-pub fn subforum_title(lang: WebsiteLanguage, subforum: Subforum) -> String {
+/// e.g. `PoE News [EN/RU] 📢`.
+pub fn subforum_title(langs: &[WebsiteLanguage], subforum: Subforum) -> String {
     let (subforum_name, emoji) = match subforum {
         Subforum::News => ("PoE News", "📢"),
         Subforum::PatchNotes => ("PoE Patch Notes", "✏️"),
@@ -141,10 +524,11 @@ pub fn subforum_title(lang: WebsiteLanguage, subforum: Subforum) -> String {
         }
     };
 
-    let lang_str = match lang {
-        WebsiteLanguage::En => "EN",
-        WebsiteLanguage::Ru => "RU",
-    };
+    let lang_str: Vec<String> = langs.iter().map(|lang| lang_str(*lang)).collect();
+
+    format!("{} [{}] {}", subforum_name, lang_str.join("/"), emoji)
+}
 
-    format!("{} [{}] {}", subforum_name, lang_str, emoji)
+fn lang_str(lang: WebsiteLanguage) -> String {
+    lang.code().to_uppercase()
 }
@@ -0,0 +1,404 @@
+//! Grouping of language versions of the same PoE announcement.
+//!
+//! Translations are posted to different subforums, usually within minutes of each other,
+//! often by the same author and with the same patch numbers in the title.
+use super::poe::PostDetailsCache;
+use chrono::{DateTime, TimeDelta, Utc};
+use poe_forum::{NewsThreadInfo, Subforum, WebsiteLanguage};
+use regex::Regex;
+use std::{collections::HashSet, sync::LazyLock};
+
+/// Max time between two versions of one announcement.
+pub const MAX_PAIR_GAP_HOURS: i64 = 12;
+/// Min [`match_score`] to consider two threads translations of each other.
+/// Closeness in time alone is not enough.
+pub const MIN_MATCH_SCORE: f64 = 1.5;
+/// Min share of Latin title words two versions have in common, when their titles
+/// share no numbers. Time and author can't pair threads without evidence from the titles.
+pub const MIN_TITLE_SIMILARITY: f64 = 0.3;
+
+/// One announcement in one or several website languages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    /// Language versions, English first.
+    pub versions: Vec<NewsThreadInfo>,
+    pub details: PostDetailsCache,
+}
+
+impl Announcement {
+    pub fn new(versions: Vec<NewsThreadInfo>) -> Self {
+        Self {
+            versions,
+            details: PostDetailsCache::default(),
+        }
+    }
+
+    pub fn single(thread: NewsThreadInfo) -> Self {
+        Self::new(vec![thread])
+    }
+
+    /// Version the announcement is presented with.
+    pub fn primary(&self) -> &NewsThreadInfo {
+        &self.versions[0]
+    }
+
+    pub fn is_paired(&self) -> bool {
+        self.versions.len() > 1
+    }
+}
+
+/// Groups translations of the same announcement. Each thread ends up in exactly one announcement
+/// with at most one version per language. Versions are attached to the English thread first,
+/// best matching pairs are taken first.
+pub fn pair(threads: Vec<NewsThreadInfo>) -> Vec<Announcement> {
+    let mut candidates = Vec::new();
+    for (i, a) in threads.iter().enumerate() {
+        for (j, b) in threads.iter().enumerate().skip(i + 1) {
+            if let Some(score) = match_score(a, b) {
+                let with_en = a.lang == WebsiteLanguage::En || b.lang == WebsiteLanguage::En;
+                candidates.push((with_en, score, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.total_cmp(&a.1)));
+
+    // Index of the group of each thread, every thread starts alone.
+    let mut group_of: Vec<usize> = (0..threads.len()).collect();
+    let mut groups: Vec<Vec<usize>> = (0..threads.len()).map(|i| vec![i]).collect();
+    for (_, _, i, j) in candidates {
+        let (a, b) = (group_of[i], group_of[j]);
+        let languages_overlap = groups[a].iter().any(|&i| {
+            groups[b]
+                .iter()
+                .any(|&j| threads[i].lang == threads[j].lang)
+        });
+        if a == b || languages_overlap {
+            continue;
+        }
+        let moved = std::mem::take(&mut groups[b]);
+        for &k in &moved {
+            group_of[k] = a;
+        }
+        groups[a].extend(moved);
+    }
+
+    let mut threads: Vec<Option<NewsThreadInfo>> = threads.into_iter().map(Some).collect();
+    let mut announcements = Vec::new();
+    for i in 0..threads.len() {
+        let mut group = std::mem::take(&mut groups[group_of[i]]);
+        if group.is_empty() {
+            continue;
+        }
+        group.sort();
+        let mut versions: Vec<NewsThreadInfo> = group
+            .into_iter()
+            .filter_map(|k| threads[k].take())
+            .collect();
+        versions.sort_by_key(|thread| thread.lang != WebsiteLanguage::En);
+        announcements.push(Announcement::new(versions));
+    }
+
+    announcements
+}
+
+/// How likely the two threads are versions of one announcement,
+/// None if they can't be, e.g. same language, different patch numbers
+/// or titles with nothing in common.
+pub fn match_score(a: &NewsThreadInfo, b: &NewsThreadInfo) -> Option<f64> {
+    if a.lang == b.lang || counterpart(a.subforum) != counterpart(b.subforum) {
+        return None;
+    }
+
+    let max_gap = TimeDelta::hours(MAX_PAIR_GAP_HOURS);
+    let gap = (a.posted_date - b.posted_date).abs();
+    if gap > max_gap {
+        return None;
+    }
+    let closeness = 1.0 - gap.num_seconds() as f64 / max_gap.num_seconds() as f64;
+
+    let (a_numbers, b_numbers) = (numbers(&a.title), numbers(&b.title));
+    let numbers = match (a_numbers.is_empty(), b_numbers.is_empty()) {
+        (false, false) if a_numbers.is_disjoint(&b_numbers) => return None,
+        (false, false) => 2.0,
+        _ => 0.0,
+    };
+    let similarity = similarity(&words(&a.title), &words(&b.title));
+    if numbers == 0.0 && similarity < MIN_TITLE_SIMILARITY {
+        return None;
+    }
+
+    let author = match (&a.author, &b.author) {
+        (Some(a), Some(b)) if a.eq_ignore_ascii_case(b) => 1.0,
+        _ => 0.0,
+    };
+
+    let score = closeness + numbers + author + similarity;
+    (score >= MIN_MATCH_SCORE).then_some(score)
+}
+
+/// Whether the unpaired announcement should wait for its translation before being posted.
+pub fn awaits_translation(
+    announcement: &Announcement,
+    translated_subforums: &[(WebsiteLanguage, Subforum)],
+    now: DateTime<Utc>,
+    timeout: TimeDelta,
+) -> bool {
+    let thread = announcement.primary();
+    !announcement.is_paired()
+        && now - thread.posted_date < timeout
+        && translated_subforums.iter().any(|(lang, subforum)| {
+            *lang != thread.lang && counterpart(*subforum) == counterpart(thread.subforum)
+        })
+}
+
+/// Same subforum in every language.
+fn counterpart(subforum: Subforum) -> Subforum {
+    match subforum {
+        Subforum::EarlyAccessPatchNotesRu => Subforum::EarlyAccessPatchNotesEn,
+        Subforum::EarlyAccessAnnouncementsRu => Subforum::EarlyAccessAnnouncementsEn,
+        subforum => subforum,
+    }
+}
+
+/// Versions and other numbers of the title, e.g. `3.26.0b`.
+fn numbers(title: &str) -> HashSet<String> {
+    static NUMBER: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\d+(?:\.\d+)*[a-z]?\b").unwrap());
+    NUMBER
+        .find_iter(&title.to_lowercase())
+        .map(|number| number.as_str().to_owned())
+        .collect()
+}
+
+/// Latin words of the title, e.g. names that are not translated.
+fn words(title: &str) -> HashSet<String> {
+    title
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|word| word.len() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    match a.union(b).count() {
+        0 => 0.0,
+        union => a.intersection(b).count() as f64 / union as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(
+        lang: WebsiteLanguage,
+        subforum: Subforum,
+        title: &str,
+        author: &str,
+        posted_date: &str,
+    ) -> NewsThreadInfo {
+        NewsThreadInfo {
+            url: format!("https://{lang}.pathofexile.com/forum/view-thread/{title}"),
+            posted_date: posted_date.parse().unwrap(),
+            title: title.to_owned(),
+            author: Some(author.to_owned()),
+            lang,
+            subforum,
+        }
+    }
+
+    #[test]
+    fn pairs_translations() {
+        let en_patch = thread(
+            WebsiteLanguage::En,
+            Subforum::PatchNotes,
+            "3.26.0b Hotfix",
+            "Community_Team",
+            "2025-04-17T16:00:00Z",
+        );
+        let en_news = thread(
+            WebsiteLanguage::En,
+            Subforum::News,
+            "Announcing Path of Exile: Secrets of the Atlas",
+            "Community_Team",
+            "2025-04-17T16:10:00Z",
+        );
+        let ru_patch = thread(
+            WebsiteLanguage::Ru,
+            Subforum::PatchNotes,
+            "Исправление 3.26.0b",
+            "Belka_GGG",
+            "2025-04-17T16:40:00Z",
+        );
+        let ru_news = thread(
+            WebsiteLanguage::Ru,
+            Subforum::News,
+            "Анонс Path of Exile: Secrets of the Atlas",
+            "Belka_GGG",
+            "2025-04-17T16:45:00Z",
+        );
+        let ru_other_patch = thread(
+            WebsiteLanguage::Ru,
+            Subforum::PatchNotes,
+            "Исправление 3.26.0a",
+            "Belka_GGG",
+            "2025-04-17T15:55:00Z",
+        );
+
+        let announcements = pair(vec![
+            ru_patch.clone(),
+            en_patch.clone(),
+            ru_other_patch.clone(),
+            ru_news.clone(),
+            en_news.clone(),
+        ]);
+
+        assert_eq!(
+            announcements,
+            vec![
+                Announcement::new(vec![en_patch, ru_patch]),
+                Announcement::single(ru_other_patch),
+                Announcement::new(vec![en_news, ru_news]),
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_every_language_of_an_announcement() {
+        let en = thread(
+            WebsiteLanguage::En,
+            Subforum::PatchNotes,
+            "3.26.0b Hotfix",
+            "Community_Team",
+            "2025-04-17T16:00:00Z",
+        );
+        let ru = thread(
+            WebsiteLanguage::Ru,
+            Subforum::PatchNotes,
+            "Исправление 3.26.0b",
+            "Belka_GGG",
+            "2025-04-17T16:40:00Z",
+        );
+        let de = thread(
+            WebsiteLanguage::De,
+            Subforum::PatchNotes,
+            "3.26.0b Hotfix",
+            "Community_Team",
+            "2025-04-17T16:20:00Z",
+        );
+        let other_de = thread(
+            WebsiteLanguage::De,
+            Subforum::PatchNotes,
+            "3.26.0b Hotfix 2",
+            "Community_Team",
+            "2025-04-17T16:25:00Z",
+        );
+
+        assert_eq!(
+            pair(vec![ru.clone(), de.clone(), en.clone(), other_de.clone()]),
+            vec![
+                Announcement::new(vec![en, ru, de]),
+                Announcement::single(other_de),
+            ]
+        );
+    }
+
+    #[test]
+    fn scores_translations() {
+        let en = thread(
+            WebsiteLanguage::En,
+            Subforum::EarlyAccessPatchNotesEn,
+            "0.2.0e Patch Notes",
+            "Community_Team",
+            "2025-04-17T16:00:00Z",
+        );
+        let ru = thread(
+            WebsiteLanguage::Ru,
+            Subforum::EarlyAccessPatchNotesRu,
+            "Список изменений 0.2.0e",
+            "Community_Team",
+            "2025-04-17T16:30:00Z",
+        );
+        assert!(match_score(&en, &ru).unwrap() > 3.5);
+
+        // Different subforum.
+        let mut announcement = ru.clone();
+        announcement.subforum = Subforum::EarlyAccessAnnouncementsRu;
+        assert_eq!(match_score(&en, &announcement), None);
+
+        // Too far apart.
+        let mut late = ru.clone();
+        late.posted_date = en.posted_date + TimeDelta::hours(13);
+        assert_eq!(match_score(&en, &late), None);
+
+        // Only close in time.
+        let unrelated = thread(
+            WebsiteLanguage::Ru,
+            Subforum::EarlyAccessPatchNotesRu,
+            "Стрим разработчиков",
+            "Belka_GGG",
+            "2025-04-17T16:05:00Z",
+        );
+        assert_eq!(match_score(&en, &unrelated), None);
+    }
+
+    #[test]
+    fn does_not_pair_unrelated_threads_of_one_author() {
+        let en = thread(
+            WebsiteLanguage::En,
+            Subforum::News,
+            "Content Update Reveal Livestream",
+            "Community_Team",
+            "2025-04-17T16:00:00Z",
+        );
+        let ru = thread(
+            WebsiteLanguage::Ru,
+            Subforum::News,
+            "Итоги конкурса фан-арта",
+            "Community_Team",
+            "2025-04-17T16:20:00Z",
+        );
+
+        assert_eq!(match_score(&en, &ru), None);
+        assert_eq!(
+            pair(vec![en.clone(), ru.clone()]),
+            vec![Announcement::single(en), Announcement::single(ru)]
+        );
+    }
+
+    #[test]
+    fn waits_for_translation_until_timeout() {
+        let en = thread(
+            WebsiteLanguage::En,
+            Subforum::News,
+            "Content Update",
+            "Community_Team",
+            "2025-04-17T16:00:00Z",
+        );
+        let announcement = Announcement::single(en.clone());
+        let subforums = [
+            (WebsiteLanguage::En, Subforum::News),
+            (WebsiteLanguage::Ru, Subforum::News),
+        ];
+        let timeout = TimeDelta::minutes(60);
+
+        assert!(awaits_translation(
+            &announcement,
+            &subforums,
+            en.posted_date + TimeDelta::minutes(30),
+            timeout
+        ));
+        assert!(!awaits_translation(
+            &announcement,
+            &subforums,
+            en.posted_date + TimeDelta::minutes(60),
+            timeout
+        ));
+        assert!(!awaits_translation(
+            &announcement,
+            &subforums[..1],
+            en.posted_date,
+            timeout
+        ));
+    }
+}
@@ -1,15 +1,24 @@
 use crate::{
+    archive::{self, ArchivedItem},
+    health,
     interval::{self},
-    SerenityContext,
+    newsletter::db_layer,
+    subscriptions,
+    watches::{self, Alert},
+    Data, SerenityContext,
 };
-use chrono::{DateTime, Utc};
-use poise::serenity_prelude::ChannelId;
-use std::error::Error;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::StreamExt;
+use poise::serenity_prelude::{self as serenity, ChannelId};
+use std::{collections::HashSet, error::Error, fmt::Debug, future::Future};
 
 pub trait Newsletter {
     type Item: NewsItem;
     type Error: Error;
 
+    /// Identifies the newsletter in persisted state, e.g. `poe1`.
+    fn source(&self) -> &str;
+
     /// Fetches newsletter data from the source.
     /// Called automatically by [`fetch`](Self::fetch) with retry handling.
     async fn fetch_impl(&self) -> Result<Vec<Self::Item>, Self::Error>;
@@ -62,44 +71,417 @@ pub trait Newsletter {
         let channel_id = channel.into();
 
         for item in items {
-            item.post_to_discord(ctx, channel_id).await;
+            if let Err(err) = item.post_to_discord(ctx, channel_id).await {
+                eprintln!("Failed to post {}: {err}", item.title());
+            }
         }
 
         Ok(())
     }
 
-    async fn start<C>(&self, ctx: &SerenityContext, channel: C)
-    where
-        C: Into<ChannelId>,
-    {
+    /// Polls the newsletter and posts new items to every subscribed channel.
+    async fn start(&self, ctx: &SerenityContext, data: &Data) {
         let name = std::any::type_name::<Self>();
         let mut interval = interval::interval();
-        let channel_id = channel.into();
+        let mut catch_up_since = Some(self.catch_up_since(data).await);
         loop {
             interval.tick().await;
-            match self.fetch().await {
+            let polled_at = Utc::now();
+            let result = self.fetch().await;
+            health::record(ctx, data, self.source(), &result).await;
+            self.report_fetch(ctx, data).await;
+            match result {
                 Ok(items) => {
-                    let items = items.into_iter().filter(|item| item.is_fresh());
-                    for item in items {
-                        item.post_to_discord(ctx, channel_id).await;
+                    let since =
+                        catch_up_since.unwrap_or_else(|| Utc::now() - interval::catch_up_max_age());
+                    self.publish_new(ctx, data, items, since).await;
+                    catch_up_since = None;
+                    self.recheck_published(ctx, data).await;
+                    // Catching up after a restart must not skip what failed now.
+                    match self.fetch_complete() {
+                        true => self.save_last_poll(data, polled_at).await,
+                        false => eprintln!("{name}: partial fetch, last poll is not saved"),
                     }
                 }
                 Err(err) => eprintln!("{name} error: {err:?}"),
             }
         }
     }
+
+    /// Lower bound for items posted by the first poll after startup: everything
+    /// since the last successful poll, but not older than [`interval::catch_up_max_age`].
+    /// If the source was never polled, only fresh items are posted.
+    async fn catch_up_since(&self, data: &Data) -> DateTime<Utc> {
+        let source = self.source();
+        let now = Utc::now();
+        let last_poll = match data.db.connect() {
+            Ok(conn) => db_layer::load_last_poll(&conn, source).await,
+            Err(e) => Err(format!("DB connection error: {e}")),
+        };
+
+        match last_poll {
+            Ok(Some(last_poll)) => {
+                let since = last_poll.max(now - interval::catch_up_max_age());
+                println!("{source}: catching up on news since {since}");
+                since
+            }
+            Ok(None) => now - TimeDelta::minutes(interval::INTERVAL_MINS),
+            Err(err) => {
+                eprintln!("Failed to load last poll for {source}: {err}");
+                now - TimeDelta::minutes(interval::INTERVAL_MINS)
+            }
+        }
+    }
+
+    async fn save_last_poll(&self, data: &Data, polled_at: DateTime<Utc>) {
+        let source = self.source();
+        let result = match data.db.connect() {
+            Ok(conn) => db_layer::save_last_poll(&conn, source, polled_at).await,
+            Err(e) => Err(format!("DB connection error: {e}")),
+        };
+        if let Err(err) = result {
+            eprintln!("Failed to save last poll for {source}: {err}");
+        }
+    }
+
+    /// Posts items that were never published for this [`source`](Self::source)
+    /// to subscribed channels and remembers them.
+    ///
+    /// Items older than `since` are only remembered and archived, so that old threads
+    /// showing up in the list don't flood the channels. Their content is fetched
+    /// concurrently for at most [`MAX_BACKFILL_FETCHES`] of them.
+    async fn publish_new(
+        &self,
+        ctx: &SerenityContext,
+        data: &Data,
+        items: Vec<Self::Item>,
+        since: DateTime<Utc>,
+    ) {
+        let source = self.source();
+        let conn = match data.db.connect() {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("DB connection error in publish_new [{source}]: {e}");
+                return;
+            }
+        };
+
+        let mut published = match db_layer::load_published_item_ids(&conn, source).await {
+            Ok(ids) => ids,
+            Err(err) => {
+                eprintln!("Failed to load published items for {source}: {err}");
+                return;
+            }
+        };
+        let mut items = self.prepare(items, &published, Utc::now());
+
+        let routes = match subscriptions::db_layer::load_routes(&conn, source).await {
+            Ok(routes) => routes,
+            Err(err) => {
+                eprintln!("Failed to load routes for {source}: {err}");
+                return;
+            }
+        };
+        items.sort_by_key(|item| item.timestamp());
+        let mut backfill = Vec::new();
+        for item in items {
+            let id = item.id();
+            let ids = item.published_ids();
+            if ids.iter().all(|id| published.contains(id)) {
+                continue;
+            }
+
+            let posted = match item.timestamp() >= since {
+                true => match subscriptions::post_to_routes(ctx, &routes, &item).await {
+                    Some(posted) => posted,
+                    // Not remembered, so it will be retried in the next cycle,
+                    // e.g. once a channel subscribes.
+                    None => {
+                        eprintln!("{source} item {id} was not posted to any channel");
+                        continue;
+                    }
+                },
+                false => Vec::new(),
+            };
+
+            save_published_ids(&conn, source, &ids).await;
+            published.extend(ids);
+
+            if posted.is_empty() {
+                backfill.push(item);
+                continue;
+            }
+
+            let content = match posted.iter().find_map(|posted| posted.body.clone()) {
+                Some(body) => Some(body),
+                None => item.content().await,
+            };
+            save_archived(&conn, source, &item, content).await;
+
+            let title = item.title();
+            let url = item.url();
+            let alert = Alert {
+                source,
+                title: &title,
+                url: Some(&url),
+                body: posted.iter().find_map(|posted| posted.body.as_deref()),
+            };
+            watches::notify(ctx, data, &alert).await;
+            self.on_published(data, &item, &posted).await;
+        }
+
+        // Newest first. Past the limit, items with fetched content are archived without it.
+        backfill.reverse();
+        let mut fetches = 0;
+        let backfill: Vec<(Self::Item, bool)> = backfill
+            .into_iter()
+            .map(|item| {
+                let fetch = !item.fetches_content() || {
+                    fetches += 1;
+                    fetches <= MAX_BACKFILL_FETCHES
+                };
+                (item, fetch)
+            })
+            .collect();
+        let archived: Vec<(Self::Item, Option<String>)> = futures::stream::iter(backfill)
+            .map(|(item, fetch)| async move {
+                let content = match fetch {
+                    true => item.content().await,
+                    false => None,
+                };
+                (item, content)
+            })
+            .buffered(MAX_CONCURRENT_FETCHES)
+            .collect()
+            .await;
+        for (item, content) in archived {
+            save_archived(&conn, source, &item, content).await;
+        }
+    }
+
+    /// Prepares fetched items for [`publish_new`](Self::publish_new), e.g. merges
+    /// translations or holds some items back until the next poll.
+    /// `published` are ids of already published items.
+    fn prepare(
+        &self,
+        items: Vec<Self::Item>,
+        _published: &HashSet<String>,
+        _now: DateTime<Utc>,
+    ) -> Vec<Self::Item> {
+        items
+    }
+
+    /// Whether the last fetch got every part of the source, e.g. every subforum.
+    /// The last poll is saved only after a complete fetch, see [`catch_up_since`](Self::catch_up_since).
+    fn fetch_complete(&self) -> bool {
+        true
+    }
+
+    /// Reports problems of the last fetch that don't fail it, e.g. a changed
+    /// layout of one subforum. Called after every poll.
+    async fn report_fetch(&self, _ctx: &SerenityContext, _data: &Data) {}
+
+    /// Called after the item was posted to at least one channel.
+    async fn on_published(&self, _data: &Data, _item: &Self::Item, _posted: &[Posted]) {}
+
+    /// Re-checks already published items, e.g. for edits. Called after every successful poll.
+    async fn recheck_published(&self, _ctx: &SerenityContext, _data: &Data) {}
+}
+
+/// Max items archived with fetched content per poll while they are not posted,
+/// see [`NewsItem::fetches_content`].
+pub const MAX_BACKFILL_FETCHES: usize = 8;
+
+async fn save_archived<I: NewsItem>(
+    conn: &libsql::Connection,
+    source: &str,
+    item: &I,
+    content: Option<String>,
+) {
+    let archived = ArchivedItem {
+        source: source.to_owned(),
+        item_id: item.id(),
+        title: item.title(),
+        url: item.url(),
+        published_at: item.timestamp(),
+        content: content.unwrap_or_default(),
+    };
+    if let Err(err) = archive::db_layer::save_item(conn, &archived).await {
+        eprintln!(
+            "Failed to archive {source} item {}: {err}",
+            archived.item_id
+        );
+    }
+}
+
+/// Remembers ids of a published item, see [`NewsItem::published_ids`].
+pub async fn save_published_ids(conn: &libsql::Connection, source: &str, ids: &[String]) {
+    for id in ids {
+        if let Err(err) = db_layer::save_published_item_id(conn, source, id).await {
+            eprintln!("CRITICAL: Could not persist published item {id} for {source}: {err}. Item might be re-posted.");
+        }
+    }
+}
+
+/// Item posted to a channel.
+#[derive(Debug, Clone, Default)]
+pub struct Posted {
+    /// Thread with item details, if one was created.
+    pub thread: Option<ChannelId>,
+    /// Text body of the item, e.g. markdown of the forum post.
+    pub body: Option<String>,
+    /// Other versions posted along with the item, e.g. translations, by their id.
+    pub versions: Vec<(String, Posted)>,
 }
 
 pub trait NewsItem {
-    async fn post_to_discord<C>(&self, ctx: &SerenityContext, channel: C)
+    async fn post_to_discord<C>(
+        &self,
+        ctx: &SerenityContext,
+        channel: C,
+    ) -> Result<Posted, serenity::Error>
     where
         C: Into<ChannelId>;
 
+    /// Unique id of the item within its newsletter, e.g. thread url.
+    fn id(&self) -> String;
+
+    /// Ids remembered once the item is published. An item is skipped only when all of them
+    /// were published, e.g. urls of every language version. Defaults to [`id`](Self::id).
+    fn published_ids(&self) -> Vec<String> {
+        vec![self.id()]
+    }
+
+    fn url(&self) -> String;
+
     fn timestamp(&self) -> DateTime<Utc>;
 
     fn title(&self) -> String;
 
-    fn is_fresh(&self) -> bool {
-        interval::is_fresh(self.timestamp())
+    /// Full markdown content for the [`archive`], when the item was not posted anywhere.
+    async fn content(&self) -> Option<String> {
+        None
+    }
+
+    /// Whether [`content`](Self::content) is fetched, e.g. from the forum page of the item.
+    fn fetches_content(&self) -> bool {
+        false
+    }
+
+    /// Subforum the item belongs to, used to filter [`subscriptions`](crate::subscriptions).
+    fn subforum_key(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Website language of the item, used to filter [`subscriptions`](crate::subscriptions).
+    fn language_key(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Every website language of the item, e.g. of all its translations.
+    /// Defaults to [`language_key`](Self::language_key).
+    fn language_keys(&self) -> Vec<&'static str> {
+        self.language_key().into_iter().collect()
+    }
+}
+
+/// Max number of subforums fetched at once by [`fetch_subforums`].
+pub const MAX_CONCURRENT_FETCHES: usize = 4;
+
+/// Fetches every subforum concurrently, at most [`MAX_CONCURRENT_FETCHES`] at once.
+/// Each subforum gets its own result, in the order of `subforums`.
+pub async fn fetch_subforums<S, F, Fut>(subforums: &[S], fetch: F) -> Vec<(S, Fut::Output)>
+where
+    S: Copy,
+    F: Fn(S) -> Fut,
+    Fut: Future,
+{
+    futures::stream::iter(subforums.iter().copied())
+        .map(|subforum| {
+            let fetched = fetch(subforum);
+            async move { (subforum, fetched.await) }
+        })
+        .buffered(MAX_CONCURRENT_FETCHES)
+        .collect()
+        .await
+}
+
+/// Merges items of successful subforums and reports each failed one.
+/// Fails only if every subforum failed, with the last error.
+pub fn merge_subforum_results<S, T, E>(
+    source: &str,
+    results: Vec<(S, Result<Vec<T>, E>)>,
+) -> Result<Vec<T>, E>
+where
+    S: Debug,
+    E: Debug,
+{
+    let mut items = Vec::new();
+    let mut last_error = None;
+    let mut any_succeeded = false;
+    for (subforum, result) in results {
+        match result {
+            Ok(subforum_items) => {
+                any_succeeded = true;
+                items.extend(subforum_items);
+            }
+            Err(err) => {
+                eprintln!("{source}: failed to fetch {subforum:?}: {err:?}");
+                last_error = Some(err);
+            }
+        }
+    }
+
+    match (any_succeeded, last_error) {
+        (false, Some(err)) => Err(err),
+        _ => Ok(items),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fetches_subforums_independently() {
+        let results = fetch_subforums(&["news", "broken", "patch-notes"], |subforum| async move {
+            match subforum {
+                "broken" => Err("503"),
+                _ => Ok(vec![format!("{subforum} 1"), format!("{subforum} 2")]),
+            }
+        })
+        .await;
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|(subforum, _)| *subforum)
+                .collect::<Vec<_>>(),
+            vec!["news", "broken", "patch-notes"]
+        );
+        assert_eq!(
+            merge_subforum_results("poe1", results),
+            Ok(vec![
+                "news 1".to_owned(),
+                "news 2".to_owned(),
+                "patch-notes 1".to_owned(),
+                "patch-notes 2".to_owned()
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_only_if_every_subforum_failed() {
+        let results = fetch_subforums(&[1, 2], |n| async move {
+            Err::<Vec<()>, _>(format!("error {n}"))
+        })
+        .await;
+        assert_eq!(
+            merge_subforum_results("epoch", results),
+            Err("error 2".to_owned())
+        );
+
+        let results = fetch_subforums(&[1], |_| async { Ok::<Vec<()>, String>(Vec::new()) }).await;
+        assert_eq!(merge_subforum_results("epoch", results), Ok(Vec::new()));
     }
 }
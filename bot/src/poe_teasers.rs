@@ -12,7 +12,12 @@
 /// AppChannel::Poe2,```
 ///
 ///
-use crate::{channel::AppChannel, Data, SerenityContext};
+use crate::{
+    channel::AppChannel,
+    health,
+    watches::{self, Alert},
+    Data, SerenityContext,
+};
 use poe_teasers::{Teaser, TeasersForumThread};
 use poise::serenity_prelude::{ChannelId, CreateEmbed, CreateEmbedAuthor, CreateMessage};
 use std::time::Duration;
@@ -39,7 +44,10 @@ async fn send_new_teasers(
     forum_thread: TeasersForumThread,
     channel_id: ChannelId,
 ) {
-    let thread_teasers = match poe_teasers::download_teasers_from_thread(forum_thread).await {
+    let result = poe_teasers::download_teasers_from_thread(forum_thread).await;
+    let source = format!("teasers {}", forum_thread.title());
+    health::record(ctx, data, &source, &result).await;
+    let thread_teasers = match result {
         Ok(teas) => teas,
         Err(err) => {
             println!("Could not download thread teasers. {err}");
@@ -81,6 +89,13 @@ async fn send_new_teasers(
             match send_teaser(ctx, channel_id, teaser).await {
                 Ok(_) => {
                     newly_published_headings.push(teaser.heading.clone());
+                    let alert = Alert {
+                        source: "teasers",
+                        title: forum_thread.title(),
+                        url: Some(forum_thread.url()),
+                        body: Some(&teaser.heading),
+                    };
+                    watches::notify(ctx, data, &alert).await;
                 }
                 Err(err) => {
                     eprintln!(
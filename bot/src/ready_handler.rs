@@ -3,7 +3,7 @@ use crate::{
     channel::AppChannel,
     newsletter::Newsletter,
     status::{get_kroiya_status, watch_status, Status},
-    Data, SerenityContext,
+    thread_watches, Data, SerenityContext,
 };
 use chrono::{DateTime, NaiveDate, Utc};
 use std::time::Duration;
@@ -46,9 +46,16 @@ async fn start_watchers(ctx: &SerenityContext, data: &Data) {
                 Status::Offline => AppChannel::General.say(ctx, ":rabbit: ушел"),
             },
         ),
-        data.newsletters.poe1.start(ctx, AppChannel::Poe1),
-        data.newsletters.poe2.start(ctx, AppChannel::Poe2),
-        data.newsletters.epoch.start(ctx, AppChannel::LastEpoch),
-        data.newsletters.diablo.start(ctx, AppChannel::Diablo),
+        data.newsletters.poe1.start(ctx, data),
+        data.newsletters.poe2.start(ctx, data),
+        data.newsletters.epoch.start(ctx, data),
+        data.newsletters.diablo.start(ctx, data),
+        thread_watches::watch_threads(ctx, data),
+        futures::future::join_all(
+            data.newsletters
+                .feeds
+                .iter()
+                .map(|feed| feed.start(ctx, data))
+        ),
     );
 }
@@ -0,0 +1,522 @@
+//! Routing of newsletters to Discord channels.
+//!
+//! Channels subscribe to a newsletter with `/subscribe`, optionally narrowed down
+//! to one subforum or website language. Newsletters post every new item to each matching route.
+use crate::{
+    channel::AppChannel,
+    commands::NewsletterChoice,
+    newsletter::{NewsItem, Posted},
+    Data, Error, PoiseContext, SerenityContext,
+};
+use poise::serenity_prelude::{ChannelId, GuildId};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    /// Only items from this subforum, see [`NewsItem::subforum_key`].
+    pub subforum: Option<String>,
+    /// Only items in this language, see [`NewsItem::language_keys`].
+    pub language: Option<String>,
+}
+
+impl Route {
+    pub fn matches<I: NewsItem>(&self, item: &I) -> bool {
+        let subforum_matches = match &self.subforum {
+            Some(subforum) => item.subforum_key() == Some(subforum.as_str()),
+            None => true,
+        };
+        let language_matches = match &self.language {
+            Some(language) => item.language_keys().contains(&language.as_str()),
+            None => true,
+        };
+
+        subforum_matches && language_matches
+    }
+}
+
+/// Routes the bot used before they became configurable.
+/// Written to the database once, so that unsubscribing from all of them sticks.
+pub const DEFAULT_ROUTES: [(NewsletterChoice, AppChannel); 4] = [
+    (NewsletterChoice::Poe1, AppChannel::Poe1),
+    (NewsletterChoice::Poe2, AppChannel::Poe2),
+    (NewsletterChoice::LastEpoch, AppChannel::LastEpoch),
+    (NewsletterChoice::Diablo, AppChannel::Diablo),
+];
+
+/// Loads all routes of given newsletter source.
+pub async fn load_routes(data: &Data, source: &str) -> Result<Vec<Route>, String> {
+    let conn = data
+        .db
+        .connect()
+        .map_err(|e| format!("DB connection error: {e}"))?;
+    db_layer::load_routes(&conn, source).await
+}
+
+/// Posts item to every matching route and returns successful posts.
+/// Returns None if no route matches the item or it could not be posted to any of them,
+/// so that it is not remembered as published.
+pub async fn post_to_routes<I: NewsItem>(
+    ctx: &SerenityContext,
+    routes: &[Route],
+    item: &I,
+) -> Option<Vec<Posted>> {
+    let mut posted = Vec::new();
+    for route in routes.iter().filter(|route| route.matches(item)) {
+        match item.post_to_discord(ctx, route.channel_id).await {
+            Ok(post) => posted.push(post),
+            Err(err) => eprintln!(
+                "Failed to post {} to channel {} of guild {}: {err}",
+                item.title(),
+                route.channel_id,
+                route.guild_id
+            ),
+        }
+    }
+
+    (!posted.is_empty()).then_some(posted)
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum SubforumChoice {
+    #[name = "news"]
+    News,
+    #[name = "patch-notes"]
+    PatchNotes,
+    #[name = "developer-blogs"]
+    DeveloperBlogs,
+}
+
+impl SubforumChoice {
+    pub fn key(&self) -> &'static str {
+        match self {
+            SubforumChoice::News => "news",
+            SubforumChoice::PatchNotes => "patch-notes",
+            SubforumChoice::DeveloperBlogs => "developer-blogs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum LanguageChoice {
+    #[name = "en"]
+    En,
+    #[name = "ru"]
+    Ru,
+    #[name = "de"]
+    De,
+    #[name = "fr"]
+    Fr,
+    #[name = "es"]
+    Es,
+    #[name = "pt"]
+    Pt,
+    #[name = "th"]
+    Th,
+    #[name = "jp"]
+    Jp,
+    #[name = "kr"]
+    Kr,
+    #[name = "tw"]
+    Tw,
+}
+
+impl LanguageChoice {
+    pub fn key(&self) -> &'static str {
+        match self {
+            LanguageChoice::En => "en",
+            LanguageChoice::Ru => "ru",
+            LanguageChoice::De => "de",
+            LanguageChoice::Fr => "fr",
+            LanguageChoice::Es => "es",
+            LanguageChoice::Pt => "pt",
+            LanguageChoice::Th => "th",
+            LanguageChoice::Jp => "jp",
+            LanguageChoice::Kr => "kr",
+            LanguageChoice::Tw => "tw",
+        }
+    }
+}
+
+pub async fn autocomplete_newsletter<'a>(
+    ctx: PoiseContext<'a>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    ctx.data()
+        .newsletters
+        .sources()
+        .into_iter()
+        .filter(move |source| source.starts_with(partial))
+        .map(str::to_owned)
+}
+
+fn check_newsletter(ctx: PoiseContext<'_>, newsletter: &str) -> Result<(), Error> {
+    let sources = ctx.data().newsletters.sources();
+    match sources.contains(&newsletter) {
+        true => Ok(()),
+        false => Err(format!(
+            "Unknown newsletter {newsletter}. Available: {}",
+            sources.join(", ")
+        )
+        .into()),
+    }
+}
+
+/// Post news from a newsletter to this channel
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn subscribe(
+    ctx: PoiseContext<'_>,
+    #[description = "Which newsletter: poe1, poe2, epoch, diablo or a feed"]
+    #[autocomplete = "autocomplete_newsletter"]
+    newsletter: String,
+    #[description = "Only news from this subforum"] subforum: Option<SubforumChoice>,
+    #[description = "Only news in this language"] language: Option<LanguageChoice>,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    check_newsletter(ctx, &newsletter)?;
+    let guild_id = ctx.guild_id().ok_or("Not in a guild")?;
+    let route = Route {
+        guild_id,
+        channel_id: ctx.channel_id(),
+        subforum: subforum.map(|s| s.key().to_owned()),
+        language: language.map(|l| l.key().to_owned()),
+    };
+
+    let conn = ctx.data().db.connect()?;
+    db_layer::save_route(&conn, &newsletter, &route).await?;
+
+    ctx.say(format!(
+        "Subscribed this channel to {newsletter}{}",
+        describe_filters(subforum, language)
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Stop posting news from a newsletter to this channel
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn unsubscribe(
+    ctx: PoiseContext<'_>,
+    #[description = "Which newsletter: poe1, poe2, epoch, diablo or a feed"]
+    #[autocomplete = "autocomplete_newsletter"]
+    newsletter: String,
+    #[description = "Only remove subscription to this subforum"] subforum: Option<SubforumChoice>,
+    #[description = "Only remove subscription to this language"] language: Option<LanguageChoice>,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let conn = ctx.data().db.connect()?;
+    let removed = db_layer::delete_routes(
+        &conn,
+        &newsletter,
+        ctx.channel_id(),
+        subforum.map(|s| s.key()),
+        language.map(|l| l.key()),
+    )
+    .await?;
+
+    match removed {
+        0 => ctx.say("This channel has no such subscription").await?,
+        n => {
+            ctx.say(format!(
+                "Removed {n} subscription(s) to {newsletter}{}",
+                describe_filters(subforum, language)
+            ))
+            .await?
+        }
+    };
+    Ok(())
+}
+
+fn describe_filters(subforum: Option<SubforumChoice>, language: Option<LanguageChoice>) -> String {
+    let filters: Vec<&str> = [subforum.map(|s| s.key()), language.map(|l| l.key())]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    match filters.is_empty() {
+        true => String::new(),
+        false => format!(" ({})", filters.join(", ")),
+    }
+}
+
+pub mod db_layer {
+    use super::{Route, DEFAULT_ROUTES};
+    use libsql::{params, Connection, Error as LibsqlError, Row};
+    use poise::serenity_prelude::{ChannelId, GuildId};
+
+    /// Empty `subforum` or `language` means the route is not filtered by it.
+    pub const CREATE_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS newsletter_routes (
+        source TEXT NOT NULL,
+        guild_id INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL,
+        subforum TEXT NOT NULL DEFAULT '',
+        language TEXT NOT NULL DEFAULT '',
+        created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (source, channel_id, subforum, language)
+    ) STRICT;"#;
+
+    /// Single row, present once [`DEFAULT_ROUTES`] were seeded.
+    pub const CREATE_SEEDS_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS newsletter_routes_seeds (
+        id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+        seeded_at TEXT DEFAULT CURRENT_TIMESTAMP
+    ) STRICT;"#;
+
+    pub async fn ensure_schema_exists(conn: &Connection) -> Result<(), LibsqlError> {
+        conn.execute(CREATE_IF_NOT_EXISTS, ()).await?;
+        conn.execute(CREATE_SEEDS_IF_NOT_EXISTS, ()).await?;
+        Ok(())
+    }
+
+    /// Writes [`DEFAULT_ROUTES`] of `guild_id` once. Databases that already have routes
+    /// are only marked as seeded.
+    pub async fn seed_default_routes(
+        conn: &Connection,
+        guild_id: GuildId,
+    ) -> Result<(), LibsqlError> {
+        let mut rows = conn
+            .query("SELECT COUNT(*) FROM newsletter_routes_seeds", ())
+            .await?;
+        if let Some(row) = rows.next().await? {
+            if row.get::<i64>(0)? > 0 {
+                return Ok(());
+            }
+        }
+
+        let mut rows = conn
+            .query("SELECT COUNT(*) FROM newsletter_routes", ())
+            .await?;
+        let count = match rows.next().await? {
+            Some(row) => row.get::<i64>(0)?,
+            None => 0,
+        };
+
+        if count == 0 {
+            insert_default_routes(conn, guild_id).await?;
+        }
+        conn.execute(
+            "INSERT OR IGNORE INTO newsletter_routes_seeds (id) VALUES (1)",
+            (),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_default_routes(
+        conn: &Connection,
+        guild_id: GuildId,
+    ) -> Result<(), LibsqlError> {
+        for (newsletter, channel) in DEFAULT_ROUTES {
+            conn.execute(
+                "INSERT OR IGNORE INTO
+                     newsletter_routes (source, guild_id, channel_id)
+                     VALUES
+                        (?, ?, ?)",
+                params![
+                    newsletter.source(),
+                    guild_id.get() as i64,
+                    channel.id().get() as i64
+                ],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn load_routes(conn: &Connection, source: &str) -> Result<Vec<Route>, String> {
+        let mut rows = conn
+            .query(
+                "SELECT
+                    guild_id, channel_id, subforum, language
+                FROM
+                    newsletter_routes
+                WHERE
+                    source = ?",
+                params![source],
+            )
+            .await
+            .map_err(|e| format!("DB query failed for load_routes [{}]: {}", source, e))?;
+
+        let mut routes = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| format!("Failed to get next row: {}", e))?
+        {
+            routes.push(
+                route_from_row(&row)
+                    .map_err(|e| format!("Invalid route row for source {}: {}", source, e))?,
+            );
+        }
+
+        Ok(routes)
+    }
+
+    fn route_from_row(row: &Row) -> Result<Route, LibsqlError> {
+        let non_empty = |s: String| (!s.is_empty()).then_some(s);
+        Ok(Route {
+            guild_id: GuildId::new(row.get::<i64>(0)? as u64),
+            channel_id: ChannelId::new(row.get::<i64>(1)? as u64),
+            subforum: non_empty(row.get::<String>(2)?),
+            language: non_empty(row.get::<String>(3)?),
+        })
+    }
+
+    pub async fn save_route(conn: &Connection, source: &str, route: &Route) -> Result<(), String> {
+        conn.execute(
+            "INSERT OR IGNORE INTO
+                 newsletter_routes (source, guild_id, channel_id, subforum, language)
+                 VALUES
+                    (?, ?, ?, ?, ?)",
+            params![
+                source,
+                route.guild_id.get() as i64,
+                route.channel_id.get() as i64,
+                route.subforum.as_deref().unwrap_or_default(),
+                route.language.as_deref().unwrap_or_default()
+            ],
+        )
+        .await
+        .map_err(|e| format!("DB execute failed for save_route [{}]: {}", source, e))?;
+
+        Ok(())
+    }
+
+    /// Deletes routes of the channel. Omitted `subforum` or `language` match any value.
+    /// Returns the number of deleted routes.
+    pub async fn delete_routes(
+        conn: &Connection,
+        source: &str,
+        channel_id: ChannelId,
+        subforum: Option<&str>,
+        language: Option<&str>,
+    ) -> Result<u64, String> {
+        conn.execute(
+            "DELETE FROM
+                 newsletter_routes
+             WHERE
+                 source = ?1
+                 AND channel_id = ?2
+                 AND (?3 IS NULL OR subforum = ?3)
+                 AND (?4 IS NULL OR language = ?4)",
+            params![source, channel_id.get() as i64, subforum, language],
+        )
+        .await
+        .map_err(|e| format!("DB execute failed for delete_routes [{}]: {}", source, e))
+    }
+}
+
+#[cfg(test)]
+mod db_layer_tests {
+    use super::{db_layer::*, Route, DEFAULT_ROUTES};
+    use libsql::{Builder, Connection};
+    use poise::serenity_prelude::{ChannelId, GuildId};
+
+    async fn memory_db_client() -> Connection {
+        let db = Builder::new_local(":memory:").build().await.unwrap();
+        let conn = db.connect().unwrap();
+        ensure_schema_exists(&conn).await.unwrap();
+        conn
+    }
+
+    fn route(channel_id: u64, subforum: Option<&str>, language: Option<&str>) -> Route {
+        Route {
+            guild_id: GuildId::new(1),
+            channel_id: ChannelId::new(channel_id),
+            subforum: subforum.map(str::to_owned),
+            language: language.map(str::to_owned),
+        }
+    }
+
+    #[tokio::test]
+    async fn seeds_default_routes_only_once() {
+        let conn = memory_db_client().await;
+
+        seed_default_routes(&conn, GuildId::new(1)).await.unwrap();
+        let routes = load_routes(&conn, "poe1").await.unwrap();
+        assert_eq!(
+            routes,
+            vec![route(DEFAULT_ROUTES[0].1.id().get(), None, None)]
+        );
+
+        delete_routes(&conn, "poe1", DEFAULT_ROUTES[0].1.id(), None, None)
+            .await
+            .unwrap();
+        seed_default_routes(&conn, GuildId::new(1)).await.unwrap();
+        assert!(load_routes(&conn, "poe1").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn does_not_reseed_after_unsubscribing_from_everything() {
+        let conn = memory_db_client().await;
+
+        seed_default_routes(&conn, GuildId::new(1)).await.unwrap();
+        for (newsletter, channel) in DEFAULT_ROUTES {
+            delete_routes(&conn, newsletter.source(), channel.id(), None, None)
+                .await
+                .unwrap();
+        }
+        seed_default_routes(&conn, GuildId::new(1)).await.unwrap();
+
+        for (newsletter, _) in DEFAULT_ROUTES {
+            assert!(load_routes(&conn, newsletter.source())
+                .await
+                .unwrap()
+                .is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn marks_configured_routes_as_seeded() {
+        let conn = memory_db_client().await;
+
+        save_route(&conn, "poe1", &route(10, None, None))
+            .await
+            .unwrap();
+        seed_default_routes(&conn, GuildId::new(1)).await.unwrap();
+        delete_routes(&conn, "poe1", ChannelId::new(10), None, None)
+            .await
+            .unwrap();
+        seed_default_routes(&conn, GuildId::new(1)).await.unwrap();
+
+        assert!(load_routes(&conn, "poe2").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn save_load_and_delete_routes() {
+        let conn = memory_db_client().await;
+
+        let all = route(10, None, None);
+        let ru_patch_notes = route(20, Some("patch-notes"), Some("ru"));
+        let ru_news = route(20, Some("news"), Some("ru"));
+        for r in [&all, &ru_patch_notes, &ru_news] {
+            save_route(&conn, "poe1", r).await.unwrap();
+        }
+        // Saving the same route twice must not duplicate it.
+        save_route(&conn, "poe1", &all).await.unwrap();
+        save_route(&conn, "epoch", &all).await.unwrap();
+
+        let routes = load_routes(&conn, "poe1").await.unwrap();
+        assert_eq!(routes.len(), 3);
+        assert!([&all, &ru_patch_notes, &ru_news]
+            .iter()
+            .all(|r| routes.contains(r)));
+
+        let removed = delete_routes(&conn, "poe1", ChannelId::new(20), Some("news"), None)
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+
+        let removed = delete_routes(&conn, "poe1", ChannelId::new(20), None, None)
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+
+        assert_eq!(load_routes(&conn, "poe1").await.unwrap(), vec![all.clone()]);
+        assert_eq!(load_routes(&conn, "epoch").await.unwrap(), vec![all]);
+    }
+}
@@ -0,0 +1,973 @@
+//! Forum threads watched for staff replies.
+//!
+//! `/watch-thread` stores a PoE, Last Epoch or Diablo thread for the channel. Watched threads
+//! are polled, and every new staff reply is posted to the watching channels with a link to the post.
+use crate::{
+    health, interval, time,
+    watches::{self, Alert},
+    Data, Error, PoiseContext, SerenityContext,
+};
+use chrono::{DateTime, Utc};
+use poe_forum::{thread::ForumPost, WebsiteLanguage};
+use poise::serenity_prelude::{
+    ChannelId, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage, Timestamp,
+};
+
+pub const MAX_THREADS_PER_CHANNEL: usize = 10;
+/// Max posts requested from Discourse at once.
+const DISCOURSE_POSTS_CHUNK: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForumThread {
+    Poe {
+        lang: WebsiteLanguage,
+        thread_id: u64,
+    },
+    LastEpoch {
+        topic_id: u64,
+    },
+    Diablo {
+        topic_id: u64,
+    },
+}
+
+impl ForumThread {
+    /// Recognizes thread urls, including links to pages and posts of the thread, e.g.
+    /// `https://www.pathofexile.com/forum/view-thread/3594080/page/2`,
+    /// `https://forum.lastepoch.com/t/last-epoch-patch-1-2-3-notes/77541/5`,
+    /// `https://us.forums.blizzard.com/en/d4/t/some-topic/231450`.
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim();
+        let url = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .unwrap_or(url);
+        let (host, path) = url.split_once('/').unwrap_or((url, ""));
+        let segments: Vec<&str> = path.split(['/', '?', '#']).collect();
+        let topic_id = |segments: &[&str]| match segments {
+            ["t", slug_or_id, rest @ ..] => rest
+                .first()
+                .and_then(|id| id.parse().ok())
+                .or_else(|| slug_or_id.parse().ok()),
+            _ => None,
+        };
+
+        match host {
+            "forum.lastepoch.com" => Some(ForumThread::LastEpoch {
+                topic_id: topic_id(&segments)?,
+            }),
+            "us.forums.blizzard.com" => match segments.as_slice() {
+                ["en", "d4", rest @ ..] => Some(ForumThread::Diablo {
+                    topic_id: topic_id(rest)?,
+                }),
+                _ => None,
+            },
+            host => match (WebsiteLanguage::from_host(host), segments.as_slice()) {
+                (Some(lang), ["forum", "view-thread", id, ..]) => Some(ForumThread::Poe {
+                    lang,
+                    thread_id: id.parse().ok()?,
+                }),
+                _ => None,
+            },
+        }
+    }
+
+    /// Canonical url of the thread, used as its id.
+    pub fn url(&self) -> String {
+        match self {
+            ForumThread::Poe { lang, thread_id } => {
+                format!("{}/forum/view-thread/{thread_id}", lang.base_url())
+            }
+            ForumThread::LastEpoch { topic_id } => {
+                last_epoch_forum::client().url(&format!("/t/{topic_id}"))
+            }
+            ForumThread::Diablo { topic_id } => diablo::client().url(&format!("/t/{topic_id}")),
+        }
+    }
+
+    pub fn forum_name(&self) -> String {
+        match self {
+            ForumThread::Poe { lang, .. } => {
+                format!("PoE Forum [{}]", lang.code().to_uppercase())
+            }
+            ForumThread::LastEpoch { .. } => "Last Epoch Forum".to_owned(),
+            ForumThread::Diablo { .. } => "Diablo IV Forum".to_owned(),
+        }
+    }
+
+    /// Fetches posts of the thread that may be new since `state`. Only the last page
+    /// is scanned if the thread was never fetched.
+    pub async fn fetch(&self, state: &ThreadState) -> Result<FetchedThread, String> {
+        match self {
+            ForumThread::Poe { lang, .. } => fetch_poe(&self.url(), *lang, state)
+                .await
+                .map_err(|e| e.to_string()),
+            ForumThread::LastEpoch { topic_id } => {
+                fetch_discourse(&last_epoch_forum::client(), *topic_id, state)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            ForumThread::Diablo { topic_id } => {
+                fetch_discourse(&diablo::client(), *topic_id, state)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/// How far the thread was read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreadState {
+    /// Newest post that was seen. Post ids grow within a forum.
+    pub last_post_id: Option<u64>,
+    /// Last page of PoE threads, to continue from it.
+    pub last_page: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchedThread {
+    pub title: Option<String>,
+    /// Posts of the scanned pages, sorted by id.
+    pub posts: Vec<Reply>,
+    /// Newest post id known from the thread, also when that post was not fetched.
+    pub newest_post_id: Option<u64>,
+    pub last_page: Option<u32>,
+}
+
+impl FetchedThread {
+    /// Staff posts newer than the state.
+    pub fn new_staff_replies(&self, state: &ThreadState) -> Vec<&Reply> {
+        self.posts
+            .iter()
+            .filter(|post| post.is_staff && state.last_post_id.is_none_or(|last| post.id > last))
+            .collect()
+    }
+
+    /// State after reading all fetched posts and the newest known one.
+    pub fn state(&self, previous: &ThreadState) -> ThreadState {
+        ThreadState {
+            last_post_id: self
+                .posts
+                .iter()
+                .map(|post| post.id)
+                .max()
+                .max(self.newest_post_id)
+                .max(previous.last_post_id),
+            last_page: self.last_page,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub id: u64,
+    pub author: Option<String>,
+    pub is_staff: bool,
+    pub posted_date: DateTime<Utc>,
+    /// Markdown of the post.
+    pub content: String,
+    pub image: Option<String>,
+    /// Link to the exact post.
+    pub url: String,
+}
+
+impl Reply {
+    fn from_poe(post: ForumPost, lang: WebsiteLanguage) -> Self {
+        Reply {
+            url: post.url(lang),
+            id: post.id,
+            author: post.author,
+            is_staff: post.is_staff,
+            posted_date: post.posted_date,
+            content: post.content,
+            image: post.images.into_iter().next(),
+        }
+    }
+
+    fn from_discourse(
+        client: &discourse::Client,
+        topic: &discourse::Topic,
+        post: &discourse::Post,
+    ) -> Self {
+        Reply {
+            id: post.id,
+            author: Some(post.username.clone()),
+            is_staff: post.staff,
+            posted_date: post.created_at,
+            content: discourse::cooked_to_markdown(
+                &post.cooked,
+                &client.topic_url(&topic.slug, topic.id),
+            ),
+            image: post.images().first().map(|src| client.absolute_url(src)),
+            url: client.url(&format!(
+                "/t/{}/{}/{}",
+                topic.slug, topic.id, post.post_number
+            )),
+        }
+    }
+}
+
+async fn fetch_poe(
+    url: &str,
+    lang: WebsiteLanguage,
+    state: &ThreadState,
+) -> Result<FetchedThread, poe_forum::Error> {
+    let timezone = time::poe_forum_timezone();
+    let first = poe_forum::thread::fetch_thread_page(url, 1, lang, timezone).await?;
+    report_skipped(url, &first);
+    let from = state.last_page.unwrap_or(first.last_page).max(1);
+    // The first page is known even if the last one has no readable posts.
+    let first_page_newest = first.posts.iter().map(|post| post.id).max();
+
+    let mut posts = Vec::new();
+    if from == 1 {
+        posts.extend(first.posts);
+    }
+    for page in from.max(2)..=first.last_page {
+        let page = poe_forum::thread::fetch_thread_page(url, page, lang, timezone).await?;
+        report_skipped(url, &page);
+        posts.extend(page.posts);
+    }
+
+    let mut posts: Vec<Reply> = posts
+        .into_iter()
+        .map(|post| Reply::from_poe(post, lang))
+        .collect();
+    posts.sort_by_key(|post| post.id);
+    posts.dedup_by_key(|post| post.id);
+
+    Ok(FetchedThread {
+        title: None,
+        newest_post_id: posts.last().map(|post| post.id).max(first_page_newest),
+        posts,
+        last_page: Some(first.last_page),
+    })
+}
+
+fn report_skipped(url: &str, page: &poe_forum::thread::ThreadPage) {
+    if let Some(err) = &page.skipped {
+        eprintln!("{url}: post skipped on page {}: {err}", page.page);
+    }
+}
+
+async fn fetch_discourse(
+    client: &discourse::Client,
+    topic_id: u64,
+    state: &ThreadState,
+) -> Result<FetchedThread, discourse::Error> {
+    let topic = client.topic(topic_id).await?;
+    let mut posts = topic.post_stream.posts.clone();
+
+    // Only the first chunk of posts comes with the topic, the rest are requested by ids.
+    let loaded: Vec<u64> = posts.iter().map(|post| post.id).collect();
+    let newest = topic.post_stream.stream.iter().copied().max();
+    let missing: Vec<u64> = topic
+        .post_stream
+        .stream
+        .iter()
+        .copied()
+        .filter(|id| !loaded.contains(id))
+        .filter(|id| match state.last_post_id {
+            Some(last) => *id > last,
+            None => Some(*id) == newest,
+        })
+        .collect();
+    for ids in missing.chunks(DISCOURSE_POSTS_CHUNK) {
+        posts.extend(client.topic_posts(topic_id, ids).await?);
+    }
+
+    let mut posts: Vec<Reply> = posts
+        .iter()
+        .map(|post| Reply::from_discourse(client, &topic, post))
+        .collect();
+    posts.sort_by_key(|post| post.id);
+    posts.dedup_by_key(|post| post.id);
+
+    Ok(FetchedThread {
+        title: Some(topic.title),
+        posts,
+        newest_post_id: newest,
+        last_page: None,
+    })
+}
+
+/// Thread with the channels it is posted to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedThread {
+    pub url: String,
+    pub title: Option<String>,
+    pub state: ThreadState,
+    pub channels: Vec<ChannelId>,
+}
+
+/// Polls watched threads and posts new staff replies.
+pub async fn watch_threads(ctx: &SerenityContext, data: &Data) {
+    let mut interval = interval::interval();
+    loop {
+        interval.tick().await;
+        let threads = match data.db.connect() {
+            Ok(conn) => db_layer::load_threads(&conn).await,
+            Err(e) => Err(format!("DB connection error: {e}")),
+        };
+        match threads {
+            Ok(threads) => {
+                for thread in threads {
+                    check_thread(ctx, data, &thread).await;
+                }
+            }
+            Err(err) => eprintln!("Failed to load watched threads: {err}"),
+        }
+    }
+}
+
+/// Source of the watched thread in [`health`].
+fn health_source(url: &str) -> String {
+    format!("thread {url}")
+}
+
+async fn check_thread(ctx: &SerenityContext, data: &Data, watched: &WatchedThread) {
+    let Some(thread) = ForumThread::parse(&watched.url) else {
+        eprintln!("Unknown watched thread {}", watched.url);
+        return;
+    };
+
+    let result = thread.fetch(&watched.state).await;
+    health::record(
+        ctx,
+        data,
+        &health_source(&watched.url),
+        &result.as_ref().map(|fetched| fetched.posts.clone()),
+    )
+    .await;
+    let fetched = match result {
+        Ok(fetched) => fetched,
+        Err(err) => {
+            eprintln!("Could not fetch watched thread {}: {err}", watched.url);
+            return;
+        }
+    };
+
+    let title = fetched
+        .title
+        .clone()
+        .or_else(|| watched.title.clone())
+        .unwrap_or_else(|| watched.url.clone());
+    let mut state = fetched.state(&watched.state);
+    for reply in fetched.new_staff_replies(&watched.state) {
+        let message = CreateMessage::new().embed(create_reply_embed(&thread, &title, reply));
+        let mut posted = false;
+        for channel_id in &watched.channels {
+            match channel_id.send_message(ctx, message.clone()).await {
+                Ok(_) => posted = true,
+                Err(err) => eprintln!(
+                    "Failed to post staff reply {} to channel {channel_id}: {err}",
+                    reply.url
+                ),
+            }
+        }
+
+        if !posted {
+            // Not remembered, so it will be retried in the next cycle.
+            state = ThreadState {
+                last_post_id: Some(reply.id - 1),
+                last_page: watched.state.last_page,
+            };
+            break;
+        }
+
+        let alert = Alert {
+            source: "threads",
+            title: &title,
+            url: Some(&reply.url),
+            body: Some(&reply.content),
+        };
+        watches::notify(ctx, data, &alert).await;
+    }
+
+    let result = match data.db.connect() {
+        Ok(conn) => {
+            db_layer::update_thread(&conn, &watched.url, fetched.title.as_deref(), &state).await
+        }
+        Err(e) => Err(format!("DB connection error: {e}")),
+    };
+    if let Err(err) = result {
+        eprintln!(
+            "Failed to save state of watched thread {}: {err}",
+            watched.url
+        );
+    }
+}
+
+fn create_reply_embed(thread: &ForumThread, title: &str, reply: &Reply) -> CreateEmbed {
+    let description: String = reply
+        .content
+        .chars()
+        .take(crate::EMBED_DESCRIPTION_MAX_CHARS)
+        .collect();
+    let mut embed = CreateEmbed::new()
+        .title(format!("💬 {title}"))
+        .url(&reply.url)
+        .description(description)
+        .footer(CreateEmbedFooter::new(thread.forum_name()));
+
+    if let Some(author) = &reply.author {
+        embed = embed.author(CreateEmbedAuthor::new(author));
+    }
+
+    if let Ok(timestamp) = Timestamp::from_millis(reply.posted_date.timestamp_millis()) {
+        embed = embed.timestamp(timestamp);
+    }
+
+    if let Some(image) = &reply.image {
+        embed = embed.image(image);
+    }
+
+    embed
+}
+
+/// Post new staff replies of a forum thread to this channel
+#[poise::command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS",
+    rename = "watch-thread"
+)]
+pub async fn watch_thread(
+    ctx: PoiseContext<'_>,
+    #[description = "Url of a PoE, Last Epoch or Diablo IV forum thread"] url: String,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let thread =
+        ForumThread::parse(&url).ok_or("Not a PoE, Last Epoch or Diablo IV forum thread url")?;
+    let guild_id = ctx.guild_id().ok_or("Not in a guild")?;
+
+    let conn = ctx.data().db.connect()?;
+    let channel_threads = db_layer::load_channel_threads(&conn, ctx.channel_id()).await?;
+    if channel_threads.len() >= MAX_THREADS_PER_CHANNEL && !channel_threads.contains(&thread.url())
+    {
+        return Err(
+            format!("A channel can watch at most {MAX_THREADS_PER_CHANNEL} threads").into(),
+        );
+    }
+
+    // Existing replies are only remembered, so that the channel is not flooded.
+    let state = ThreadState::default();
+    let fetched = thread
+        .fetch(&state)
+        .await
+        .map_err(|err| format!("Could not read the thread: {err}"))?;
+    db_layer::save_thread(
+        &conn,
+        &thread.url(),
+        fetched.title.as_deref(),
+        &fetched.state(&state),
+    )
+    .await?;
+    db_layer::save_channel(&conn, &thread.url(), guild_id, ctx.channel_id()).await?;
+
+    ctx.say(format!(
+        "Watching {} for staff replies",
+        fetched.title.unwrap_or_else(|| thread.url())
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Stop posting staff replies of a forum thread to this channel
+#[poise::command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS",
+    rename = "unwatch-thread"
+)]
+pub async fn unwatch_thread(
+    ctx: PoiseContext<'_>,
+    #[description = "Url of the watched thread"] url: String,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let thread =
+        ForumThread::parse(&url).ok_or("Not a PoE, Last Epoch or Diablo IV forum thread url")?;
+
+    let conn = ctx.data().db.connect()?;
+    match db_layer::delete_channel(&conn, &thread.url(), ctx.channel_id()).await? {
+        0 => ctx.say("This channel does not watch the thread").await?,
+        _ => {
+            if !db_layer::is_watched(&conn, &thread.url()).await? {
+                ctx.data()
+                    .health
+                    .lock()
+                    .await
+                    .remove(&health_source(&thread.url()));
+            }
+            ctx.say(format!("Stopped watching {}", thread.url()))
+                .await?
+        }
+    };
+    Ok(())
+}
+
+pub mod db_layer {
+    use super::{ThreadState, WatchedThread};
+    use libsql::{params, Connection, Error as LibsqlError};
+    use poise::serenity_prelude::{ChannelId, GuildId};
+    use std::collections::BTreeMap;
+
+    /// Zero `last_post_id` or `last_page` means not known yet.
+    pub const CREATE_THREADS_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS watched_threads (
+        url TEXT PRIMARY KEY,
+        title TEXT NOT NULL DEFAULT '',
+        last_post_id INTEGER NOT NULL DEFAULT 0,
+        last_page INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT DEFAULT CURRENT_TIMESTAMP
+    ) STRICT;"#;
+
+    pub const CREATE_CHANNELS_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS watched_thread_channels (
+        url TEXT NOT NULL,
+        guild_id INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL,
+        created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (url, channel_id)
+    ) STRICT;"#;
+
+    pub async fn ensure_schema_exists(conn: &Connection) -> Result<(), LibsqlError> {
+        conn.execute(CREATE_THREADS_IF_NOT_EXISTS, ()).await?;
+        conn.execute(CREATE_CHANNELS_IF_NOT_EXISTS, ()).await?;
+        Ok(())
+    }
+
+    /// Saves the thread, keeping its state if it is already watched by another channel.
+    pub async fn save_thread(
+        conn: &Connection,
+        url: &str,
+        title: Option<&str>,
+        state: &ThreadState,
+    ) -> Result<(), String> {
+        conn.execute(
+            "INSERT OR IGNORE INTO
+                 watched_threads (url, title, last_post_id, last_page)
+                 VALUES
+                    (?, ?, ?, ?)",
+            params![
+                url,
+                title.unwrap_or_default(),
+                state.last_post_id.unwrap_or_default() as i64,
+                state.last_page.unwrap_or_default() as i64
+            ],
+        )
+        .await
+        .map_err(|e| format!("DB execute failed for save_thread [{}]: {}", url, e))?;
+
+        Ok(())
+    }
+
+    /// Updates the state of the thread, and its title if known.
+    pub async fn update_thread(
+        conn: &Connection,
+        url: &str,
+        title: Option<&str>,
+        state: &ThreadState,
+    ) -> Result<(), String> {
+        conn.execute(
+            "UPDATE
+                 watched_threads
+             SET
+                 title = COALESCE(?2, title),
+                 last_post_id = ?3,
+                 last_page = ?4
+             WHERE
+                 url = ?1",
+            params![
+                url,
+                title,
+                state.last_post_id.unwrap_or_default() as i64,
+                state.last_page.unwrap_or_default() as i64
+            ],
+        )
+        .await
+        .map_err(|e| format!("DB execute failed for update_thread [{}]: {}", url, e))?;
+
+        Ok(())
+    }
+
+    pub async fn save_channel(
+        conn: &Connection,
+        url: &str,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    ) -> Result<(), String> {
+        conn.execute(
+            "INSERT OR IGNORE INTO
+                 watched_thread_channels (url, guild_id, channel_id)
+                 VALUES
+                    (?, ?, ?)",
+            params![url, guild_id.get() as i64, channel_id.get() as i64],
+        )
+        .await
+        .map_err(|e| format!("DB execute failed for save_channel [{}]: {}", url, e))?;
+
+        Ok(())
+    }
+
+    /// Stops posting the thread to the channel, and forgets the thread if no channel watches it.
+    /// Returns the number of deleted channels.
+    pub async fn delete_channel(
+        conn: &Connection,
+        url: &str,
+        channel_id: ChannelId,
+    ) -> Result<u64, String> {
+        let deleted = conn
+            .execute(
+                "DELETE FROM watched_thread_channels WHERE url = ? AND channel_id = ?",
+                params![url, channel_id.get() as i64],
+            )
+            .await
+            .map_err(|e| format!("DB execute failed for delete_channel [{}]: {}", url, e))?;
+
+        conn.execute(
+            "DELETE FROM
+                 watched_threads
+             WHERE
+                 url = ?
+                 AND url NOT IN (SELECT url FROM watched_thread_channels)",
+            params![url],
+        )
+        .await
+        .map_err(|e| format!("DB execute failed for delete_channel [{}]: {}", url, e))?;
+
+        Ok(deleted)
+    }
+
+    /// Whether any channel watches the thread.
+    pub async fn is_watched(conn: &Connection, url: &str) -> Result<bool, String> {
+        let mut rows = conn
+            .query(
+                "SELECT COUNT(*) FROM watched_threads WHERE url = ?",
+                params![url],
+            )
+            .await
+            .map_err(|e| format!("DB query failed for is_watched [{}]: {}", url, e))?;
+
+        match rows
+            .next()
+            .await
+            .map_err(|e| format!("Failed to get next row: {}", e))?
+        {
+            Some(row) => Ok(row
+                .get::<i64>(0)
+                .map_err(|e| format!("Failed to get count for {}: {}", url, e))?
+                > 0),
+            None => Ok(false),
+        }
+    }
+
+    /// Urls of threads watched by the channel.
+    pub async fn load_channel_threads(
+        conn: &Connection,
+        channel_id: ChannelId,
+    ) -> Result<Vec<String>, String> {
+        let mut rows = conn
+            .query(
+                "SELECT url FROM watched_thread_channels WHERE channel_id = ?",
+                params![channel_id.get() as i64],
+            )
+            .await
+            .map_err(|e| format!("DB query failed for load_channel_threads: {}", e))?;
+
+        let mut urls = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| format!("Failed to get next row: {}", e))?
+        {
+            urls.push(
+                row.get::<String>(0)
+                    .map_err(|e| format!("Invalid url: {}", e))?,
+            );
+        }
+
+        Ok(urls)
+    }
+
+    /// Every watched thread with its channels.
+    pub async fn load_threads(conn: &Connection) -> Result<Vec<WatchedThread>, String> {
+        let mut rows = conn
+            .query(
+                "SELECT
+                    t.url, t.title, t.last_post_id, t.last_page, c.channel_id
+                FROM
+                    watched_threads t
+                    JOIN watched_thread_channels c ON c.url = t.url
+                ORDER BY
+                    t.url",
+                (),
+            )
+            .await
+            .map_err(|e| format!("DB query failed for load_threads: {}", e))?;
+
+        let mut threads: BTreeMap<String, WatchedThread> = BTreeMap::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| format!("Failed to get next row: {}", e))?
+        {
+            let invalid = |e: LibsqlError| format!("Invalid watched thread row: {}", e);
+            let url = row.get::<String>(0).map_err(invalid)?;
+            let title = row.get::<String>(1).map_err(invalid)?;
+            let last_post_id = row.get::<i64>(2).map_err(invalid)?;
+            let last_page = row.get::<i64>(3).map_err(invalid)?;
+            let channel_id = ChannelId::new(row.get::<i64>(4).map_err(invalid)? as u64);
+
+            threads
+                .entry(url.clone())
+                .or_insert_with(|| WatchedThread {
+                    url,
+                    title: (!title.is_empty()).then_some(title),
+                    state: ThreadState {
+                        last_post_id: (last_post_id > 0).then_some(last_post_id as u64),
+                        last_page: (last_page > 0).then_some(last_page as u32),
+                    },
+                    channels: Vec::new(),
+                })
+                .channels
+                .push(channel_id);
+        }
+
+        Ok(threads.into_values().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_thread_urls() {
+        let en = ForumThread::parse("https://www.pathofexile.com/forum/view-thread/3594080/page/2");
+        assert_eq!(
+            en,
+            Some(ForumThread::Poe {
+                lang: WebsiteLanguage::En,
+                thread_id: 3594080
+            })
+        );
+        assert_eq!(
+            en.unwrap().url(),
+            "https://www.pathofexile.com/forum/view-thread/3594080"
+        );
+
+        assert_eq!(
+            ForumThread::parse("ru.pathofexile.com/forum/view-thread/3789613"),
+            Some(ForumThread::Poe {
+                lang: WebsiteLanguage::Ru,
+                thread_id: 3789613
+            })
+        );
+
+        let epoch = ForumThread::parse(
+            "https://forum.lastepoch.com/t/last-epoch-patch-1-2-3-notes/77541/5",
+        );
+        assert_eq!(epoch, Some(ForumThread::LastEpoch { topic_id: 77541 }));
+        assert_eq!(epoch.unwrap().url(), "https://forum.lastepoch.com/t/77541");
+        assert_eq!(
+            ForumThread::parse("https://forum.lastepoch.com/t/77541"),
+            Some(ForumThread::LastEpoch { topic_id: 77541 })
+        );
+
+        assert_eq!(
+            ForumThread::parse("https://us.forums.blizzard.com/en/d4/t/some-topic/231450?u=x"),
+            Some(ForumThread::Diablo { topic_id: 231450 })
+        );
+
+        assert_eq!(
+            ForumThread::parse("https://www.pathofexile.com/forum/view-forum/news"),
+            None
+        );
+        assert_eq!(
+            ForumThread::parse("https://example.com/t/some-topic/1"),
+            None
+        );
+    }
+
+    fn reply(id: u64, is_staff: bool) -> Reply {
+        Reply {
+            id,
+            author: None,
+            is_staff,
+            posted_date: "2025-04-17T16:00:00Z".parse().unwrap(),
+            content: String::new(),
+            image: None,
+            url: String::new(),
+        }
+    }
+
+    #[test]
+    fn finds_new_staff_replies() {
+        let fetched = FetchedThread {
+            title: None,
+            posts: vec![
+                reply(1, true),
+                reply(2, false),
+                reply(3, true),
+                reply(4, false),
+            ],
+            newest_post_id: Some(4),
+            last_page: Some(3),
+        };
+        let state = ThreadState {
+            last_post_id: Some(2),
+            last_page: Some(2),
+        };
+
+        assert_eq!(
+            fetched
+                .new_staff_replies(&state)
+                .iter()
+                .map(|reply| reply.id)
+                .collect::<Vec<_>>(),
+            vec![3]
+        );
+        assert_eq!(
+            fetched.state(&state),
+            ThreadState {
+                last_post_id: Some(4),
+                last_page: Some(3)
+            }
+        );
+        assert_eq!(
+            FetchedThread {
+                posts: Vec::new(),
+                newest_post_id: None,
+                ..fetched
+            }
+            .state(&state)
+            .last_post_id,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn remembers_newest_post_that_was_not_fetched() {
+        let state = ThreadState::default();
+        let fetched = FetchedThread {
+            title: None,
+            posts: Vec::new(),
+            newest_post_id: Some(7),
+            last_page: None,
+        };
+        let state = fetched.state(&state);
+        assert_eq!(state.last_post_id, Some(7));
+
+        let next = FetchedThread {
+            posts: vec![reply(5, true), reply(7, true), reply(8, true)],
+            newest_post_id: Some(8),
+            ..fetched
+        };
+        assert_eq!(
+            next.new_staff_replies(&state)
+                .iter()
+                .map(|reply| reply.id)
+                .collect::<Vec<_>>(),
+            vec![8]
+        );
+    }
+}
+
+#[cfg(test)]
+mod db_layer_tests {
+    use super::{db_layer::*, ThreadState};
+    use libsql::{Builder, Connection};
+    use poise::serenity_prelude::{ChannelId, GuildId};
+
+    async fn memory_db_client() -> Connection {
+        let db = Builder::new_local(":memory:").build().await.unwrap();
+        let conn = db.connect().unwrap();
+        ensure_schema_exists(&conn).await.unwrap();
+        conn
+    }
+
+    #[tokio::test]
+    async fn save_update_and_delete_threads() {
+        let conn = memory_db_client().await;
+        let url = "https://forum.lastepoch.com/t/77541";
+        let state = ThreadState {
+            last_post_id: Some(100),
+            last_page: None,
+        };
+
+        save_thread(&conn, url, Some("Patch 1.2.3"), &state)
+            .await
+            .unwrap();
+        save_channel(&conn, url, GuildId::new(1), ChannelId::new(10))
+            .await
+            .unwrap();
+        save_channel(&conn, url, GuildId::new(1), ChannelId::new(20))
+            .await
+            .unwrap();
+        // Second channel doesn't reset the state.
+        save_thread(&conn, url, None, &ThreadState::default())
+            .await
+            .unwrap();
+
+        let threads = load_threads(&conn).await.unwrap();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].title.as_deref(), Some("Patch 1.2.3"));
+        assert_eq!(threads[0].state, state);
+        assert_eq!(
+            threads[0].channels,
+            vec![ChannelId::new(10), ChannelId::new(20)]
+        );
+        assert_eq!(
+            load_channel_threads(&conn, ChannelId::new(20))
+                .await
+                .unwrap(),
+            vec![url.to_owned()]
+        );
+
+        let updated = ThreadState {
+            last_post_id: Some(120),
+            last_page: None,
+        };
+        update_thread(&conn, url, None, &updated).await.unwrap();
+        let threads = load_threads(&conn).await.unwrap();
+        assert_eq!(threads[0].state, updated);
+        assert_eq!(threads[0].title.as_deref(), Some("Patch 1.2.3"));
+
+        assert_eq!(
+            delete_channel(&conn, url, ChannelId::new(10))
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            delete_channel(&conn, url, ChannelId::new(10))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            load_threads(&conn).await.unwrap()[0].channels,
+            vec![ChannelId::new(20)]
+        );
+        assert!(is_watched(&conn, url).await.unwrap());
+
+        delete_channel(&conn, url, ChannelId::new(20))
+            .await
+            .unwrap();
+        assert!(load_threads(&conn).await.unwrap().is_empty());
+        assert!(!is_watched(&conn, url).await.unwrap());
+        // Forgotten thread starts from scratch when watched again.
+        save_thread(&conn, url, None, &ThreadState::default())
+            .await
+            .unwrap();
+        save_channel(&conn, url, GuildId::new(1), ChannelId::new(10))
+            .await
+            .unwrap();
+        assert_eq!(
+            load_threads(&conn).await.unwrap()[0].state,
+            ThreadState::default()
+        );
+    }
+}
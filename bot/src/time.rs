@@ -1,32 +1,342 @@
-use chrono::{DateTime, FixedOffset, Utc};
-pub const TIMEZONE: Timezone = Timezone::Moscow;
-
-#[allow(unused)]
-pub enum Timezone {
-    BritishWinter,
-    BritishSummer,
-    Moscow,
-}
-
-impl Timezone {
-    pub fn offset(&self) -> Option<FixedOffset> {
-        match self {
-            Timezone::BritishWinter => FixedOffset::east_opt(0),
-            Timezone::BritishSummer => FixedOffset::east_opt(3600),
-            Timezone::Moscow => FixedOffset::east_opt(3600 * 3),
+//! IANA timezones of forum dates and of dates shown in Discord.
+//!
+//! Forums are read in their own timezone, see [`poe_forum_timezone`].
+//! Dates are shown in the timezone of the user, else of the guild,
+//! else in [`default_display_timezone`], all following DST.
+use crate::{Data, Error, PoiseContext};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use poise::serenity_prelude::{GuildId, UserId};
+
+/// Timezone of PoE forum dates, unless set with `POE_FORUM_TIMEZONE` env.
+pub const DEFAULT_POE_FORUM_TIMEZONE: Tz = Tz::Europe__Moscow;
+/// Timezone of shown dates, unless set with `DISPLAY_TIMEZONE` env or `/timezone`.
+pub const DEFAULT_DISPLAY_TIMEZONE: Tz = poe_challenge_extractor::DEFAULT_DISPLAY_TIMEZONE;
+
+/// Max suggestions of timezone autocomplete, the Discord limit.
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+
+/// IANA timezone from the env var, e.g. `Europe/London`.
+pub fn env_timezone(key: &str) -> Option<Tz> {
+    let name = std::env::var(key).ok()?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    match name.parse() {
+        Ok(timezone) => Some(timezone),
+        Err(e) => {
+            eprintln!("Invalid {key} {name}: {e}");
+            None
         }
     }
 }
 
-pub fn fmt(utc: DateTime<Utc>, with_timezone: bool) -> String {
-    let format = "%d.%m  %H:%M";
+pub fn poe_forum_timezone() -> Tz {
+    env_timezone("POE_FORUM_TIMEZONE").unwrap_or(DEFAULT_POE_FORUM_TIMEZONE)
+}
+
+pub fn default_display_timezone() -> Tz {
+    env_timezone("DISPLAY_TIMEZONE").unwrap_or(DEFAULT_DISPLAY_TIMEZONE)
+}
+
+pub fn fmt(utc: DateTime<Utc>, timezone: Tz) -> String {
+    utc.with_timezone(&timezone)
+        .format("%d.%m  %H:%M")
+        .to_string()
+}
 
-    let s = match with_timezone {
-        true => utc
-            .with_timezone(&TIMEZONE.offset().unwrap())
-            .format(format),
-        false => utc.format(format),
+/// Timezone of the user, else of the guild, else [`default_display_timezone`].
+pub async fn display_timezone(data: &Data, guild_id: Option<GuildId>, user_id: UserId) -> Tz {
+    let conn = match data.db.connect() {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Could not load display timezone. {e}");
+            return default_display_timezone();
+        }
     };
+    match db_layer::load_timezone(&conn, guild_id, user_id).await {
+        Ok(timezone) => timezone.unwrap_or_else(default_display_timezone),
+        Err(e) => {
+            eprintln!("Could not load display timezone. {e}");
+            default_display_timezone()
+        }
+    }
+}
+
+/// [`display_timezone`] of the command author.
+pub async fn author_timezone(ctx: PoiseContext<'_>) -> Tz {
+    display_timezone(ctx.data(), ctx.guild_id(), ctx.author().id).await
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    User(UserId),
+    Guild(GuildId),
+}
+
+fn parse_timezone(name: &str) -> Result<Tz, Error> {
+    name.trim()
+        .parse()
+        .map_err(|_| format!("Unknown timezone {name}, expected e.g. Europe/London").into())
+}
+
+async fn autocomplete_timezone<'a>(
+    _ctx: PoiseContext<'a>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    let partial = partial.to_lowercase();
+    chrono_tz::TZ_VARIANTS
+        .iter()
+        .map(|timezone| timezone.name())
+        .filter(move |name| name.to_lowercase().contains(&partial))
+        .take(MAX_AUTOCOMPLETE_CHOICES)
+        .map(str::to_owned)
+}
+
+/// Set the timezone dates are shown in
+#[poise::command(
+    slash_command,
+    subcommands("me", "server", "show"),
+    subcommand_required
+)]
+pub async fn timezone(_ctx: PoiseContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show dates to you in this timezone
+#[poise::command(slash_command)]
+pub async fn me(
+    ctx: PoiseContext<'_>,
+    #[description = "IANA timezone, e.g. Europe/London. Empty to use the server timezone"]
+    #[autocomplete = "autocomplete_timezone"]
+    timezone: Option<String>,
+) -> Result<(), Error> {
+    set(ctx, Scope::User(ctx.author().id), timezone).await
+}
+
+/// Show dates to everyone in the server in this timezone
+#[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn server(
+    ctx: PoiseContext<'_>,
+    #[description = "IANA timezone, e.g. Europe/London. Empty to use the default"]
+    #[autocomplete = "autocomplete_timezone"]
+    timezone: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Not in a guild")?;
+    set(ctx, Scope::Guild(guild_id), timezone).await
+}
+
+/// Show the timezone dates are shown to you in
+#[poise::command(slash_command)]
+pub async fn show(ctx: PoiseContext<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let timezone = author_timezone(ctx).await;
+    ctx.say(format!(
+        "Dates are shown in {timezone}, now {}",
+        fmt(Utc::now(), timezone)
+    ))
+    .await?;
+    Ok(())
+}
+
+async fn set(ctx: PoiseContext<'_>, scope: Scope, timezone: Option<String>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let timezone = timezone.as_deref().map(parse_timezone).transpose()?;
+
+    let conn = ctx.data().db.connect()?;
+    match timezone {
+        Some(timezone) => db_layer::save_timezone(&conn, scope, timezone).await?,
+        None => db_layer::delete_timezone(&conn, scope).await?,
+    }
+
+    let timezone = author_timezone(ctx).await;
+    ctx.say(format!(
+        "Dates are shown to you in {timezone}, now {}",
+        fmt(Utc::now(), timezone)
+    ))
+    .await?;
+    Ok(())
+}
+
+pub mod db_layer {
+    use super::Scope;
+    use chrono_tz::Tz;
+    use libsql::{params, Connection, Error as LibsqlError};
+    use poise::serenity_prelude::{GuildId, UserId};
+
+    pub const CREATE_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS display_timezones (
+        scope TEXT NOT NULL,
+        id INTEGER NOT NULL,
+        timezone TEXT NOT NULL,
+        updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (scope, id)
+    ) STRICT;"#;
+
+    pub async fn ensure_schema_exists(conn: &Connection) -> Result<(), LibsqlError> {
+        conn.execute(CREATE_IF_NOT_EXISTS, ()).await?;
+        Ok(())
+    }
+
+    fn key(scope: Scope) -> (&'static str, i64) {
+        match scope {
+            Scope::User(user_id) => ("user", user_id.get() as i64),
+            Scope::Guild(guild_id) => ("guild", guild_id.get() as i64),
+        }
+    }
+
+    pub async fn save_timezone(
+        conn: &Connection,
+        scope: Scope,
+        timezone: Tz,
+    ) -> Result<(), String> {
+        let (scope_name, id) = key(scope);
+        conn.execute(
+            "INSERT INTO
+                display_timezones (scope, id, timezone)
+                VALUES
+                    (?, ?, ?)
+                ON CONFLICT (scope, id) DO UPDATE SET
+                    timezone = excluded.timezone,
+                    updated_at = CURRENT_TIMESTAMP",
+            params![scope_name, id, timezone.name()],
+        )
+        .await
+        .map_err(|e| format!("DB execute failed for save_timezone [{scope:?}]: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn delete_timezone(conn: &Connection, scope: Scope) -> Result<(), String> {
+        let (scope_name, id) = key(scope);
+        conn.execute(
+            "DELETE FROM display_timezones WHERE scope = ? AND id = ?",
+            params![scope_name, id],
+        )
+        .await
+        .map_err(|e| format!("DB execute failed for delete_timezone [{scope:?}]: {}", e))?;
+
+        Ok(())
+    }
 
-    format!("{s}")
+    /// Timezone of the user, else of the guild.
+    pub async fn load_timezone(
+        conn: &Connection,
+        guild_id: Option<GuildId>,
+        user_id: UserId,
+    ) -> Result<Option<Tz>, String> {
+        let mut rows = conn
+            .query(
+                "SELECT
+                    timezone
+                FROM
+                    display_timezones
+                WHERE
+                    (scope = 'user' AND id = ?1)
+                    OR (scope = 'guild' AND id = ?2)
+                ORDER BY
+                    scope = 'user' DESC
+                LIMIT 1",
+                params![
+                    user_id.get() as i64,
+                    guild_id.map(|guild_id| guild_id.get() as i64)
+                ],
+            )
+            .await
+            .map_err(|e| format!("DB query failed for load_timezone [{user_id}]: {}", e))?;
+
+        let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| format!("Failed to get next row: {}", e))?
+        else {
+            return Ok(None);
+        };
+        let name: String = row
+            .get(0)
+            .map_err(|e| format!("Failed to get timezone: {}", e))?;
+        name.parse()
+            .map(Some)
+            .map_err(|e| format!("Invalid stored timezone {name}: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fmt;
+    use chrono_tz::Tz;
+
+    #[test]
+    fn formats_with_daylight_saving_time() {
+        let london = Tz::Europe__London;
+        assert_eq!(
+            fmt("2025-01-15T12:00:00Z".parse().unwrap(), london),
+            "15.01  12:00"
+        );
+        assert_eq!(
+            fmt("2025-07-15T12:00:00Z".parse().unwrap(), london),
+            "15.07  13:00"
+        );
+        assert_eq!(
+            fmt("2025-07-15T12:00:00Z".parse().unwrap(), Tz::Europe__Moscow),
+            "15.07  15:00"
+        );
+    }
+}
+
+#[cfg(test)]
+mod db_layer_tests {
+    use super::{db_layer::*, Scope};
+    use chrono_tz::Tz;
+    use libsql::{Builder, Connection};
+    use poise::serenity_prelude::{GuildId, UserId};
+
+    async fn memory_db_client() -> Connection {
+        let db = Builder::new_local(":memory:").build().await.unwrap();
+        let conn = db.connect().unwrap();
+        ensure_schema_exists(&conn).await.unwrap();
+        conn
+    }
+
+    #[tokio::test]
+    async fn prefers_user_timezone_over_guild_timezone() {
+        let conn = memory_db_client().await;
+        let (guild_id, user_id) = (GuildId::new(1), UserId::new(2));
+        assert_eq!(
+            load_timezone(&conn, Some(guild_id), user_id).await,
+            Ok(None)
+        );
+
+        save_timezone(&conn, Scope::Guild(guild_id), Tz::Europe__Berlin)
+            .await
+            .unwrap();
+        assert_eq!(
+            load_timezone(&conn, Some(guild_id), user_id).await,
+            Ok(Some(Tz::Europe__Berlin))
+        );
+        // Outside of the guild, e.g. in DMs.
+        assert_eq!(load_timezone(&conn, None, user_id).await, Ok(None));
+
+        save_timezone(&conn, Scope::User(user_id), Tz::Asia__Tokyo)
+            .await
+            .unwrap();
+        save_timezone(&conn, Scope::User(user_id), Tz::America__New_York)
+            .await
+            .unwrap();
+        assert_eq!(
+            load_timezone(&conn, Some(guild_id), user_id).await,
+            Ok(Some(Tz::America__New_York))
+        );
+        assert_eq!(
+            load_timezone(&conn, None, user_id).await,
+            Ok(Some(Tz::America__New_York))
+        );
+
+        delete_timezone(&conn, Scope::User(user_id)).await.unwrap();
+        assert_eq!(
+            load_timezone(&conn, Some(guild_id), user_id).await,
+            Ok(Some(Tz::Europe__Berlin))
+        );
+    }
 }
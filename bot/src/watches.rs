@@ -0,0 +1,573 @@
+//! Personal keyword watches.
+//!
+//! Users add keywords or regular expressions with `/watch add`. Titles and bodies of
+//! news, teasers and live updates are matched against them, and every match is sent
+//! to the user as a DM or as a mention in the channel the watch was added in.
+use crate::{Data, Error, PoiseContext, SerenityContext};
+use poise::serenity_prelude::{ChannelId, CreateEmbed, CreateEmbedFooter, CreateMessage, UserId};
+use regex::{Regex, RegexBuilder};
+use std::{collections::HashSet, ops::Range};
+
+pub const MAX_WATCHES_PER_USER: usize = 25;
+pub const MAX_PATTERN_CHARS: usize = 100;
+/// Chars of context on each side of the match in the excerpt.
+const EXCERPT_RADIUS_CHARS: usize = 120;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watch {
+    pub user_id: UserId,
+    pub pattern: String,
+    pub is_regex: bool,
+    /// Channel to mention the user in, DM if None.
+    pub channel_id: Option<ChannelId>,
+}
+
+impl Watch {
+    /// Case-insensitive regex of the watch. Keywords only match whole words.
+    pub fn regex(&self) -> Result<Regex, regex::Error> {
+        let pattern = match self.is_regex {
+            true => self.pattern.clone(),
+            false => {
+                let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+                let boundary = |c: Option<char>| match c.is_some_and(is_word_char) {
+                    true => r"\b",
+                    false => "",
+                };
+                format!(
+                    "{}{}{}",
+                    boundary(self.pattern.chars().next()),
+                    regex::escape(&self.pattern),
+                    boundary(self.pattern.chars().last())
+                )
+            }
+        };
+
+        RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .size_limit(1 << 20)
+            .build()
+    }
+}
+
+/// Something new that was posted, to be matched against watches.
+#[derive(Debug, Clone)]
+pub struct Alert<'a> {
+    /// Where the alert comes from, e.g. `poe1` or `teasers`.
+    pub source: &'a str,
+    pub title: &'a str,
+    pub url: Option<&'a str>,
+    /// Full text, e.g. markdown of the forum post.
+    pub body: Option<&'a str>,
+}
+
+impl Alert<'_> {
+    /// Excerpt around the first match in the title or the body.
+    pub fn find(&self, regex: &Regex) -> Option<String> {
+        std::iter::once(self.title)
+            .chain(self.body)
+            .find_map(|text| regex.find(text).map(|m| excerpt(text, m.range())))
+    }
+}
+
+/// Text around the range with the range in bold, whitespace collapsed.
+pub fn excerpt(text: &str, range: Range<usize>) -> String {
+    let before = &text[..range.start];
+    let start = before
+        .char_indices()
+        .rev()
+        .nth(EXCERPT_RADIUS_CHARS - 1)
+        .map(|(index, _)| index)
+        .unwrap_or(0);
+    let after = &text[range.end..];
+    let end = after
+        .char_indices()
+        .nth(EXCERPT_RADIUS_CHARS)
+        .map(|(index, _)| range.end + index)
+        .unwrap_or(text.len());
+
+    let collapse = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut excerpt = String::new();
+    if start > 0 {
+        excerpt.push('…');
+    }
+    excerpt.push_str(&collapse(&text[start..range.start]));
+    if text[start..range.start].ends_with(char::is_whitespace) {
+        excerpt.push(' ');
+    }
+    excerpt.push_str(&format!("**{}**", collapse(&text[range.clone()])));
+    if text[range.end..end].starts_with(char::is_whitespace) {
+        excerpt.push(' ');
+    }
+    excerpt.push_str(&collapse(&text[range.end..end]));
+    if end < text.len() {
+        excerpt.push('…');
+    }
+    excerpt
+}
+
+/// Notifies every user with a matching watch, once per user.
+pub async fn notify(ctx: &SerenityContext, data: &Data, alert: &Alert<'_>) {
+    let watches = match data.db.connect() {
+        Ok(conn) => db_layer::load_watches(&conn).await,
+        Err(e) => Err(format!("DB connection error: {e}")),
+    };
+    let watches = match watches {
+        Ok(watches) => watches,
+        Err(err) => {
+            eprintln!("Failed to load watches: {err}");
+            return;
+        }
+    };
+
+    let mut notified = HashSet::new();
+    for watch in watches {
+        if notified.contains(&watch.user_id) {
+            continue;
+        }
+
+        let regex = match watch.regex() {
+            Ok(regex) => regex,
+            Err(err) => {
+                eprintln!(
+                    "Invalid watch {} of {}: {err}",
+                    watch.pattern, watch.user_id
+                );
+                continue;
+            }
+        };
+        let Some(excerpt) = alert.find(&regex) else {
+            continue;
+        };
+
+        notified.insert(watch.user_id);
+        let embed = create_alert_embed(alert, &watch, &excerpt);
+        let result = match watch.channel_id {
+            Some(channel_id) => {
+                channel_id
+                    .send_message(
+                        ctx,
+                        CreateMessage::new()
+                            .content(format!("<@{}>", watch.user_id))
+                            .embed(embed),
+                    )
+                    .await
+            }
+            None => {
+                watch
+                    .user_id
+                    .direct_message(ctx, CreateMessage::new().embed(embed))
+                    .await
+            }
+        };
+        if let Err(err) = result {
+            eprintln!(
+                "Failed to notify {} about {}: {err}",
+                watch.user_id, alert.title
+            );
+        }
+    }
+}
+
+fn create_alert_embed(alert: &Alert, watch: &Watch, excerpt: &str) -> CreateEmbed {
+    let mut embed = CreateEmbed::new()
+        .title(alert.title)
+        .description(excerpt)
+        .footer(CreateEmbedFooter::new(format!(
+            "👀 {} · {}",
+            watch.pattern, alert.source
+        )));
+
+    if let Some(url) = alert.url {
+        embed = embed.url(url);
+    }
+
+    embed
+}
+
+/// Get notified when news mention a keyword
+#[poise::command(
+    slash_command,
+    subcommands("add", "remove", "list"),
+    subcommand_required
+)]
+pub async fn watch(_ctx: PoiseContext<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Watch news, teasers and live updates for a keyword
+#[poise::command(slash_command)]
+pub async fn add(
+    ctx: PoiseContext<'_>,
+    #[description = "Keyword, e.g. Necromancer, or regular expression"] pattern: String,
+    #[description = "Treat the pattern as a regular expression"] regex: Option<bool>,
+    #[description = "Mention me in this channel instead of sending a DM"] mention_here: Option<
+        bool,
+    >,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let pattern = pattern.trim().to_owned();
+    if pattern.is_empty() || pattern.chars().count() > MAX_PATTERN_CHARS {
+        return Err(format!("Pattern must be 1-{MAX_PATTERN_CHARS} characters long").into());
+    }
+
+    let watch = Watch {
+        user_id: ctx.author().id,
+        pattern,
+        is_regex: regex.unwrap_or(false),
+        channel_id: mention_here.unwrap_or(false).then(|| ctx.channel_id()),
+    };
+    if let Err(err) = watch.regex() {
+        return Err(format!("Invalid regular expression: {err}").into());
+    }
+
+    let conn = ctx.data().db.connect()?;
+    let watches = db_layer::load_user_watches(&conn, watch.user_id).await?;
+    if watches.len() >= MAX_WATCHES_PER_USER && !watches.iter().any(|w| w.pattern == watch.pattern)
+    {
+        return Err(format!("You can have at most {MAX_WATCHES_PER_USER} watches").into());
+    }
+    db_layer::save_watch(&conn, &watch).await?;
+
+    ctx.say(format!(
+        "Watching for `{}`, alerts will be sent {}",
+        watch.pattern,
+        match watch.channel_id {
+            Some(channel_id) => format!("to <#{channel_id}>"),
+            None => "in DM".to_owned(),
+        }
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Stop watching a keyword
+#[poise::command(slash_command)]
+pub async fn remove(
+    ctx: PoiseContext<'_>,
+    #[description = "Keyword or regular expression as it was added"] pattern: String,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let conn = ctx.data().db.connect()?;
+    let pattern = pattern.trim();
+
+    match db_layer::delete_watch(&conn, ctx.author().id, pattern).await? {
+        0 => ctx.say(format!("You are not watching `{pattern}`")).await?,
+        _ => ctx.say(format!("Stopped watching `{pattern}`")).await?,
+    };
+
+    Ok(())
+}
+
+/// List your watches
+#[poise::command(slash_command)]
+pub async fn list(ctx: PoiseContext<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    let conn = ctx.data().db.connect()?;
+    let watches = db_layer::load_user_watches(&conn, ctx.author().id).await?;
+
+    if watches.is_empty() {
+        ctx.say("You have no watches. Add one with /watch add")
+            .await?;
+        return Ok(());
+    }
+
+    let lines = watches
+        .iter()
+        .map(|watch| {
+            format!(
+                "- `{}`{} → {}",
+                watch.pattern,
+                match watch.is_regex {
+                    true => " (regex)",
+                    false => "",
+                },
+                match watch.channel_id {
+                    Some(channel_id) => format!("<#{channel_id}>"),
+                    None => "DM".to_owned(),
+                }
+            )
+        })
+        .collect::<Vec<_>>();
+    ctx.say(lines.join("\n")).await?;
+
+    Ok(())
+}
+
+pub mod db_layer {
+    use super::Watch;
+    use libsql::{params, Connection, Error as LibsqlError, Row};
+    use poise::serenity_prelude::{ChannelId, UserId};
+
+    pub const CREATE_IF_NOT_EXISTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS keyword_watches (
+        user_id INTEGER NOT NULL,
+        pattern TEXT NOT NULL,
+        is_regex INTEGER NOT NULL DEFAULT 0,
+        channel_id INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (user_id, pattern)
+    ) STRICT;"#;
+
+    pub async fn ensure_schema_exists(conn: &Connection) -> Result<(), LibsqlError> {
+        conn.execute(CREATE_IF_NOT_EXISTS, ()).await?;
+        Ok(())
+    }
+
+    /// Saves the watch, replacing its mode and channel if the user already watches the pattern.
+    pub async fn save_watch(conn: &Connection, watch: &Watch) -> Result<(), String> {
+        conn.execute(
+            "INSERT INTO
+                 keyword_watches (user_id, pattern, is_regex, channel_id)
+                 VALUES
+                    (?, ?, ?, ?)
+                 ON CONFLICT (user_id, pattern) DO UPDATE SET
+                    is_regex = excluded.is_regex,
+                    channel_id = excluded.channel_id",
+            params![
+                watch.user_id.get() as i64,
+                watch.pattern.as_str(),
+                watch.is_regex as i64,
+                watch.channel_id.map(|id| id.get() as i64).unwrap_or(0)
+            ],
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "DB execute failed for save_watch [{} - {}]: {}",
+                watch.user_id, watch.pattern, e
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Returns number of deleted watches.
+    pub async fn delete_watch(
+        conn: &Connection,
+        user_id: UserId,
+        pattern: &str,
+    ) -> Result<u64, String> {
+        conn.execute(
+            "DELETE FROM keyword_watches WHERE user_id = ? AND pattern = ?",
+            params![user_id.get() as i64, pattern],
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "DB execute failed for delete_watch [{} - {}]: {}",
+                user_id, pattern, e
+            )
+        })
+    }
+
+    /// Loads watches of all users.
+    pub async fn load_watches(conn: &Connection) -> Result<Vec<Watch>, String> {
+        let mut rows = conn
+            .query(
+                "SELECT
+                    user_id, pattern, is_regex, channel_id
+                FROM
+                    keyword_watches
+                ORDER BY
+                    user_id, created_at, pattern",
+                (),
+            )
+            .await
+            .map_err(|e| format!("DB query failed for load_watches: {}", e))?;
+
+        let mut watches = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| format!("Failed to get next row: {}", e))?
+        {
+            watches.push(watch_from_row(&row)?);
+        }
+
+        Ok(watches)
+    }
+
+    pub async fn load_user_watches(
+        conn: &Connection,
+        user_id: UserId,
+    ) -> Result<Vec<Watch>, String> {
+        let mut rows = conn
+            .query(
+                "SELECT
+                    user_id, pattern, is_regex, channel_id
+                FROM
+                    keyword_watches
+                WHERE
+                    user_id = ?
+                ORDER BY
+                    created_at, pattern",
+                params![user_id.get() as i64],
+            )
+            .await
+            .map_err(|e| format!("DB query failed for load_user_watches [{}]: {}", user_id, e))?;
+
+        let mut watches = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| format!("Failed to get next row: {}", e))?
+        {
+            watches.push(watch_from_row(&row)?);
+        }
+
+        Ok(watches)
+    }
+
+    fn watch_from_row(row: &Row) -> Result<Watch, String> {
+        let user_id: i64 = row
+            .get(0)
+            .map_err(|e| format!("Failed to get user_id: {}", e))?;
+        let pattern: String = row
+            .get(1)
+            .map_err(|e| format!("Failed to get pattern: {}", e))?;
+        let is_regex: i64 = row
+            .get(2)
+            .map_err(|e| format!("Failed to get is_regex: {}", e))?;
+        let channel_id: i64 = row
+            .get(3)
+            .map_err(|e| format!("Failed to get channel_id: {}", e))?;
+
+        Ok(Watch {
+            user_id: UserId::new(user_id as u64),
+            pattern,
+            is_regex: is_regex != 0,
+            channel_id: (channel_id != 0).then(|| ChannelId::new(channel_id as u64)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch(pattern: &str, is_regex: bool) -> Watch {
+        Watch {
+            user_id: UserId::new(1),
+            pattern: pattern.to_owned(),
+            is_regex,
+            channel_id: None,
+        }
+    }
+
+    #[test]
+    fn keywords_match_whole_words_ignoring_case() {
+        let necro = watch("Necromancer", false).regex().unwrap();
+        assert!(necro.is_match("New necromancer skills"));
+        assert!(!necro.is_match("Necromancers"));
+
+        let trade = watch("Trade", false).regex().unwrap();
+        assert!(trade.is_match("Changes to trade in 0.3"));
+        assert!(!trade.is_match("Trademark"));
+
+        let version = watch("3.26.", false).regex().unwrap();
+        assert!(version.is_match("Patch 3.26.0b"));
+        assert!(!version.is_match("Patch 3x26y"));
+    }
+
+    #[test]
+    fn regex_watches() {
+        let hotfix = watch(r"hotfix\s*\d+", true).regex().unwrap();
+        assert!(hotfix.is_match("0.2.0e Hotfix 3"));
+        assert!(watch("(unclosed", true).regex().is_err());
+    }
+
+    #[test]
+    fn finds_excerpt_in_title_then_body() {
+        let alert = Alert {
+            source: "poe2",
+            title: "0.2.0e Hotfix",
+            url: None,
+            body: Some("Fixed a crash.\n\n- Warlock minions no longer\n  get stuck."),
+        };
+
+        assert_eq!(
+            alert.find(&watch("hotfix", false).regex().unwrap()),
+            Some("0.2.0e **Hotfix**".to_owned())
+        );
+        assert_eq!(
+            alert.find(&watch("warlock", false).regex().unwrap()),
+            Some("Fixed a crash. - **Warlock** minions no longer get stuck.".to_owned())
+        );
+        assert_eq!(alert.find(&watch("Druid", false).regex().unwrap()), None);
+    }
+
+    #[test]
+    fn excerpt_is_shortened_around_match() {
+        let text = format!("{} Warlock {}", "a ".repeat(200), "b ".repeat(200));
+        let start = text.find("Warlock").unwrap();
+        let excerpt = excerpt(&text, start..start + "Warlock".len());
+
+        assert!(excerpt.starts_with('…'));
+        assert!(excerpt.ends_with('…'));
+        assert!(excerpt.contains(" **Warlock** "));
+        assert!(excerpt.chars().count() < 2 * EXCERPT_RADIUS_CHARS + 20);
+    }
+}
+
+#[cfg(test)]
+mod db_layer_tests {
+    use super::{db_layer::*, Watch};
+    use libsql::{Builder, Connection};
+    use poise::serenity_prelude::{ChannelId, UserId};
+
+    async fn memory_db_client() -> Connection {
+        let db = Builder::new_local(":memory:").build().await.unwrap();
+        db.connect().unwrap()
+    }
+
+    #[tokio::test]
+    async fn save_load_and_delete_watches() {
+        let conn = memory_db_client().await;
+        ensure_schema_exists(&conn).await.unwrap();
+        ensure_schema_exists(&conn).await.unwrap();
+
+        let necro = Watch {
+            user_id: UserId::new(1),
+            pattern: "Necromancer".to_owned(),
+            is_regex: false,
+            channel_id: None,
+        };
+        let hotfix = Watch {
+            user_id: UserId::new(2),
+            pattern: r"hotfix\s*\d+".to_owned(),
+            is_regex: true,
+            channel_id: Some(ChannelId::new(842131980538871878)),
+        };
+        save_watch(&conn, &necro).await.unwrap();
+        save_watch(&conn, &hotfix).await.unwrap();
+
+        assert_eq!(
+            load_watches(&conn).await.unwrap(),
+            vec![necro.clone(), hotfix.clone()]
+        );
+
+        // Adding the same pattern again updates the watch.
+        let necro_here = Watch {
+            channel_id: Some(ChannelId::new(1)),
+            ..necro.clone()
+        };
+        save_watch(&conn, &necro_here).await.unwrap();
+        assert_eq!(
+            load_user_watches(&conn, UserId::new(1)).await.unwrap(),
+            vec![necro_here]
+        );
+
+        assert_eq!(
+            delete_watch(&conn, UserId::new(1), "Trade").await.unwrap(),
+            0
+        );
+        assert_eq!(
+            delete_watch(&conn, UserId::new(1), "Necromancer")
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(load_watches(&conn).await.unwrap(), vec![hotfix]);
+    }
+}
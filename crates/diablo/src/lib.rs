@@ -1,26 +1,24 @@
 use chrono::{DateTime, Utc};
+use discourse::{Client, GroupPost};
 use regex::Regex;
 use serde::Deserialize;
 use serde_repr::Serialize_repr;
 use thiserror::Error;
 
-const POSTS_URL: &str = "https://us.forums.blizzard.com/en/d4/groups/blizzard-tracker/posts.json";
 const BASE_URL: &str = "https://us.forums.blizzard.com/en/d4";
-const WEBSITE_DOMAIN_URL: &str = "https://us.forums.blizzard.com";
+const TRACKER_GROUP: &str = "blizzard-tracker";
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("http error: {0}")]
-    Http(#[from] http::Error),
-    #[error("json parse error: {0}")]
-    Json(#[from] serde_json::Error),
+    #[error("discourse error: {0}")]
+    Discourse(#[from] discourse::Error),
     #[error("regex error: {0}")]
     Regex(#[from] regex::Error),
 }
 
 #[derive(Debug, Clone)]
 pub struct User {
-    pub id: u32,
+    pub id: u64,
     pub name: String,
     pub avatar_url: String,
 }
@@ -56,25 +54,30 @@ impl PostCategory {
     }
 }
 
+impl From<u64> for PostCategory {
+    fn from(value: u64) -> Self {
+        match value {
+            5 => PostCategory::PcGeneralDiscussion,
+            6 => PostCategory::ConsoleDiscussion,
+            11 => PostCategory::ConsoleBugReport,
+            _ => PostCategory::Other,
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for PostCategory {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        let value = u16::deserialize(deserializer)?;
-        Ok(match value {
-            5 => PostCategory::PcGeneralDiscussion,
-            6 => PostCategory::ConsoleDiscussion,
-            11 => PostCategory::ConsoleBugReport,
-            _ => PostCategory::Other,
-        })
+        u64::deserialize(deserializer).map(PostCategory::from)
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct DiabloPost {
     pub title: String,
-    pub id: u32,
+    pub id: u64,
     pub description: String,
     pub url: String,
     pub pub_date: DateTime<Utc>,
@@ -83,48 +86,28 @@ pub struct DiabloPost {
     pub category: PostCategory,
 }
 
+pub fn client() -> Client {
+    Client::new(BASE_URL)
+}
+
 pub async fn fetch_posts() -> Result<Vec<DiabloPost>, Error> {
-    let content = http::text(POSTS_URL).await?;
-    let posts = parse_posts(&content)?;
-    Ok(posts)
+    let client = client();
+    let posts = client.group_posts(TRACKER_GROUP).await?;
+    prepare_posts(&client, posts)
 }
 
 pub fn parse_posts(content: &str) -> Result<Vec<DiabloPost>, Error> {
-    #[derive(Debug, Clone, Deserialize)]
-    struct RawUser {
-        pub id: u32,
-        #[serde(rename = "username")]
-        pub name: String,
-        pub avatar_template: String,
-    }
-
-    #[derive(Debug, Clone, Deserialize)]
-    struct RawDiabloPost {
-        #[serde(rename = "topic_title")]
-        pub title: String,
-        pub id: u32,
-        #[serde(rename = "excerpt")]
-        pub description: String,
-        #[serde(rename = "url")]
-        pub pathname: String,
-        #[serde(rename = "created_at")]
-        pub pub_date: DateTime<Utc>,
-        pub user: RawUser,
-        pub category_id: PostCategory,
-    }
-
-    #[derive(Debug, Clone, Deserialize)]
-    struct Response {
-        posts: Vec<RawDiabloPost>,
-    }
+    let posts = discourse::parse_group_posts(content)?;
+    prepare_posts(&client(), posts)
+}
 
-    let posts = serde_json::from_str::<Response>(content)?.posts;
+fn prepare_posts(client: &Client, posts: Vec<GroupPost>) -> Result<Vec<DiabloPost>, Error> {
     let re = Regex::new(r##"<a href=\"([^\\"]+\.(?:png|jpg|jpeg|gif))\".*?>.*?</a>"##)?;
 
     let posts = posts
         .into_iter()
         .map(|raw_post| {
-            let description = html_escape::decode_html_entities(&raw_post.description).to_string();
+            let description = html_escape::decode_html_entities(&raw_post.excerpt).to_string();
             let (kind, description) = if raw_post.user.id == 1 {
                 if let Some(captures) = re.captures(&description) {
                     let image_url = captures.get(1).map(|m| m.as_str().to_string());
@@ -147,29 +130,24 @@ pub fn parse_posts(content: &str) -> Result<Vec<DiabloPost>, Error> {
                 (PostKind::Other, description)
             };
 
-            let avatar_url = {
-                let avatar = &raw_post.user.avatar_template;
-                match avatar.starts_with("https") {
-                    true => avatar.clone(),
-                    false => format!("{}{}", WEBSITE_DOMAIN_URL, avatar.replace("{size}", "128")),
-                }
-            };
-
             let user = User {
                 id: raw_post.user.id,
-                name: raw_post.user.name,
-                avatar_url,
+                avatar_url: client.avatar_url(&raw_post.user.avatar_template, 128),
+                name: raw_post.user.username,
             };
 
             DiabloPost {
-                title: raw_post.title,
+                title: raw_post.topic_title,
                 id: raw_post.id,
                 description: html2md::parse_html(&description).trim().to_string(),
-                url: format!("{}{}", BASE_URL, raw_post.pathname),
-                pub_date: raw_post.pub_date,
+                url: client.url(&raw_post.url),
+                pub_date: raw_post.created_at,
                 user,
                 kind,
-                category: raw_post.category_id,
+                category: raw_post
+                    .category_id
+                    .map(PostCategory::from)
+                    .unwrap_or(PostCategory::Other),
             }
         })
         .collect();
@@ -213,7 +191,12 @@ mod tests {
 
         match &first_post.kind {
             PostKind::News { post_image_url } => {
-                assert_eq!(post_image_url.as_deref(), Some("https://bnetcmsus-a.akamaihd.net/cms/blog_header/47/47LPZ5UXDG1X1758584759888.png"));
+                assert_eq!(
+                    post_image_url.as_deref(),
+                    Some(
+                        "https://bnetcmsus-a.akamaihd.net/cms/blog_header/47/47LPZ5UXDG1X1758584759888.png"
+                    )
+                );
             }
             _ => panic!("Expected PostKind::News"),
         }
@@ -225,6 +208,9 @@ mod tests {
             "https://us.forums.blizzard.com/en/d4/u/BlizzardEntertainment/activity"
         );
         assert_eq!(first_post.category, PostCategory::PcGeneralDiscussion);
-        assert_eq!(first_post.user.avatar_url, "https://us.forums.blizzard.com/en/d4/plugins/discourse-blizzard-plugin/images/avatars/d4/default.png");
+        assert_eq!(
+            first_post.user.avatar_url,
+            "https://us.forums.blizzard.com/en/d4/plugins/discourse-blizzard-plugin/images/avatars/d4/default.png"
+        );
     }
 }
@@ -0,0 +1,163 @@
+//! Typed access to the JSON API of [Discourse](https://www.discourse.org/) forums,
+//! e.g. `forum.lastepoch.com` or `us.forums.blizzard.com`.
+
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+pub mod model;
+
+pub use model::{CategoryPage, GroupPost, Post, Poster, Topic, TopicListItem, User};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("http error: {0}")]
+    Http(#[from] http::Error),
+    #[error("json parse error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Client of one Discourse forum.
+#[derive(Debug, Clone)]
+pub struct Client {
+    base_url: String,
+}
+
+impl Client {
+    /// Base url of the forum, including subfolder if forum has one,
+    /// e.g. `https://forum.lastepoch.com` or `https://us.forums.blizzard.com/en/d4`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let base_url: String = base_url.into();
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Latest topics of category. `/c/<slug>.json`
+    pub async fn category(&self, slug: &str) -> Result<CategoryPage, Error> {
+        self.get(&format!("/c/{slug}.json")).await
+    }
+
+    /// Page of latest topics of category, the first one is 0. `/c/<slug>.json?page=<page>`
+    pub async fn category_page(&self, slug: &str, page: u32) -> Result<CategoryPage, Error> {
+        self.get(&format!("/c/{slug}.json?page={page}")).await
+    }
+
+    /// Topic with its first posts. `/t/<id>.json`
+    pub async fn topic(&self, id: u64) -> Result<Topic, Error> {
+        self.get(&format!("/t/{id}.json")).await
+    }
+
+    /// Latest posts of group members. `/groups/<group>/posts.json`
+    pub async fn group_posts(&self, group: &str) -> Result<Vec<GroupPost>, Error> {
+        parse_group_posts(&http::text(&self.url(&format!("/groups/{group}/posts.json"))).await?)
+    }
+
+    /// Posts of the topic by their ids, e.g. ids from [`model::PostStream::stream`]
+    /// past the first chunk. `/t/<id>/posts.json?post_ids[]=<post_id>`
+    pub async fn topic_posts(&self, topic_id: u64, post_ids: &[u64]) -> Result<Vec<Post>, Error> {
+        let query: Vec<String> = post_ids
+            .iter()
+            .map(|id| format!("post_ids[]={id}"))
+            .collect();
+        parse_topic_posts(
+            &http::text(&self.url(&format!("/t/{topic_id}/posts.json?{}", query.join("&"))))
+                .await?,
+        )
+    }
+
+    /// Single post. `/posts/<id>.json`
+    pub async fn post(&self, id: u64) -> Result<Post, Error> {
+        self.get(&format!("/posts/{id}.json")).await
+    }
+
+    /// Url of forum page by path relative to base url, e.g. `/t/some-topic/42`.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Url of topic html page.
+    pub fn topic_url(&self, slug: &str, id: u64) -> String {
+        self.url(&format!("/t/{slug}/{id}"))
+    }
+
+    /// Url of user profile page.
+    pub fn user_url(&self, username: &str) -> String {
+        self.url(&format!("/u/{username}"))
+    }
+
+    /// Makes absolute url from `src` of uploads and avatars.
+    /// These are relative to the forum domain, not to the base url.
+    pub fn absolute_url(&self, src: &str) -> String {
+        if src.starts_with("http://") || src.starts_with("https://") {
+            src.to_owned()
+        } else if let Some(src) = src.strip_prefix("//") {
+            format!("https://{src}")
+        } else {
+            format!("{}{}", self.origin(), src)
+        }
+    }
+
+    /// Absolute url of user avatar of given size in pixels.
+    pub fn avatar_url(&self, avatar_template: &str, size: u32) -> String {
+        self.absolute_url(&avatar_template.replace("{size}", &size.to_string()))
+    }
+
+    /// Scheme and host part of base url.
+    fn origin(&self) -> &str {
+        let host_start = self.base_url.find("://").map(|i| i + 3).unwrap_or(0);
+        match self.base_url[host_start..].find('/') {
+            Some(path_start) => &self.base_url[..host_start + path_start],
+            None => &self.base_url,
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let content = http::text(&self.url(path)).await?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Converts cooked html of a post to markdown, links resolved against `url` of the topic.
+pub fn cooked_to_markdown(cooked: &str, url: &str) -> String {
+    let fragment = scraper::Html::parse_fragment(cooked);
+    markdown::html_to_markdown(&fragment.root_element(), url)
+}
+
+/// Parses response of `/c/<slug>.json`.
+pub fn parse_category(content: &str) -> Result<CategoryPage, Error> {
+    Ok(serde_json::from_str(content)?)
+}
+
+/// Parses response of `/t/<id>.json`.
+pub fn parse_topic(content: &str) -> Result<Topic, Error> {
+    Ok(serde_json::from_str(content)?)
+}
+
+/// Parses response of `/groups/<group>/posts.json`.
+pub fn parse_group_posts(content: &str) -> Result<Vec<GroupPost>, Error> {
+    #[derive(serde::Deserialize)]
+    struct Response {
+        posts: Vec<GroupPost>,
+    }
+
+    Ok(serde_json::from_str::<Response>(content)?.posts)
+}
+
+/// Parses response of `/t/<id>/posts.json`.
+pub fn parse_topic_posts(content: &str) -> Result<Vec<Post>, Error> {
+    #[derive(serde::Deserialize)]
+    struct Response {
+        post_stream: model::PostStream,
+    }
+
+    Ok(serde_json::from_str::<Response>(content)?.post_stream.posts)
+}
+
+/// Parses response of `/posts/<id>.json`.
+pub fn parse_post(content: &str) -> Result<Post, Error> {
+    Ok(serde_json::from_str(content)?)
+}
@@ -0,0 +1,205 @@
+use chrono::{DateTime, Utc};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct User {
+    pub id: u64,
+    pub username: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Avatar path with `{size}` placeholder, see [`crate::Client::avatar_url`].
+    pub avatar_template: String,
+}
+
+/// Response of `/c/<slug>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CategoryPage {
+    /// Everyone mentioned in [`TopicListItem::posters`].
+    #[serde(default)]
+    pub users: Vec<User>,
+    pub topic_list: TopicList,
+}
+
+impl CategoryPage {
+    pub fn topics(&self) -> &[TopicListItem] {
+        &self.topic_list.topics
+    }
+
+    /// Author of the topic.
+    pub fn original_poster(&self, topic: &TopicListItem) -> Option<&User> {
+        let poster = topic
+            .posters
+            .iter()
+            .find(|poster| poster.description.contains("Original Poster"))?;
+        self.users.iter().find(|user| user.id == poster.user_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TopicList {
+    pub topics: Vec<TopicListItem>,
+    /// Path of the next page, e.g. `/c/news/5/l/latest?page=1`.
+    #[serde(default)]
+    pub more_topics_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TopicListItem {
+    pub id: u64,
+    pub title: String,
+    pub slug: String,
+    pub posts_count: u32,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_posted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub bumped: bool,
+    #[serde(default)]
+    pub bumped_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub pinned_globally: bool,
+    #[serde(default)]
+    pub closed: bool,
+    /// Topic thumbnail.
+    #[serde(default)]
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub excerpt: Option<String>,
+    #[serde(default)]
+    pub category_id: Option<u64>,
+    #[serde(default)]
+    pub posters: Vec<Poster>,
+}
+
+impl TopicListItem {
+    /// Pinned at the top of the category or globally.
+    pub fn is_pinned(&self) -> bool {
+        self.pinned || self.pinned_globally
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Poster {
+    pub user_id: u64,
+    /// e.g. `Original Poster, Most Recent Poster`.
+    pub description: String,
+}
+
+/// Response of `/t/<id>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Topic {
+    pub id: u64,
+    pub title: String,
+    pub slug: String,
+    pub posts_count: u32,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub pinned_globally: bool,
+    #[serde(default)]
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub category_id: Option<u64>,
+    pub post_stream: PostStream,
+}
+
+impl Topic {
+    /// Pinned at the top of the category or globally.
+    pub fn is_pinned(&self) -> bool {
+        self.pinned || self.pinned_globally
+    }
+
+    /// Opening post of the topic.
+    pub fn first_post(&self) -> Option<&Post> {
+        self.post_stream
+            .posts
+            .iter()
+            .find(|post| post.post_number == 1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PostStream {
+    /// First chunk of posts, usually 20.
+    pub posts: Vec<Post>,
+    /// Ids of all posts of the topic.
+    #[serde(default)]
+    pub stream: Vec<u64>,
+}
+
+/// Post as returned by `/t/<id>.json` and `/posts/<id>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Post {
+    pub id: u64,
+    pub topic_id: u64,
+    #[serde(default)]
+    pub topic_slug: Option<String>,
+    pub post_number: u32,
+    pub username: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub avatar_template: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Rendered html of the post.
+    pub cooked: String,
+    #[serde(default)]
+    pub staff: bool,
+}
+
+impl Post {
+    /// Images of the post, without emojis and avatars. Full size image is
+    /// preferred for lightboxed thumbnails. Urls may be relative, see [`crate::Client::absolute_url`].
+    pub fn images(&self) -> Vec<String> {
+        cooked_images(&self.cooked)
+    }
+}
+
+/// Post as returned by `/groups/<group>/posts.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GroupPost {
+    pub id: u64,
+    pub topic_id: u64,
+    pub topic_title: String,
+    pub topic_slug: String,
+    /// Path relative to forum base url, e.g. `/t/some-topic/231450/1`.
+    pub url: String,
+    /// Html excerpt of the post.
+    pub excerpt: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub category_id: Option<u64>,
+    pub post_number: u32,
+    pub user: User,
+}
+
+fn cooked_images(cooked: &str) -> Vec<String> {
+    let fragment = Html::parse_fragment(cooked);
+    let img_selector = Selector::parse("img").unwrap();
+
+    fragment
+        .select(&img_selector)
+        .filter(|img| {
+            !img.value()
+                .classes()
+                .any(|class| class == "emoji" || class == "avatar")
+        })
+        .filter_map(|img| {
+            let lightbox_href = img
+                .ancestors()
+                .filter_map(scraper::ElementRef::wrap)
+                .find(|el| {
+                    el.value().name() == "a" && el.value().classes().any(|c| c == "lightbox")
+                })
+                .and_then(|a| a.value().attr("href"));
+            lightbox_href
+                .or_else(|| img.value().attr("src"))
+                .map(str::to_owned)
+        })
+        .collect()
+}
@@ -0,0 +1,149 @@
+use chrono::{DateTime, Utc};
+use discourse::Client;
+
+fn date(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(format!("tests/fixtures/{name}")).unwrap()
+}
+
+#[test]
+fn parses_category() {
+    let page = discourse::parse_category(&fixture("category.json")).unwrap();
+    let topics = page.topics();
+
+    assert_eq!(topics.len(), 9);
+    assert_eq!(
+        page.topic_list.more_topics_url.as_deref(),
+        Some("/c/news/5/l/latest?page=1")
+    );
+
+    let about = &topics[0];
+    assert_eq!(about.title, "About the News category");
+    assert!(about.is_pinned());
+
+    let live = &topics[1];
+    assert!(!live.is_pinned());
+    assert_eq!(live.id, 75431);
+    assert_eq!(
+        live.title,
+        "Last Epoch - Season 2 - Tombs of the Erased is officially LIVE!"
+    );
+    assert_eq!(live.created_at, date("2025-04-17T16:01:00Z"));
+    assert_eq!(live.bumped_at, Some(date("2025-04-17T21:12:44Z")));
+    assert_eq!(live.posts_count, 118);
+    assert_eq!(
+        page.original_poster(live)
+            .map(|user| user.username.as_str()),
+        Some("EHG_Kain")
+    );
+
+    assert_eq!(topics[7].title, "I Just Called, To Say, 'VC's When?'");
+    assert!(topics[4].image_url.is_some());
+}
+
+#[test]
+fn parses_topic() {
+    let topic = discourse::parse_topic(&fixture("topic.json")).unwrap();
+
+    assert_eq!(topic.id, 77541);
+    assert_eq!(topic.title, "Last Epoch Patch 1.2.3 Notes");
+    assert_eq!(topic.slug, "last-epoch-patch-1-2-3-notes");
+    assert!(!topic.is_pinned());
+    assert_eq!(topic.post_stream.posts.len(), 3);
+    assert_eq!(topic.post_stream.stream.len(), 20);
+
+    let first = topic.first_post().unwrap();
+    assert_eq!(first.username, "Averielle");
+    assert!(first.staff);
+    assert_eq!(first.created_at, date("2025-05-08T16:00:00Z"));
+    assert_eq!(first.updated_at, Some(date("2025-05-08T16:54:50Z")));
+    assert!(first.cooked.contains("Bug Fixes</h1>"));
+
+    // Only emojis in the second post.
+    assert!(topic.post_stream.posts[1].images().is_empty());
+}
+
+#[test]
+fn parses_topic_posts() {
+    let posts = discourse::parse_topic_posts(&fixture("topic_posts.json")).unwrap();
+
+    assert_eq!(
+        posts.iter().map(|post| post.id).collect::<Vec<_>>(),
+        vec![100001, 100002]
+    );
+    assert!(posts.iter().all(|post| post.topic_id == 77541));
+    assert!(!posts[0].staff);
+}
+
+#[test]
+fn parses_group_posts() {
+    let posts = discourse::parse_group_posts(&fixture("group_posts.json")).unwrap();
+
+    assert_eq!(posts.len(), 4);
+
+    let first = &posts[0];
+    assert_eq!(first.id, 1989745);
+    assert_eq!(first.topic_id, 231450);
+    assert_eq!(
+        first.url,
+        "/t/embody-the-sector%E2%80%99s-finest-with-starcraft-x-diablo-iv/231450/1"
+    );
+    assert_eq!(first.category_id, Some(5));
+    assert_eq!(first.user.id, 1);
+    assert_eq!(first.user.username, "BlizzardEntertainment");
+    assert!(first.excerpt.contains("View Full Article"));
+}
+
+#[test]
+fn parses_post_with_images() {
+    let post = discourse::parse_post(&fixture("post.json")).unwrap();
+
+    assert_eq!(post.id, 412345);
+    assert_eq!(post.topic_id, 75431);
+    assert_eq!(post.post_number, 1);
+    assert!(post.staff);
+    assert_eq!(
+        post.images(),
+        vec![
+            "https://forum.lastepoch.com/uploads/default/original/2X/5/5c175ed1fa21413c0cfc3084cd724ac8c6130e41.png",
+            "/uploads/default/original/2X/a/a1b2c3d4e5f60718293a4b5c6d7e8f9012345678.jpeg",
+        ]
+    );
+}
+
+#[test]
+fn builds_urls() {
+    let epoch = Client::new("https://forum.lastepoch.com/");
+    assert_eq!(
+        epoch.topic_url("last-epoch-patch-1-2-3-notes", 77541),
+        "https://forum.lastepoch.com/t/last-epoch-patch-1-2-3-notes/77541"
+    );
+    assert_eq!(
+        epoch.absolute_url("/uploads/default/original/2X/a/a.jpeg"),
+        "https://forum.lastepoch.com/uploads/default/original/2X/a/a.jpeg"
+    );
+    assert_eq!(
+        epoch.absolute_url("//cdn.example.com/a.png"),
+        "https://cdn.example.com/a.png"
+    );
+
+    let diablo = Client::new("https://us.forums.blizzard.com/en/d4");
+    assert_eq!(
+        diablo.url("/t/hotfix/231178/1"),
+        "https://us.forums.blizzard.com/en/d4/t/hotfix/231178/1"
+    );
+    assert_eq!(
+        diablo.avatar_url(
+            "/en/d4/plugins/discourse-blizzard-plugin/images/avatars/d4/default.png",
+            128
+        ),
+        "https://us.forums.blizzard.com/en/d4/plugins/discourse-blizzard-plugin/images/avatars/d4/default.png"
+    );
+    assert_eq!(
+        diablo.avatar_url("/user_avatar/us.forums.blizzard.com/x/{size}/1_2.png", 128),
+        "https://us.forums.blizzard.com/user_avatar/us.forums.blizzard.com/x/128/1_2.png"
+    );
+}
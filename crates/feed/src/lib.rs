@@ -0,0 +1,142 @@
+use chrono::{DateTime, Utc};
+use feed_rs::model::{Entry, Feed, Text};
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("http error: {0}")]
+    Http(#[from] http::Error),
+    #[error("feed parse error: {0}")]
+    Parse(#[from] feed_rs::parser::ParseFeedError),
+}
+
+/// One entry of an RSS 2.0 or Atom feed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedItem {
+    /// Entry guid/id, falls back to a hash generated by the parser.
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub published: DateTime<Utc>,
+    pub author: Option<String>,
+    /// Entry summary or content, converted to markdown.
+    pub summary: Option<String>,
+    pub image_url: Option<String>,
+    /// Title of the whole feed.
+    pub feed_title: Option<String>,
+}
+
+pub async fn fetch_feed(url: &str) -> Result<Vec<FeedItem>, Error> {
+    let content = http::text(url).await?;
+    parse_feed(&content)
+}
+
+/// Parses RSS 2.0 or Atom feed. Entries without a link or a date are skipped.
+pub fn parse_feed(content: &str) -> Result<Vec<FeedItem>, Error> {
+    let feed = feed_rs::parser::parse(content.as_bytes())?;
+    Ok(items(feed))
+}
+
+fn items(feed: Feed) -> Vec<FeedItem> {
+    let feed_url = feed
+        .links
+        .first()
+        .map(|link| link.href.clone())
+        .unwrap_or_default();
+    let feed_title = feed.title.map(|title| text_to_markdown(&title, &feed_url));
+    let feed_author = feed.authors.into_iter().next().map(|person| person.name);
+
+    feed.entries
+        .into_iter()
+        .filter_map(|entry| {
+            let mut item = parse_entry(entry)?;
+            item.author = item.author.or_else(|| feed_author.clone());
+            item.feed_title = feed_title.clone();
+            Some(item)
+        })
+        .collect()
+}
+
+fn parse_entry(entry: Entry) -> Option<FeedItem> {
+    let url = entry
+        .links
+        .iter()
+        .find(|link| matches!(link.rel.as_deref(), None | Some("alternate")))
+        .or_else(|| entry.links.first())?
+        .href
+        .clone();
+    let published = entry.published.or(entry.updated)?;
+
+    let summary = entry
+        .summary
+        .as_ref()
+        .map(|summary| text_to_markdown(summary, &url))
+        .or_else(|| {
+            entry
+                .content
+                .as_ref()
+                .and_then(|content| content.body.as_deref())
+                .map(|body| html_to_markdown(body, &url))
+        })
+        .or_else(|| {
+            entry
+                .media
+                .iter()
+                .find_map(|media| media.description.as_ref())
+                .map(|description| text_to_markdown(description, &url))
+        })
+        .filter(|summary| !summary.is_empty());
+
+    Some(FeedItem {
+        id: entry.id,
+        title: entry
+            .title
+            .map(|title| text_to_markdown(&title, &url))
+            .unwrap_or_else(|| url.clone()),
+        url,
+        published,
+        author: entry.authors.into_iter().next().map(|person| person.name),
+        summary,
+        image_url: image_url(&entry.media),
+        feed_title: None,
+    })
+}
+
+/// First thumbnail or image attachment of the entry.
+fn image_url(media: &[feed_rs::model::MediaObject]) -> Option<String> {
+    media
+        .iter()
+        .find_map(|media| media.thumbnails.first())
+        .map(|thumbnail| thumbnail.image.uri.clone())
+        .or_else(|| {
+            media
+                .iter()
+                .flat_map(|media| &media.content)
+                .find(|content| {
+                    content
+                        .content_type
+                        .as_ref()
+                        .is_some_and(|content_type| content_type.to_string().starts_with("image/"))
+                })
+                .and_then(|content| content.url.as_ref())
+                .map(|url| url.to_string())
+        })
+}
+
+/// Text as markdown, links resolved against `base_url`.
+fn text_to_markdown(text: &Text, base_url: &str) -> String {
+    match text.content_type.to_string().as_str() {
+        "text/plain" => markdown::clean_text(&text.content),
+        _ => html_to_markdown(&text.content, base_url),
+    }
+}
+
+fn html_to_markdown(html: &str, base_url: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    markdown::html_to_markdown(&fragment.root_element(), base_url)
+        .trim()
+        .to_owned()
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use feed::FeedItem;
+
+fn date(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+#[test]
+fn parses_rss() {
+    let content = std::fs::read_to_string("tests/fixtures/rss.xml").unwrap();
+    let items = feed::parse_feed(&content).unwrap();
+
+    assert_eq!(items.len(), 2, "undated item should be skipped");
+
+    let patch = &items[0];
+    assert_eq!(patch.id, "patch-1-2-3");
+    assert_eq!(patch.title, "Patch 1.2.3 Notes");
+    assert_eq!(patch.url, "https://example.com/patch-notes/1-2-3");
+    assert_eq!(patch.published, date("2025-04-17T16:01:00Z"));
+    assert_eq!(patch.author.as_deref(), Some("Mike"));
+    assert_eq!(
+        patch.image_url.as_deref(),
+        Some("https://example.com/images/1-2-3.png")
+    );
+    assert_eq!(patch.feed_title.as_deref(), Some("Patch Notes Mirror"));
+
+    let summary = patch.summary.as_deref().unwrap();
+    assert!(summary.contains("**Warlock**"), "{summary}");
+    assert!(
+        summary.contains("[follow](https://example.com/minions)"),
+        "{summary}"
+    );
+
+    assert_eq!(
+        items[1],
+        FeedItem {
+            id: "https://example.com/patch-notes/1-2-2b".to_owned(),
+            title: "Hotfix 1.2.2b".to_owned(),
+            url: "https://example.com/patch-notes/1-2-2b".to_owned(),
+            published: date("2025-04-15T06:30:00Z"),
+            author: None,
+            summary: Some("Server stability improvements.".to_owned()),
+            image_url: None,
+            feed_title: Some("Patch Notes Mirror".to_owned()),
+        }
+    );
+}
+
+#[test]
+fn parses_youtube_atom() {
+    let content = std::fs::read_to_string("tests/fixtures/youtube_atom.xml").unwrap();
+    let items = feed::parse_feed(&content).unwrap();
+
+    assert_eq!(
+        items,
+        vec![
+            FeedItem {
+                id: "yt:video:dQw4w9WgXcQ".to_owned(),
+                title: "Season 3 Gameplay Reveal".to_owned(),
+                url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_owned(),
+                published: date("2025-05-20T17:00:06Z"),
+                author: Some("Example Game Channel".to_owned()),
+                summary: Some("Take a first look at the new season.\nComing June 12th.".to_owned()),
+                image_url: Some("https://i1.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg".to_owned()),
+                feed_title: Some("Example Game Channel".to_owned()),
+            },
+            FeedItem {
+                id: "yt:video:oHg5SJYRHA0".to_owned(),
+                title: "Developer Q&A".to_owned(),
+                url: "https://www.youtube.com/watch?v=oHg5SJYRHA0".to_owned(),
+                published: date("2025-05-13T16:00:00Z"),
+                // Falls back to the feed author.
+                author: Some("Example Game Channel".to_owned()),
+                summary: None,
+                image_url: Some("https://i2.ytimg.com/vi/oHg5SJYRHA0/hqdefault.jpg".to_owned()),
+                feed_title: Some("Example Game Channel".to_owned()),
+            },
+        ]
+    );
+}
@@ -1,11 +1,39 @@
+use markdown::patch_notes::{self, PatchNotes};
 use scraper::{Html, Selector};
 
 pub fn create_selector(selectors: &str) -> Selector {
     Selector::parse(selectors).unwrap()
 }
 
-pub fn get_content(document: &Html) -> Option<String> {
+/// Markdown of the first post of the topic page at `url`.
+pub fn get_content(document: &Html, url: &str) -> Option<String> {
     let el_content = document.select(&create_selector(".post")).next()?;
 
-    Some(markdown::html_to_markdown(&el_content))
+    Some(markdown::html_to_markdown(&el_content, url))
+}
+
+/// First post of a patch notes topic page as sections and bullets, version from the page title.
+pub fn get_patch_notes(document: &Html) -> Option<PatchNotes> {
+    let el_content = document.select(&create_selector(".post")).next()?;
+    let title: String = document
+        .select(&create_selector("title"))
+        .next()
+        .map(|title| title.text().collect())
+        .unwrap_or_default();
+
+    Some(with_version(PatchNotes::from_html(&el_content), &title))
+}
+
+/// Converts cooked html of a post from Discourse JSON API to patch notes,
+/// version from the topic title.
+pub fn cooked_to_patch_notes(title: &str, cooked: &str) -> PatchNotes {
+    let fragment = Html::parse_fragment(cooked);
+    with_version(PatchNotes::from_html(&fragment.root_element()), title)
+}
+
+fn with_version(mut notes: PatchNotes, title: &str) -> PatchNotes {
+    if notes.version.is_none() {
+        notes.version = patch_notes::version(title);
+    }
+    notes
 }
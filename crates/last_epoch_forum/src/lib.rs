@@ -1,13 +1,182 @@
 use chrono::{DateTime, Utc};
+use discourse::{CategoryPage, Client, Topic, TopicListItem};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 
+pub use discourse::Error;
+pub use markdown::patch_notes::PatchNotes;
+
 pub mod content;
 
-pub async fn fetch_subforum_threads_list(
+pub const BASE_URL: &str = "https://forum.lastepoch.com";
+
+/// Threads read from the top of a subforum by default, pinned ones included.
+pub const DEFAULT_DEPTH: usize = 8;
+/// Topic pages fetched at the same time.
+const MAX_CONCURRENT_TOPIC_FETCHES: usize = 4;
+
+pub fn client() -> Client {
+    Client::new(BASE_URL)
+}
+
+/// What to read of a subforum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchOptions {
+    /// Threads from the top of the list, pinned ones included.
+    /// Next pages of the list are fetched until there are enough.
+    pub depth: usize,
+    /// Fetch every topic for its content, author and date of the first post.
+    /// Without it, only the list is fetched and threads have no content.
+    pub fetch_topics: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            depth: DEFAULT_DEPTH,
+            fetch_topics: true,
+        }
+    }
+}
+
+impl FetchOptions {
+    /// Titles, urls and dates of `depth` threads, without fetching topics.
+    pub fn list_only(depth: usize) -> Self {
+        Self {
+            depth,
+            fetch_topics: false,
+        }
+    }
+}
+
+/// Threads of the subforum with [`FetchOptions::default`]. Topics that could not be
+/// fetched are logged and left out, the fetch fails only if every topic failed.
+pub async fn fetch_subforum_threads_list(subforum: Subforum) -> Result<Vec<NewsThreadInfo>, Error> {
+    let mut threads = Vec::new();
+    let mut errors = Vec::new();
+    for result in fetch_threads(subforum, FetchOptions::default()).await? {
+        match result {
+            Ok(thread) => threads.push(thread),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    if let Some(err) = errors.first() {
+        eprintln!(
+            "last_epoch {subforum}: {} of {} topics failed, first error: {err}",
+            errors.len(),
+            threads.len() + errors.len()
+        );
+    }
+    match (threads.is_empty(), errors.into_iter().next()) {
+        (true, Some(err)) => Err(err),
+        _ => Ok(threads),
+    }
+}
+
+/// Threads of the subforum in the order of its list. A topic that could not be fetched
+/// keeps its position as an error.
+pub async fn fetch_threads(
+    subforum: Subforum,
+    options: FetchOptions,
+) -> Result<Vec<Result<NewsThreadInfo, Error>>, Error> {
+    fetch_threads_from(&client(), subforum, options).await
+}
+
+/// [`fetch_threads`] from the forum of `client`.
+pub async fn fetch_threads_from(
+    client: &Client,
     subforum: Subforum,
-) -> Result<Vec<NewsThreadInfo>, reqwest::Error> {
-    let html = http::text(&format!("https://forum.lastepoch.com/c/{subforum}")).await?;
-    Ok(html::prepare_threads_info(&html, subforum).await)
+    options: FetchOptions,
+) -> Result<Vec<Result<NewsThreadInfo, Error>>, Error> {
+    let threads = fetch_list(client, subforum, options.depth).await?;
+    if !options.fetch_topics {
+        return Ok(threads.into_iter().map(|(_, thread)| Ok(thread)).collect());
+    }
+
+    Ok(futures::stream::iter(threads)
+        .map(|(id, listed)| async move {
+            let topic = client.topic(id).await?;
+            // A topic without posts keeps the thread of the list, without content.
+            Ok(match thread_info(client, &topic, subforum) {
+                Some(thread) => NewsThreadInfo {
+                    is_pinned: listed.is_pinned || thread.is_pinned,
+                    ..thread
+                },
+                None => listed,
+            })
+        })
+        .buffered(MAX_CONCURRENT_TOPIC_FETCHES)
+        .collect()
+        .await)
+}
+
+/// Topic ids and threads of the category list without content, following its pages
+/// until `depth`. A failed page after the first one ends the list.
+async fn fetch_list(
+    client: &Client,
+    subforum: Subforum,
+    depth: usize,
+) -> Result<Vec<(u64, NewsThreadInfo)>, Error> {
+    let slug = subforum.to_string();
+    let mut threads = Vec::new();
+    let mut page = 0;
+    while threads.len() < depth {
+        let category = match client.category_page(&slug, page).await {
+            Ok(category) => category,
+            Err(err) if page == 0 => return Err(err),
+            Err(_) => break,
+        };
+        threads.extend(category.topics().iter().map(|topic| {
+            (
+                topic.id,
+                list_thread_info(client, &category, topic, subforum),
+            )
+        }));
+        if category.topics().is_empty() || category.topic_list.more_topics_url.is_none() {
+            break;
+        }
+        page += 1;
+    }
+    threads.truncate(depth);
+    Ok(threads)
+}
+
+/// Prepares thread info from an item of the category list of Discourse JSON API.
+/// Without the topic, there is no content.
+pub fn list_thread_info(
+    client: &Client,
+    category: &CategoryPage,
+    topic: &TopicListItem,
+    subforum: Subforum,
+) -> NewsThreadInfo {
+    NewsThreadInfo {
+        url: client.topic_url(&topic.slug, topic.id),
+        title: topic.title.clone(),
+        datetime: topic.created_at,
+        content: None,
+        author: category
+            .original_poster(topic)
+            .map(|user| user.username.clone()),
+        subforum,
+        is_pinned: topic.is_pinned(),
+    }
+}
+
+/// Prepares thread info from topic of Discourse JSON API.
+pub fn thread_info(client: &Client, topic: &Topic, subforum: Subforum) -> Option<NewsThreadInfo> {
+    let post = topic.first_post()?;
+
+    let url = client.topic_url(&topic.slug, topic.id);
+    Some(NewsThreadInfo {
+        content: Some(discourse::cooked_to_markdown(&post.cooked, &url)),
+        url,
+        title: topic.title.clone(),
+        datetime: post.created_at,
+        author: Some(post.username.clone()),
+        subforum,
+        is_pinned: topic.is_pinned(),
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
@@ -40,195 +209,3 @@ impl std::fmt::Display for Subforum {
         f.write_str(s)
     }
 }
-
-pub mod html {
-    use crate::{NewsThreadInfo, Subforum};
-    use chrono::{DateTime, Utc};
-    use scraper::{ElementRef, Html, Selector};
-
-    const TITLE_SELECTOR: &str = "a.title";
-    pub const TABLE_ROW_SELECTOR: &str = "table tbody tr";
-
-    pub async fn prepare_threads_info(
-        subforum_threads_page_html: &str,
-        subforum: Subforum,
-    ) -> Vec<NewsThreadInfo> {
-        let url_title_ispinned = {
-            let document = Html::parse_document(subforum_threads_page_html);
-            let tr_selector = create_selector("table tbody tr");
-            let is_pinneds = is_pinned::read_html(&document);
-
-            document
-                .select(&tr_selector)
-                .take(3)
-                .enumerate()
-                .filter_map(|(index, row)| {
-                    Some((
-                        get_thread_url(&row)?,
-                        get_thread_title(&row)?,
-                        is_pinneds
-                            .as_ref()
-                            .and_then(|is_pinneds| is_pinneds.get(index).copied())
-                            .unwrap_or(false),
-                    ))
-                })
-                .collect::<Vec<(String, String, bool)>>()
-        };
-
-        let mut results = Vec::new();
-        for (url, title, is_pinned) in url_title_ispinned.into_iter() {
-            if let Ok(post_page_html) = http::text(&url).await {
-                let document = Html::parse_document(&post_page_html);
-
-                if let Some(datetime) = get_datetime(&document) {
-                    results.push(NewsThreadInfo {
-                        url,
-                        title,
-                        datetime,
-                        content: crate::content::get_content(&document),
-                        author: get_author(&document),
-                        subforum,
-                        is_pinned,
-                    });
-                }
-            }
-        }
-
-        results
-    }
-
-    fn get_author(document: &Html) -> Option<String> {
-        document
-            .select(&create_selector(".creator"))
-            .next()
-            .map(|creator| creator.text().collect::<String>())
-    }
-
-    pub fn create_selector(selectors: &str) -> Selector {
-        Selector::parse(selectors).unwrap()
-    }
-
-    fn get_thread_url(tr: &ElementRef) -> Option<String> {
-        Some(
-            tr.select(&create_selector(TITLE_SELECTOR))
-                .next()?
-                .attr("href")?
-                .to_owned(),
-        )
-    }
-
-    fn get_thread_title(tr: &ElementRef) -> Option<String> {
-        Some(
-            tr.select(&create_selector(TITLE_SELECTOR))
-                .next()?
-                .text()
-                .next()?
-                .trim()
-                .to_string(),
-        )
-    }
-
-    fn get_datetime(document: &Html) -> Option<DateTime<Utc>> {
-        let selector = create_selector(".topic-body time");
-        let datetime_str = document.select(&selector).next()?.attr("datetime")?;
-        let datetime: DateTime<Utc> = datetime_str.parse().ok()?;
-        Some(datetime)
-    }
-
-    /// Check if thread is pinned at the top of thread subforum thread list.
-    mod is_pinned {
-        use chrono::NaiveDate;
-        use scraper::Html;
-
-        use super::create_selector;
-        use crate::html::TABLE_ROW_SELECTOR;
-
-        /// Returns array of is_pinned bools from given html of subforum threads list.
-        pub fn read_html(document: &Html) -> Option<Vec<bool>> {
-            let naive_dates = extract_naive_dates_from_subforum_threads_list(document).unwrap();
-            Some(analyze_dates(naive_dates))
-        }
-
-        fn extract_naive_dates_from_subforum_threads_list(
-            document: &Html,
-        ) -> Option<Vec<NaiveDate>> {
-            let row_selector = crate::html::create_selector(TABLE_ROW_SELECTOR);
-            let rows = document.select(&row_selector);
-
-            let mut naive_dates = vec![];
-            for row in rows.take(4) {
-                let last_td = row.select(&create_selector("td")).last().unwrap();
-                let date_str = last_td.text().collect::<String>();
-                let date_str = date_str.trim();
-                let date = get_rough_date(date_str).ok().unwrap();
-                naive_dates.push(date);
-            }
-
-            Some(naive_dates)
-        }
-
-        fn get_rough_date(date_str: &str) -> chrono::ParseResult<NaiveDate> {
-            NaiveDate::parse_from_str(date_str, "%B %d, %Y")
-        }
-
-        /// Returns array of is_pinned bools
-        pub fn analyze_dates(naive_dates: Vec<NaiveDate>) -> Vec<bool> {
-            let mut is_pinneds: Vec<bool> = vec![];
-            let mut cur_index = naive_dates.len() - 1;
-
-            loop {
-                let cur = naive_dates[cur_index];
-                let cur_minus_one = naive_dates[cur_index - 1];
-
-                is_pinneds.push(false);
-
-                cur_index -= 1;
-                if cur > cur_minus_one || cur_index == 0 {
-                    break;
-                }
-            }
-
-            while is_pinneds.len() != naive_dates.len() {
-                is_pinneds.push(true);
-            }
-
-            is_pinneds.reverse();
-
-            is_pinneds
-        }
-
-        #[cfg(test)]
-        mod is_pinned_tests {
-            use chrono::NaiveDate;
-
-            #[test]
-            fn analyze_dates() {
-                fn test_group(expected: Vec<bool>, date_strs: Vec<&str>) {
-                    let parse_naive_date =
-                        |date_str| NaiveDate::parse_from_str(date_str, "%B %d, %Y").unwrap();
-                    let naive_dates = date_strs
-                        .into_iter()
-                        .map(parse_naive_date)
-                        .collect::<Vec<_>>();
-
-                    assert_eq!(expected, super::analyze_dates(naive_dates))
-                }
-
-                test_group(
-                    vec![true, true, false, false],
-                    vec![
-                        "March 7, 2022",
-                        "March 7, 2022",
-                        "May 7, 2025",
-                        "April 8, 2025",
-                    ],
-                );
-
-                test_group(
-                    vec![true, false, false],
-                    vec!["August 26, 2021", "May 15, 2025", "May 14, 2025"],
-                );
-            }
-        }
-    }
-}
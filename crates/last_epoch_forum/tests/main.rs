@@ -1,55 +1,148 @@
 use chrono::{DateTime, Utc};
-use last_epoch_forum::{NewsThreadInfo, Subforum};
+use last_epoch_forum::{FetchOptions, NewsThreadInfo, Subforum};
+use markdown::patch_notes::Section;
 use scraper::Html;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
 
-#[tokio::test]
-async fn prepares_threads_info_based_on_markup() {
-    let html = std::fs::read_to_string("./tests/fixtures/news.html").unwrap();
-    let threads = last_epoch_forum::html::prepare_threads_info(&html, Subforum::News).await;
-
-    assert_eq!(3, threads.len());
-
-    #[derive(PartialEq)]
-    struct PartialInfo {
-        url: String,
-        title: String,
-        datetime: DateTime<Utc>,
-    }
-
-    impl From<NewsThreadInfo> for PartialInfo {
-        fn from(value: NewsThreadInfo) -> Self {
-            PartialInfo {
-                url: value.url,
-                title: value.title,
-                datetime: value.datetime,
-            }
+/// Topic of the 1.2.3 patch notes.
+const TOPIC_JSON: &str = "tests/fixtures/topic.json";
+/// First page of the news category.
+const CATEGORY_JSON: &str = "tests/fixtures/category_page_0.json";
+
+/// Serves fixtures by path and query of the request, anything else is not found.
+/// Returns the base url of the server.
+async fn serve(routes: Vec<(&'static str, &'static str)>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let routes = routes.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split(' ').nth(1).unwrap_or_default();
+                let (status, body) = match routes.iter().find(|(route, _)| *route == path) {
+                    Some((_, fixture)) => ("200 OK", std::fs::read_to_string(fixture).unwrap()),
+                    None => ("404 Not Found", String::new()),
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            });
         }
-    }
+    });
+    format!("http://{addr}")
+}
 
-    let partials = threads
-        .into_iter()
-        .map(PartialInfo::from)
-        .collect::<Vec<_>>();
+#[test]
+fn reads_threads_list_from_category_json() {
+    let json = std::fs::read_to_string(CATEGORY_JSON).unwrap();
+    let category = discourse::parse_category(&json).unwrap();
+    let client = last_epoch_forum::client();
+    let threads: Vec<NewsThreadInfo> = category
+        .topics()
+        .iter()
+        .map(|topic| last_epoch_forum::list_thread_info(&client, &category, topic, Subforum::News))
+        .collect();
 
-    let a = PartialInfo {
-        url: "https://forum.lastepoch.com/t/last-epoch-season-2-tombs-of-the-erased-is-officially-live/75431".to_owned(),
-        title: "Last Epoch - Season 2 - Tombs of the Erased is officially LIVE!".to_owned(),
-        datetime: "2025-04-17T16:01:00Z".parse().unwrap(),
-    };
+    assert_eq!(threads.len(), category.topics().len());
+    assert!(threads[0].is_pinned);
+    assert!(!threads[1].is_pinned);
+    assert!(threads.iter().all(|thread| thread.content.is_none()));
+    assert_eq!(threads[1].author.as_deref(), Some("EHG_Kain"));
+}
 
-    let b = PartialInfo {
-        url: "https://forum.lastepoch.com/t/supporter-packs-now-available/75419".to_owned(),
-        title: "Supporter Packs now Available!".to_owned(),
-        datetime: "2025-04-16T18:40:00Z".parse().unwrap(),
+#[tokio::test]
+async fn reads_threads_list_across_pages() {
+    let base_url = serve(vec![
+        ("/c/news.json?page=0", CATEGORY_JSON),
+        ("/c/news.json?page=1", "tests/fixtures/category_page_1.json"),
+    ])
+    .await;
+    let client = discourse::Client::new(base_url);
+    let read = |depth| {
+        let client = client.clone();
+        async move {
+            last_epoch_forum::fetch_threads_from(
+                &client,
+                Subforum::News,
+                FetchOptions::list_only(depth),
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|thread| thread.unwrap())
+            .collect::<Vec<_>>()
+        }
     };
 
-    let c = PartialInfo {
-        url: "https://forum.lastepoch.com/t/48-hours-till-go-live/75386".to_owned(),
-        title: "48 Hours till go live".to_owned(),
-        datetime: "2025-04-15T16:02:00Z".parse().unwrap(),
-    };
+    let threads = read(20).await;
+    assert_eq!(threads.len(), 11);
+    assert!(threads[0].is_pinned);
+    assert!(threads[1..].iter().all(|thread| !thread.is_pinned));
+    assert!(threads.iter().all(|thread| thread.content.is_none()));
+    assert_eq!(threads[9].title, "Hotfix 1.2.1.2");
+    assert_eq!(threads[10].title, "Season 2 roadmap");
+    assert_eq!(threads[10].author.as_deref(), Some("EHG_Kain"));
+
+    let threads = read(3).await;
+    assert_eq!(
+        threads
+            .iter()
+            .map(|thread| thread.url.rsplit('/').next().unwrap())
+            .collect::<Vec<_>>(),
+        ["2", "75431", "75419"]
+    );
+}
+
+#[tokio::test]
+async fn keeps_failed_topics_in_list_order() {
+    let base_url = serve(vec![
+        ("/c/news.json?page=0", CATEGORY_JSON),
+        ("/t/75431.json", TOPIC_JSON),
+    ])
+    .await;
+    let client = discourse::Client::new(base_url);
+    let threads = last_epoch_forum::fetch_threads_from(
+        &client,
+        Subforum::News,
+        FetchOptions {
+            depth: 3,
+            fetch_topics: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(threads.len(), 3);
+    assert!(threads[0].is_err());
+    let thread = threads[1].as_ref().unwrap();
+    assert_eq!(thread.title, "Last Epoch Patch 1.2.3 Notes");
+    assert_eq!(thread.author.as_deref(), Some("Averielle"));
+    assert!(thread.content.is_some());
+    assert!(threads[2].is_err());
+}
+
+#[tokio::test]
+async fn fails_without_list() {
+    let client = discourse::Client::new(serve(Vec::new()).await);
+    let result =
+        last_epoch_forum::fetch_threads_from(&client, Subforum::News, FetchOptions::default())
+            .await;
 
-    assert!([a, b, c].iter().all(|t| partials.contains(t)));
+    assert!(result.is_err());
 }
 
 #[tokio::test]
@@ -62,10 +155,98 @@ async fn fetches() {
 #[test]
 fn parses_body_markdown() {
     let html = std::fs::read_to_string("tests/fixtures/1.2.3/input.html").unwrap();
-    let actual = last_epoch_forum::content::get_content(&Html::parse_document(&html));
+    let actual = last_epoch_forum::content::get_content(
+        &Html::parse_document(&html),
+        "https://forum.lastepoch.com/t/patch-notes-1-2-3/1",
+    );
 
     assert!(actual.is_some());
 
     let expected = std::fs::read_to_string("tests/fixtures/1.2.3/expected.md").unwrap();
     assert_eq!(expected, actual.unwrap());
 }
+
+#[test]
+fn prepares_thread_info_from_topic_json() {
+    let json = std::fs::read_to_string(TOPIC_JSON).unwrap();
+    let topic = discourse::parse_topic(&json).unwrap();
+    let thread =
+        last_epoch_forum::thread_info(&last_epoch_forum::client(), &topic, Subforum::PatchNotes)
+            .unwrap();
+
+    assert_eq!(
+        thread.url,
+        "https://forum.lastepoch.com/t/last-epoch-patch-1-2-3-notes/77541"
+    );
+    assert_eq!(thread.title, "Last Epoch Patch 1.2.3 Notes");
+    assert_eq!(
+        thread.datetime,
+        "2025-05-08T16:00:00Z".parse::<DateTime<Utc>>().unwrap()
+    );
+    assert_eq!(thread.author.as_deref(), Some("Averielle"));
+    assert!(!thread.is_pinned);
+
+    let expected = std::fs::read_to_string("tests/fixtures/1.2.3/expected.md").unwrap();
+    assert_eq!(expected, thread.content.unwrap());
+}
+
+#[test]
+fn parses_patch_notes() {
+    let html = std::fs::read_to_string("tests/fixtures/1.2.3/input.html").unwrap();
+    let notes = last_epoch_forum::content::get_patch_notes(&Html::parse_document(&html)).unwrap();
+
+    assert_eq!(notes.version.as_deref(), Some("1.2.3"));
+    assert_eq!(notes.title, None);
+    assert!(notes.text[0].starts_with("The team is currently aware of a bug with WASD"));
+
+    let titles = |sections: &[Section]| -> Vec<String> {
+        sections
+            .iter()
+            .map(|section| section.title.clone())
+            .collect()
+    };
+    assert_eq!(
+        titles(&notes.sections),
+        ["Bug Fixes", "Improvements", "Known Issues"]
+    );
+    let bug_fixes = &notes.sections[0];
+    assert_eq!(
+        titles(&bug_fixes.subsections),
+        [
+            "Skills",
+            "Imprinted Items",
+            "Dungeon, Monolith and Quest",
+            "Items",
+            "Input",
+            "UI/Visual",
+            "Other"
+        ]
+    );
+    assert_eq!(bug_fixes.subsections[0].items.len(), 18);
+
+    let imprinted = &bug_fixes.subsections[1];
+    assert_eq!(
+        imprinted.text,
+        ["Made a number of fixes and changes to Imprinted items:"]
+    );
+    assert_eq!(imprinted.items.len(), 7);
+
+    let tangled_lasers = bug_fixes.subsections[6]
+        .items
+        .iter()
+        .find(|item| item.text.starts_with("Fixed the Tangled Lasers"))
+        .unwrap();
+    assert_eq!(
+        tangled_lasers.children[0].text,
+        "Tangled Lasers now deal 52% less damage."
+    );
+
+    // Same notes from the JSON API.
+    let json = std::fs::read_to_string(TOPIC_JSON).unwrap();
+    let topic = discourse::parse_topic(&json).unwrap();
+    let from_json = last_epoch_forum::content::cooked_to_patch_notes(
+        &topic.title,
+        &topic.first_post().unwrap().cooked,
+    );
+    assert_eq!(from_json, notes);
+}
@@ -0,0 +1,308 @@
+//! PoE forum colours as Discord ```` ```ansi ```` code blocks.
+//!
+//! Coloured spans are marked while converting, and a paragraph with marks becomes
+//! an ANSI block, unless it has links that would stop working there. List items
+//! can't hold code blocks and lose their colours. Item tooltips become ANSI blocks
+//! with the colours of their frame, properties and mods.
+use scraper::{ElementRef, Node};
+
+/// Starts a coloured span, followed by the ANSI code and [`CODE_END`].
+const OPEN: char = '\u{e000}';
+const CODE_END: char = '\u{e001}';
+/// Ends the last opened span.
+const CLOSE: char = '\u{e002}';
+
+const GRAY: &str = "30";
+const RED: &str = "31";
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const BLUE: &str = "34";
+const PINK: &str = "35";
+const CYAN: &str = "36";
+const WHITE: &str = "37";
+const BOLD_YELLOW: &str = "1;33";
+
+/// Discord colours of ANSI codes, to find the closest one to a css colour.
+const PALETTE: [(&str, (u8, u8, u8)); 8] = [
+    (GRAY, (0x4f, 0x54, 0x5c)),
+    (RED, (0xdc, 0x32, 0x2f)),
+    (GREEN, (0x85, 0x99, 0x00)),
+    (YELLOW, (0xb5, 0x89, 0x00)),
+    (BLUE, (0x26, 0x8b, 0xd2)),
+    (PINK, (0xd3, 0x36, 0x82)),
+    (CYAN, (0x2a, 0xa1, 0x98)),
+    (WHITE, (0xff, 0xff, 0xff)),
+];
+
+/// Forum colour classes without `colour` prefix and `Popup` suffix, lowercased,
+/// e.g. `colourUnique`, `uniquePopup` and `explicitMod`.
+const CLASS_COLOURS: [(&str, &str); 26] = [
+    ("default", WHITE),
+    ("normal", WHITE),
+    ("magic", BLUE),
+    ("rare", BOLD_YELLOW),
+    ("unique", YELLOW),
+    ("gem", CYAN),
+    ("currency", YELLOW),
+    ("divination", BLUE),
+    ("divinationcard", BLUE),
+    ("quest", GREEN),
+    ("prophecy", PINK),
+    ("relic", GREEN),
+    ("fractured", YELLOW),
+    ("fracturedmod", YELLOW),
+    ("crafted", BLUE),
+    ("craftedmod", BLUE),
+    ("enchanted", BLUE),
+    ("enchantmod", BLUE),
+    ("augmented", BLUE),
+    ("implicitmod", BLUE),
+    ("explicitmod", BLUE),
+    ("utilitymod", BLUE),
+    ("corrupted", RED),
+    ("flavourtext", YELLOW),
+    ("descrtext", GRAY),
+    ("secdescrtext", GRAY),
+];
+
+/// Named css colours used on the forum.
+const NAMED_COLOURS: [(&str, (u8, u8, u8)); 10] = [
+    ("red", (0xff, 0x00, 0x00)),
+    ("green", (0x00, 0x80, 0x00)),
+    ("lime", (0x00, 0xff, 0x00)),
+    ("blue", (0x00, 0x00, 0xff)),
+    ("yellow", (0xff, 0xff, 0x00)),
+    ("orange", (0xff, 0xa5, 0x00)),
+    ("purple", (0x80, 0x00, 0x80)),
+    ("teal", (0x00, 0x80, 0x80)),
+    ("gray", (0x80, 0x80, 0x80)),
+    ("grey", (0x80, 0x80, 0x80)),
+];
+
+/// ANSI code of a coloured inline element, from its forum colour class,
+/// `style` or `color` attribute. White is the colour of text anyway.
+pub fn colour(element: &ElementRef) -> Option<&'static str> {
+    let code = element
+        .value()
+        .classes()
+        .filter(|class| class.starts_with("colour"))
+        .find_map(class_colour)
+        .or_else(|| {
+            let style = element.attr("style").unwrap_or_default();
+            style
+                .split(';')
+                .filter_map(|declaration| declaration.split_once(':'))
+                .find(|(property, _)| property.trim().eq_ignore_ascii_case("color"))
+                .map(|(_, value)| value)
+                .or(element.attr("color"))
+                .and_then(css_colour)
+        })?;
+    (code != WHITE).then_some(code)
+}
+
+/// Marks the markdown of an inline element as coloured.
+pub fn mark(markdown: &str, code: &str) -> String {
+    match markdown.trim().is_empty() {
+        true => markdown.to_owned(),
+        false => format!("{OPEN}{code}{CODE_END}{markdown}{CLOSE}"),
+    }
+}
+
+/// Paragraph with coloured spans as an ANSI block. With links, the colours are dropped.
+pub fn paragraph(markdown: &str) -> String {
+    if !markdown.contains(OPEN) {
+        return markdown.to_owned();
+    }
+    if markdown.contains("](") || markdown.contains("http://") || markdown.contains("https://") {
+        return strip(markdown);
+    }
+    code_block(&paint(markdown))
+}
+
+/// Markdown without colour marks.
+pub fn strip(markdown: &str) -> String {
+    let mut stripped = String::with_capacity(markdown.len());
+    let mut in_code = false;
+    for c in markdown.chars() {
+        match c {
+            OPEN => in_code = true,
+            CODE_END => in_code = false,
+            CLOSE => {}
+            _ if in_code => {}
+            _ => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+/// Plain text of marked markdown with ANSI colours, without escapes and emphasis.
+fn paint(markdown: &str) -> String {
+    let mut painted = String::with_capacity(markdown.len());
+    let mut codes: Vec<String> = Vec::new();
+    let mut chars = markdown.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => painted.extend(chars.next()),
+            OPEN => {
+                let code: String = chars.by_ref().take_while(|c| *c != CODE_END).collect();
+                painted.push_str(&sgr(&code));
+                codes.push(code);
+            }
+            CLOSE => {
+                codes.pop();
+                painted.push_str(&sgr(codes.last().map_or("0", String::as_str)));
+            }
+            // Emphasis and code span markers.
+            '*' | '`' => {}
+            '_' | '~' | '|' if chars.peek() == Some(&c) => {
+                chars.next();
+            }
+            _ => painted.push(c),
+        }
+    }
+    painted
+}
+
+/// Item tooltip as an ANSI block: name in the colour of the frame,
+/// then properties, requirements and mods between separators.
+pub fn item(element: &ElementRef) -> String {
+    let frame = element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .flat_map(|element| element.value().classes())
+        .filter(|class| class.ends_with("Popup"))
+        .find_map(class_colour)
+        .unwrap_or(WHITE);
+
+    let mut lines: Vec<String> = Vec::new();
+    for element in element.descendants().skip(1).filter_map(ElementRef::wrap) {
+        let classes: Vec<&str> = element.value().classes().collect();
+        if classes.contains(&"itemName") {
+            lines.push(paint_line(&text(&element), frame));
+        } else if classes.contains(&"separator") {
+            lines.push(paint_line(crate::convert::RULE, GRAY));
+        } else if element
+            .parent()
+            .and_then(ElementRef::wrap)
+            .is_some_and(|parent| parent.value().classes().any(|class| class == "content"))
+        {
+            let code = classes
+                .iter()
+                .copied()
+                .find_map(class_colour)
+                .unwrap_or(GRAY);
+            lines.extend(
+                coloured_text(&element, code)
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(|line| format!("{}{line}{}", sgr(code), sgr("0"))),
+            );
+        }
+    }
+    // Separators only between lines.
+    while lines
+        .last()
+        .is_some_and(|line| line.contains(crate::convert::RULE))
+    {
+        lines.pop();
+    }
+    code_block(&lines.join("\n"))
+}
+
+fn paint_line(text: &str, code: &str) -> String {
+    format!("{}{}{}", sgr(code), text.trim(), sgr("0"))
+}
+
+/// Text of the element with its coloured spans painted, in the `code` colour otherwise.
+fn coloured_text(element: &ElementRef, code: &str) -> String {
+    let mut text = String::new();
+    for node in element.children() {
+        match ElementRef::wrap(node) {
+            Some(child) if child.value().name() == "br" => text.push('\n'),
+            Some(child) => match child.value().classes().find_map(class_colour) {
+                Some(child_code) => {
+                    text.push_str(&sgr(child_code));
+                    text.push_str(&coloured_text(&child, child_code));
+                    text.push_str(&sgr(code));
+                }
+                None => text.push_str(&coloured_text(&child, code)),
+            },
+            None => {
+                if let Node::Text(node_text) = node.value() {
+                    text.push_str(&crate::convert::collapse_whitespace(node_text));
+                }
+            }
+        }
+    }
+    text
+}
+
+fn text(element: &ElementRef) -> String {
+    crate::convert::collapse_whitespace(&element.text().collect::<String>())
+}
+
+fn class_colour(class: &str) -> Option<&'static str> {
+    let class = class.to_lowercase();
+    let name = class.strip_prefix("colour").unwrap_or(&class);
+    let name = name.strip_suffix("popup").unwrap_or(name);
+    CLASS_COLOURS
+        .iter()
+        .find(|(class_name, _)| *class_name == name)
+        .map(|(_, code)| *code)
+}
+
+/// Closest ANSI colour of a css colour, e.g. `#ff0000`, `#f00`, `rgb(255, 0, 0)` or `red`.
+fn css_colour(value: &str) -> Option<&'static str> {
+    let value = value.trim().to_lowercase();
+    let rgb = if let Some(hex) = value.strip_prefix('#') {
+        let channel = |i: usize, len: usize| {
+            let channel = u8::from_str_radix(hex.get(i * len..(i + 1) * len)?, 16).ok()?;
+            Some(if len == 1 { channel * 17 } else { channel })
+        };
+        let len = match hex.len() {
+            3 => 1,
+            6 => 2,
+            _ => return None,
+        };
+        (channel(0, len)?, channel(1, len)?, channel(2, len)?)
+    } else if let Some(channels) = value
+        .strip_prefix("rgb(")
+        .and_then(|value| value.strip_suffix(')'))
+    {
+        let channels: Vec<u8> = channels
+            .split(',')
+            .filter_map(|channel| channel.trim().parse().ok())
+            .collect();
+        match channels[..] {
+            [r, g, b] => (r, g, b),
+            _ => return None,
+        }
+    } else {
+        NAMED_COLOURS
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, rgb)| *rgb)?
+    };
+
+    let distance = |(r, g, b): (u8, u8, u8)| {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(r, rgb.0) + d(g, rgb.1) + d(b, rgb.2)
+    };
+    PALETTE
+        .iter()
+        .min_by_key(|(_, palette_rgb)| distance(*palette_rgb))
+        .map(|(code, _)| *code)
+}
+
+/// Select Graphic Rendition sequence, e.g. `ESC[0;33m`.
+fn sgr(code: &str) -> String {
+    match code {
+        "0" => "\u{1b}[0m".to_owned(),
+        code => format!("\u{1b}[0;{code}m"),
+    }
+}
+
+fn code_block(text: &str) -> String {
+    format!("```ansi\n{}\n```", text.replace("```", "`\u{200b}``"))
+}
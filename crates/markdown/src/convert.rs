@@ -0,0 +1,590 @@
+//! HTML to Discord flavoured markdown.
+//!
+//! Block elements become paragraphs separated by blank lines, inline elements
+//! become emphasis, links and code spans. Discord has no tables, so they are
+//! aligned in code blocks, and no small text, so `<small>` becomes `-#` subtext.
+//! Coloured text and item tooltips become ANSI code blocks, see [`crate::ansi`].
+use crate::{ansi, video_url};
+use scraper::{ElementRef, Node};
+use url::Url;
+
+/// Elements without content to show.
+const SKIPPED: [&str; 9] = [
+    "script", "style", "input", "button", "noscript", "template", "meta", "link", "head",
+];
+
+/// Elements that only separate their content from the surrounding blocks.
+const CONTAINERS: [&str; 21] = [
+    "p", "div", "section", "article", "aside", "header", "footer", "main", "nav", "figure",
+    "details", "center", "dl", "dt", "dd", "form", "fieldset", "address", "body", "html", "tbody",
+];
+
+/// Elements rendered by [`block`], except spoilers that are divs.
+const BLOCKS: [&str; 16] = [
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ul",
+    "ol",
+    "li",
+    "pre",
+    "blockquote",
+    "table",
+    "hr",
+    "small",
+    "figcaption",
+    "summary",
+];
+
+/// Discord headings go down to `###`, lower ones are bold lines.
+const MAX_HEADING_LEVEL: usize = 3;
+
+/// Horizontal rule, Discord has none.
+pub(crate) const RULE: &str = "───";
+
+/// Markdown of the children of the element. Links and images are resolved
+/// against `base_url`, the url of the page, see [`crate::resolve_url`].
+pub fn html_to_markdown(element: &ElementRef, base_url: &str) -> String {
+    Converter {
+        base_url: Url::parse(base_url).ok(),
+    }
+    .markdown(element)
+}
+
+struct Converter {
+    base_url: Option<Url>,
+}
+
+/// Blocks of markdown, with inline content collected until the next block.
+struct Blocks<'a> {
+    converter: &'a Converter,
+    blocks: Vec<String>,
+    inline: String,
+    /// Paragraphs with coloured spans become ANSI blocks, see [`ansi::paragraph`].
+    colours: bool,
+}
+
+impl Blocks<'_> {
+    fn children(&mut self, element: &ElementRef) {
+        for node in element.children() {
+            match ElementRef::wrap(node) {
+                Some(child) => self.element(&child),
+                None => {
+                    if let Node::Text(text) = node.value() {
+                        self.inline.push_str(&escape(&collapse_whitespace(text)));
+                    }
+                }
+            }
+        }
+    }
+
+    fn element(&mut self, element: &ElementRef) {
+        let name = element.value().name();
+        if let Some(block) = self.converter.block(element) {
+            self.flush();
+            if !block.trim().is_empty() {
+                self.blocks.push(block);
+            }
+        } else if CONTAINERS.contains(&name) || has_blocks(element) {
+            self.flush();
+            self.children(element);
+            self.flush();
+        } else {
+            self.inline.push_str(&self.converter.inline(element));
+        }
+    }
+
+    /// Ends the current paragraph.
+    fn flush(&mut self) {
+        let paragraph = normalize_lines(&std::mem::take(&mut self.inline));
+        let paragraph = match self.colours {
+            true => paragraph
+                .split("\n\n")
+                .map(ansi::paragraph)
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            false => paragraph,
+        };
+        if !paragraph.is_empty() {
+            self.blocks.push(paragraph);
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        self.blocks.join("\n\n")
+    }
+}
+
+/// Inline element with blocks inside, e.g. `<strong><ul>...</ul></strong>`,
+/// is read as a container so that the blocks keep their lines.
+fn has_blocks(element: &ElementRef) -> bool {
+    element
+        .descendants()
+        .skip(1)
+        .filter_map(ElementRef::wrap)
+        .any(|descendant| {
+            let name = descendant.value().name();
+            BLOCKS.contains(&name) || CONTAINERS.contains(&name)
+        })
+}
+
+impl Converter {
+    /// Markdown of the children of the element.
+    fn markdown(&self, element: &ElementRef) -> String {
+        self.blocks_markdown(element, true)
+    }
+
+    /// Markdown of a list item. A code block can't be a part of a bullet,
+    /// so coloured spans lose their colours.
+    fn list_item_markdown(&self, element: &ElementRef) -> String {
+        self.blocks_markdown(element, false)
+    }
+
+    fn blocks_markdown(&self, element: &ElementRef, colours: bool) -> String {
+        let mut blocks = Blocks {
+            converter: self,
+            blocks: Vec::new(),
+            inline: String::new(),
+            colours,
+        };
+        blocks.children(element);
+        ansi::strip(&blocks.finish())
+    }
+
+    fn resolve(&self, url: &str) -> String {
+        crate::resolve(self.base_url.as_ref(), url)
+    }
+
+    /// Markdown of a block element, None if the element is not a block.
+    fn block(&self, element: &ElementRef) -> Option<String> {
+        let name = element.value().name();
+        let markdown = match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level: usize = name[1..].parse().unwrap();
+                let text = escape(&collapse_whitespace(&element.text().collect::<String>()));
+                let text = text.trim();
+                match level <= MAX_HEADING_LEVEL {
+                    true => format!("{} {text}", "#".repeat(level)),
+                    false => emphasize(text, "**"),
+                }
+            }
+            "ul" | "ol" => self.list(element, name == "ol"),
+            "li" => list_item("-", &self.list_item_markdown(element)),
+            "pre" => code_block(element),
+            "blockquote" => prefix_lines(&self.markdown(element), "> ", "> "),
+            "table" => table(element),
+            "hr" => RULE.to_owned(),
+            "small" | "figcaption" => {
+                let text = normalize_lines(&self.inline_children(element));
+                prefix_lines(&text, "-# ", "-# ")
+            }
+            "summary" => emphasize(&normalize_lines(&self.inline_children(element)), "**"),
+            "div" if has_class(element, "spoiler") => self.spoiler(element),
+            "div"
+                if has_class(element, "itemPopupContainer")
+                    || has_class(element, "newItemPopup") =>
+            {
+                ansi::item(element)
+            }
+            _ => return None,
+        };
+        Some(markdown)
+    }
+
+    /// Markdown of an inline element, marked if it is coloured, see [`ansi::paragraph`].
+    fn inline(&self, element: &ElementRef) -> String {
+        let markdown = self.inline_element(element);
+        match ansi::colour(element) {
+            Some(code) => ansi::mark(&markdown, code),
+            None => markdown,
+        }
+    }
+
+    fn inline_element(&self, element: &ElementRef) -> String {
+        let name = element.value().name();
+        match name {
+            _ if SKIPPED.contains(&name) => String::new(),
+            "br" => "\n".to_owned(),
+            "strong" | "b" => emphasize(&self.inline_children(element), "**"),
+            "em" | "i" | "cite" => emphasize(&self.inline_children(element), "*"),
+            "u" | "ins" => emphasize(&self.inline_children(element), "__"),
+            "s" | "strike" | "del" => emphasize(&self.inline_children(element), "~~"),
+            "code" | "kbd" | "samp" => {
+                code_span(&collapse_whitespace(&element.text().collect::<String>()))
+            }
+            "a" => self.link(element),
+            "img" => match element.attr("src") {
+                Some(src) => self.image(element, src),
+                None => String::new(),
+            },
+            "iframe" => match element
+                .attr("src")
+                .and_then(|src| video_url(&self.resolve(src)))
+            {
+                Some(url) => {
+                    let text = element
+                        .attr("title")
+                        .map(|title| format!("Video {title}"))
+                        .unwrap_or("Video".to_owned());
+                    format!("\n\n[{text}]({url})\n\n")
+                }
+                None => String::new(),
+            },
+            "span" => {
+                let style = element.attr("style").unwrap_or_default().replace(' ', "");
+                let mut text = self.inline_children(element);
+                for (property, marker) in [
+                    ("font-weight:bold", "**"),
+                    ("font-style:italic", "*"),
+                    ("text-decoration:underline", "__"),
+                    ("text-decoration:line-through", "~~"),
+                ] {
+                    if style.contains(property) {
+                        text = emphasize(&text, marker);
+                    }
+                }
+                text
+            }
+            // Block inside inline content, e.g. a paragraph inside a link.
+            _ if BLOCKS.contains(&name) || CONTAINERS.contains(&name) => {
+                format!("\n\n{}\n\n", self.markdown(element))
+            }
+            _ => self.inline_children(element),
+        }
+    }
+
+    fn inline_children(&self, element: &ElementRef) -> String {
+        let mut markdown = String::new();
+        for node in element.children() {
+            match ElementRef::wrap(node) {
+                Some(child) => markdown.push_str(&self.inline(&child)),
+                None => {
+                    if let Node::Text(text) = node.value() {
+                        markdown.push_str(&escape(&collapse_whitespace(text)));
+                    }
+                }
+            }
+        }
+        markdown
+    }
+
+    /// Image as a paragraph with a link, e.g. `[Image Huntress](https://...)`.
+    fn image(&self, img: &ElementRef, src: &str) -> String {
+        let text = img
+            .attr("alt")
+            .map(|alt| format!("Image {alt}"))
+            .unwrap_or("Image".to_owned());
+        format!("\n\n[{text}]({})\n\n", self.resolve(src))
+    }
+
+    fn link(&self, a: &ElementRef) -> String {
+        let href = self.resolve(a.attr("href").unwrap_or_default());
+        let href = href.as_str();
+        let is_page_link = href.starts_with("http") || href.starts_with('/');
+
+        // Linked image links to the page, not to the image.
+        if let Some(img) = a
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .find(|element| element.value().name() == "img")
+        {
+            return match is_page_link {
+                true => self.image(&img, href),
+                false => self.inline_children(a),
+            };
+        }
+
+        let text = self.inline_children(a);
+        let text = normalize_lines(&text).replace('\n', " ");
+        match (is_page_link, text.is_empty()) {
+            (false, _) => text,
+            (true, true) => href.to_owned(),
+            (true, false) if text == href || text == escape(href) => href.to_owned(),
+            (true, false) => format!("[{text}]({href})"),
+        }
+    }
+
+    /// List with nested lists indented under their items.
+    fn list(&self, element: &ElementRef, ordered: bool) -> String {
+        let mut number: usize = element
+            .attr("start")
+            .and_then(|start| start.parse().ok())
+            .unwrap_or(1);
+        let mut items: Vec<String> = Vec::new();
+        for child in element.children().filter_map(ElementRef::wrap) {
+            match child.value().name() {
+                "li" => {
+                    if let Some(value) = child.attr("value").and_then(|value| value.parse().ok()) {
+                        number = value;
+                    }
+                    let content = self.list_item_markdown(&child);
+                    if content.is_empty() {
+                        continue;
+                    }
+                    let marker = match ordered {
+                        true => format!("{number}."),
+                        false => "-".to_owned(),
+                    };
+                    items.push(list_item(&marker, &content));
+                    number += 1;
+                }
+                // List right inside a list belongs to the item before it.
+                "ul" | "ol" => {
+                    let nested = self.list(&child, child.value().name() == "ol");
+                    match items.last_mut() {
+                        Some(item) if !nested.is_empty() => {
+                            item.push('\n');
+                            item.push_str(&prefix_lines(&nested, "  ", "  "));
+                        }
+                        _ => items.push(nested),
+                    }
+                }
+                _ => {}
+            }
+        }
+        items.join("\n")
+    }
+
+    /// PoE forum spoiler, its title in bold and every paragraph hidden.
+    /// Images and videos stay visible, Discord can't hide their embeds.
+    fn spoiler(&self, element: &ElementRef) -> String {
+        let title = element
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .find(|element| has_class(element, "spoilerTitle"))
+            .map(|title| {
+                collapse_whitespace(&title.text().collect::<String>())
+                    .trim()
+                    .to_owned()
+            })
+            .unwrap_or_default();
+        let content = element
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .find(|element| has_class(element, "spoilerContent"))
+            .map(|content| self.markdown(&content))
+            .unwrap_or_default();
+
+        let paragraphs = content.split("\n\n").map(|paragraph| {
+            let is_media = !paragraph.contains('\n')
+                && (paragraph.starts_with("[Image") || paragraph.starts_with("[Video"))
+                && paragraph.ends_with(')');
+            match is_media || paragraph.starts_with("```") {
+                true => paragraph.to_owned(),
+                false => format!("||{paragraph}||"),
+            }
+        });
+        std::iter::once(emphasize(&escape(&title), "**"))
+            .filter(|title| !title.is_empty())
+            .chain(paragraphs.filter(|paragraph| paragraph != "||||"))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Wraps text in markers, e.g. `**bold**`, line by line.
+/// Surrounding whitespace stays outside, Discord ignores `** bold **`.
+fn emphasize(text: &str, marker: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let trimmed = line.trim();
+            // Already emphasized, but `**bold**` is not `*italic*`.
+            let is_wrapped = trimmed.len() > 2 * marker.len()
+                && trimmed.starts_with(marker)
+                && trimmed.ends_with(marker)
+                && !trimmed[marker.len()..].starts_with(&marker[..1]);
+            if trimmed.is_empty() || is_wrapped {
+                return line.to_owned();
+            }
+            let leading = &line[..line.len() - line.trim_start().len()];
+            let trailing = &line[line.trim_end().len()..];
+            format!("{leading}{marker}{trimmed}{marker}{trailing}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn code_span(text: &str) -> String {
+    let text = text.trim();
+    match (text.is_empty(), text.contains('`')) {
+        (true, _) => String::new(),
+        (false, true) => format!("`` {text} ``"),
+        (false, false) => format!("`{text}`"),
+    }
+}
+
+fn code_block(pre: &ElementRef) -> String {
+    let language = std::iter::once(*pre)
+        .chain(pre.children().filter_map(ElementRef::wrap))
+        .flat_map(|element| element.value().classes())
+        .find_map(|class| {
+            class
+                .strip_prefix("lang-")
+                .or_else(|| class.strip_prefix("language-"))
+        })
+        .unwrap_or_default();
+    let text: String = pre.text().collect();
+    format!(
+        "```{language}\n{}\n```",
+        text.trim_matches('\n')
+            .trim_end()
+            .replace("```", "`\u{200b}``")
+    )
+}
+
+/// Item with its lines after the first one indented under the marker.
+fn list_item(marker: &str, content: &str) -> String {
+    let content: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let indent = " ".repeat(marker.chars().count() + 1);
+    prefix_lines(&content.join("\n"), &format!("{marker} "), &indent)
+}
+
+/// Table as a code block with aligned columns.
+fn table(element: &ElementRef) -> String {
+    let rows: Vec<(bool, Vec<String>)> = element
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|element| element.value().name() == "tr")
+        .map(|tr| {
+            let cells: Vec<ElementRef> = tr
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                .collect();
+            let is_header =
+                !cells.is_empty() && cells.iter().all(|cell| cell.value().name() == "th");
+            let cells = cells
+                .iter()
+                .map(|cell| {
+                    collapse_whitespace(&cell.text().collect::<String>())
+                        .trim()
+                        .to_owned()
+                })
+                .collect();
+            (is_header, cells)
+        })
+        .filter(|(_, cells): &(bool, Vec<String>)| cells.iter().any(|cell| !cell.is_empty()))
+        .collect();
+    if rows.is_empty() {
+        return String::new();
+    }
+
+    let columns = rows.iter().map(|(_, cells)| cells.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|(_, cells)| cells.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut lines = Vec::new();
+    for (i, (is_header, cells)) in rows.iter().enumerate() {
+        let line = widths
+            .iter()
+            .enumerate()
+            .map(|(column, width)| {
+                let cell = cells.get(column).map(String::as_str).unwrap_or_default();
+                format!("{cell}{}", " ".repeat(width - cell.chars().count()))
+            })
+            .collect::<Vec<_>>()
+            .join(" | ");
+        lines.push(line.trim_end().to_owned());
+        let next_is_header = rows.get(i + 1).is_some_and(|(is_header, _)| *is_header);
+        if *is_header && !next_is_header {
+            lines.push(
+                widths
+                    .iter()
+                    .map(|width| "-".repeat(*width))
+                    .collect::<Vec<_>>()
+                    .join("-+-"),
+            );
+        }
+    }
+    format!("```\n{}\n```", lines.join("\n"))
+}
+
+fn has_class(element: &ElementRef, class: &str) -> bool {
+    element.value().classes().any(|c| c == class)
+}
+
+fn prefix_lines(text: &str, first: &str, rest: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 { first } else { rest };
+            match line.is_empty() {
+                true => prefix.trim_end().to_owned(),
+                false => format!("{prefix}{line}"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Whitespace of html source, including line breaks, is a single space.
+pub(crate) fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        match c.is_whitespace() {
+            true if !in_whitespace => {
+                collapsed.push(' ');
+                in_whitespace = true;
+            }
+            true => {}
+            false => {
+                collapsed.push(c);
+                in_whitespace = false;
+            }
+        }
+    }
+    collapsed
+}
+
+/// Trims lines and keeps at most one empty line between paragraphs.
+fn normalize_lines(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = collapse_whitespace(line).trim().to_owned();
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Escapes markdown characters of text, except in urls.
+fn escape(text: &str) -> String {
+    text.split(' ')
+        .map(
+            |word| match word.starts_with("http://") || word.starts_with("https://") {
+                true => word.to_owned(),
+                false => {
+                    let mut escaped = String::with_capacity(word.len());
+                    for c in word.chars() {
+                        if matches!(c, '\\' | '*' | '_' | '~' | '|' | '`') {
+                            escaped.push('\\');
+                        }
+                        escaped.push(c);
+                    }
+                    escaped
+                }
+            },
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -1,5 +1,10 @@
-use scraper::{ElementRef, Selector};
-use std::fmt::Write;
+pub use convert::html_to_markdown;
+use url::Url;
+
+mod ansi;
+mod convert;
+pub mod patch_notes;
+pub mod split;
 
 pub fn clean_text(text: &str) -> String {
     text.replace("\t", "")
@@ -10,100 +15,42 @@ pub fn clean_text(text: &str) -> String {
         .join("\n")
 }
 
-pub fn html_to_markdown(element: &ElementRef) -> String {
-    let mut output = String::new();
-    let mut in_list = false;
-
-    for node in element.children() {
-        match node.value() {
-            scraper::node::Node::Text(t) => {
-                let text = t.trim();
-                if !text.is_empty() {
-                    if in_list {
-                        // Handle text nodes directly under ul/ol but not in li
-                        in_list = false;
-                        writeln!(&mut output).unwrap();
-                    }
-                    write!(&mut output, "{}", clean_text(text)).unwrap();
-                }
-            }
-            scraper::Node::Element(e) => match e.name() {
-                "br" => {
-                    // Respect <br> tags as explicit newlines
-                    writeln!(&mut output).unwrap();
-                }
-                "li" => {
-                    in_list = true;
-                    let li_text = ElementRef::wrap(node).unwrap().text().collect::<String>();
-                    let cleaned = clean_text(&li_text);
-                    writeln!(&mut output, "- {}", cleaned).unwrap();
-                }
-                "ul" | "ol" => {
-                    let list_md = html_to_markdown(&ElementRef::wrap(node).unwrap());
-                    write!(&mut output, "{}", list_md).unwrap();
-                    in_list = false
-                }
-                "h1" => {
-                    let text = ElementRef::wrap(node).unwrap().text().collect::<String>();
-                    writeln!(&mut output, "# {}", clean_text(&text)).unwrap();
-                }
-                "h2" => {
-                    let text = ElementRef::wrap(node).unwrap().text().collect::<String>();
-                    writeln!(&mut output, "## {}", clean_text(&text)).unwrap();
-                }
-                "h3" => {
-                    let text = ElementRef::wrap(node).unwrap().text().collect::<String>();
-                    writeln!(&mut output, "### {}", clean_text(&text)).unwrap();
-                }
-                "strong" => {
-                    let text = ElementRef::wrap(node).unwrap().text().collect::<String>();
-                    write!(&mut output, " **{}** ", clean_text(&text)).unwrap();
-                }
-                "a" => {
-                    let a = ElementRef::wrap(node).unwrap();
+/// Absolute url of a link or image on the page at `base_url`, e.g. `https://web.poecdn.com/image.png`
+/// for `//web.poecdn.com/image.png`. Absolute urls, e.g. of trackers and redirect wrappers,
+/// are kept as they are, as well as in-page anchors and every url if the base is not a url.
+pub fn resolve_url(base_url: &str, url: &str) -> String {
+    resolve(Url::parse(base_url).ok().as_ref(), url)
+}
 
-                    match a.select(&Selector::parse("img").unwrap()).next() {
-                        Some(img) => {
-                            if let Some(href) = a.attr("href") {
-                                let text = img
-                                    .attr("alt")
-                                    .map(|alt| format!("Image {alt}"))
-                                    .unwrap_or("Image".to_owned());
-                                write!(&mut output, "\n\n[{text}]({href})\n\n").unwrap()
-                            }
-                        }
-                        None => {
-                            let text: String = a.text().collect();
-                            let text = clean_text(&text);
-                            match a.attr("href") {
-                                Some(href) => write!(&mut output, " [{text}]({href}) ").unwrap(),
-                                None => write!(&mut output, "{text}").unwrap(),
-                            }
-                        }
-                    }
-                }
-                "img" => {
-                    let img = ElementRef::wrap(node).unwrap();
-                    if let Some(src) = img.attr("src") {
-                        let text = img
-                            .attr("alt")
-                            .map(|alt| format!("Image {alt}"))
-                            .unwrap_or("Image".to_owned());
-                        write!(&mut output, "\n\n[{text}]({src})\n\n").unwrap()
-                    }
-                }
-                _ => {
-                    if in_list {
-                        in_list = false;
-                        writeln!(&mut output).unwrap();
-                    }
-                    let child_text = html_to_markdown(&ElementRef::wrap(node).unwrap());
-                    write!(&mut output, "{}", child_text).unwrap();
-                }
-            },
-            _ => {}
-        }
+fn resolve(base_url: Option<&Url>, url: &str) -> String {
+    let url = url.trim();
+    let is_relative = !url.is_empty() && !url.starts_with('#') && Url::parse(url).is_err();
+    match base_url {
+        Some(base_url) if is_relative => base_url
+            .join(url)
+            .map(String::from)
+            .unwrap_or_else(|_| url.to_owned()),
+        _ => url.to_owned(),
     }
+}
 
-    output
+/// Page of a video embedded with an iframe, e.g. `https://www.youtube.com/watch?v=ID`
+/// for `https://www.youtube.com/embed/ID`. The src must be resolved, see [`resolve_url`].
+/// None for iframes of other hosts, e.g. maps or chats.
+pub fn video_url(iframe_src: &str) -> Option<String> {
+    let url = Url::parse(iframe_src).ok()?;
+    let id = |prefix: &str| {
+        url.path()
+            .strip_prefix(prefix)
+            .map(|id| id.trim_end_matches('/'))
+            .filter(|id| !id.is_empty() && !id.contains('/'))
+    };
+    match url.host_str()? {
+        "www.youtube.com" | "youtube.com" | "www.youtube-nocookie.com" => Some(format!(
+            "https://www.youtube.com/watch?v={}",
+            id("/embed/")?
+        )),
+        "player.vimeo.com" => Some(format!("https://vimeo.com/{}", id("/video/")?)),
+        _ => None,
+    }
 }
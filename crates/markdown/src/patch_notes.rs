@@ -0,0 +1,271 @@
+//! Patch notes as a tree of sections, built from forum HTML.
+//!
+//! Headings become sections nested by their level. A line made only of bold text,
+//! e.g. `<strong>Sorceress Specific Changes</strong><br>`, is a heading below `h6`.
+use scraper::{ElementRef, Node};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatchNotes {
+    /// Patch version, e.g. `0.2.0e` or `1.2.3`.
+    pub version: Option<String>,
+    /// Heading above every section, e.g. `0.2.0e Patch Notes`.
+    pub title: Option<String>,
+    /// Paragraphs before the first section.
+    pub text: Vec<String>,
+    /// Bullets before the first section.
+    pub items: Vec<Item>,
+    pub sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Section {
+    pub title: String,
+    /// Paragraphs of the section that are not bullets.
+    pub text: Vec<String>,
+    pub items: Vec<Item>,
+    pub subsections: Vec<Section>,
+}
+
+/// Bullet point with its nested bullets.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Item {
+    pub text: String,
+    pub children: Vec<Item>,
+}
+
+impl PatchNotes {
+    /// Builds patch notes from the post content element.
+    pub fn from_html(element: &ElementRef) -> Self {
+        let mut reader = BlockReader::default();
+        reader.read(element);
+        let mut blocks = reader.finish();
+
+        let mut notes = PatchNotes::default();
+        // Heading that is above all others, e.g. `<h2>` followed only by `<h3>`s.
+        if let Some(Block::Heading { rank, title }) = blocks.first()
+            && !blocks[1..]
+                .iter()
+                .any(|block| matches!(block, Block::Heading { rank: other, .. } if other <= rank))
+        {
+            notes.version = version(title);
+            notes.title = Some(title.clone());
+            blocks.remove(0);
+        }
+
+        let mut open: Vec<(u8, Section)> = Vec::new();
+        for block in blocks {
+            match block {
+                Block::Heading { rank, title } => {
+                    while open.last().is_some_and(|(open_rank, _)| *open_rank >= rank) {
+                        close_section(&mut notes, &mut open);
+                    }
+                    open.push((
+                        rank,
+                        Section {
+                            title,
+                            ..Default::default()
+                        },
+                    ));
+                }
+                Block::Text(text) => match open.last_mut() {
+                    Some((_, section)) => section.text.push(text),
+                    None => notes.text.push(text),
+                },
+                Block::List(items) => match open.last_mut() {
+                    Some((_, section)) => section.items.extend(items),
+                    None => notes.items.extend(items),
+                },
+            }
+        }
+        while !open.is_empty() {
+            close_section(&mut notes, &mut open);
+        }
+
+        notes
+    }
+}
+
+/// Version in the text, e.g. `1.2.3` of `Last Epoch Patch 1.2.3 Notes`.
+/// Hotfix letter is kept for three-part versions, e.g. `0.2.0e`.
+pub fn version(text: &str) -> Option<String> {
+    let is_number = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    text.split(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == ',')
+        .map(|word| word.trim_end_matches([':', '!', '.']))
+        .find(|word| {
+            let parts: Vec<&str> = word.split('.').collect();
+            let Some((last, init)) = parts.split_last() else {
+                return false;
+            };
+            let last_digits = last.strip_suffix(|c: char| c.is_ascii_lowercase());
+            parts.len() >= 2
+                && init.iter().all(|part| is_number(part))
+                && match last_digits {
+                    Some(digits) => parts.len() >= 3 && is_number(digits),
+                    None => is_number(last),
+                }
+        })
+        .map(str::to_owned)
+}
+
+fn close_section(notes: &mut PatchNotes, open: &mut Vec<(u8, Section)>) {
+    let Some((_, section)) = open.pop() else {
+        return;
+    };
+    match open.last_mut() {
+        Some((_, parent)) => parent.subsections.push(section),
+        None => notes.sections.push(section),
+    }
+}
+
+#[derive(Debug)]
+enum Block {
+    /// `h1` is rank 1, a bold line is [`BOLD_LINE_RANK`].
+    Heading {
+        rank: u8,
+        title: String,
+    },
+    Text(String),
+    List(Vec<Item>),
+}
+
+const BOLD_LINE_RANK: u8 = 7;
+
+const BLOCK_ELEMENTS: [&str; 14] = [
+    "p",
+    "div",
+    "blockquote",
+    "aside",
+    "section",
+    "article",
+    "table",
+    "tr",
+    "td",
+    "details",
+    "summary",
+    "figure",
+    "pre",
+    "hr",
+];
+
+/// Reads content into blocks, collecting inline content into lines.
+#[derive(Default)]
+struct BlockReader {
+    blocks: Vec<Block>,
+    line: String,
+    /// Line has text outside of bold elements.
+    line_has_plain: bool,
+    line_has_bold: bool,
+}
+
+impl BlockReader {
+    fn read(&mut self, element: &ElementRef) {
+        for node in element.children() {
+            match node.value() {
+                Node::Text(text) => {
+                    if !text.trim().is_empty() {
+                        self.line_has_plain = true;
+                    }
+                    self.line.push_str(text);
+                }
+                Node::Element(e) => {
+                    let child = ElementRef::wrap(node).unwrap();
+                    match e.name() {
+                        "br" => self.flush(),
+                        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                            self.flush();
+                            let rank = e.name()[1..].parse().unwrap();
+                            let title = normalize(&child.text().collect::<String>());
+                            if !title.is_empty() {
+                                self.blocks.push(Block::Heading { rank, title });
+                            }
+                        }
+                        "ul" | "ol" => {
+                            self.flush();
+                            let items = list_items(&child);
+                            if !items.is_empty() {
+                                self.blocks.push(Block::List(items));
+                            }
+                        }
+                        "strong" | "b" => {
+                            let text: String = child.text().collect();
+                            if !text.trim().is_empty() {
+                                self.line_has_bold = true;
+                            }
+                            self.line.push_str(&text);
+                        }
+                        "img" | "script" | "style" => {}
+                        name if BLOCK_ELEMENTS.contains(&name) => {
+                            self.flush();
+                            self.read(&child);
+                            self.flush();
+                        }
+                        _ => self.read(&child),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        let line = normalize(&std::mem::take(&mut self.line));
+        if !line.is_empty() {
+            self.blocks
+                .push(match self.line_has_bold && !self.line_has_plain {
+                    true => Block::Heading {
+                        rank: BOLD_LINE_RANK,
+                        title: line,
+                    },
+                    false => Block::Text(line),
+                });
+        }
+        self.line_has_plain = false;
+        self.line_has_bold = false;
+    }
+
+    fn finish(mut self) -> Vec<Block> {
+        self.flush();
+        self.blocks
+    }
+}
+
+fn list_items(list: &ElementRef) -> Vec<Item> {
+    list.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|li| li.value().name() == "li")
+        .map(|li| {
+            let mut text = String::new();
+            let mut children = Vec::new();
+            item_content(&li, &mut text, &mut children);
+            Item {
+                text: normalize(&text),
+                children,
+            }
+        })
+        .filter(|item| !item.text.is_empty() || !item.children.is_empty())
+        .collect()
+}
+
+/// Text of the item without its nested lists, which become children.
+fn item_content(element: &ElementRef, text: &mut String, children: &mut Vec<Item>) {
+    for node in element.children() {
+        match node.value() {
+            Node::Text(t) => text.push_str(t),
+            Node::Element(e) => {
+                let child = ElementRef::wrap(node).unwrap();
+                match e.name() {
+                    "ul" | "ol" => children.extend(list_items(&child)),
+                    "br" => text.push(' '),
+                    _ => item_content(&child, text, children),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Collapses whitespace, including line breaks of the html source.
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,271 @@
+//! Splitting markdown into chunks that fit Discord embeds.
+//!
+//! Text is broken at the coarsest boundary that fits: before a heading, between
+//! paragraphs, between lines, after a sentence and between words. Links and
+//! formatting spans are kept whole, code blocks are closed at the end of a chunk
+//! and re-opened at the start of the next one. Sizes are counted in chars.
+
+/// Formatting spans that are never broken, longest first.
+const SPAN_MARKERS: [&str; 5] = ["**", "__", "~~", "||", "*"];
+/// Prefixes repeated on every chunk of a long line.
+const LINE_PREFIXES: [&str; 2] = ["> ", "-# "];
+const FENCE: &str = "```";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Sections,
+    Paragraphs,
+    Lines,
+    Sentences,
+    Words,
+    Chars,
+}
+
+impl Level {
+    fn finer(self) -> Level {
+        match self {
+            Level::Sections => Level::Paragraphs,
+            Level::Paragraphs => Level::Lines,
+            Level::Lines => Level::Sentences,
+            Level::Sentences => Level::Words,
+            Level::Words | Level::Chars => Level::Chars,
+        }
+    }
+
+    fn separator(self) -> &'static str {
+        match self {
+            Level::Sections | Level::Paragraphs => "\n\n",
+            Level::Lines => "\n",
+            Level::Sentences | Level::Words | Level::Chars => " ",
+        }
+    }
+}
+
+/// Splits markdown into chunks of at most `max_chars` chars.
+///
+/// A chunk is only cut mid-word when a single word, link or span is longer than `max_chars`.
+pub fn split(markdown: &str, max_chars: usize) -> Vec<String> {
+    let markdown = markdown.trim();
+    if markdown.is_empty() || max_chars == 0 {
+        return Vec::new();
+    }
+    split_at(markdown, max_chars, Level::Sections)
+}
+
+fn split_at(text: &str, max_chars: usize, level: Level) -> Vec<String> {
+    if chars(text) <= max_chars {
+        return vec![text.to_owned()];
+    }
+    if let Some((open, body, close)) = code_block(text) {
+        return split_code_block(open, body, close, max_chars);
+    }
+    if let Some(prefix) = line_prefix(text) {
+        let body_max_chars = max_chars.saturating_sub(chars(prefix)).max(1);
+        return split_at(&text[prefix.len()..], body_max_chars, Level::Sentences)
+            .into_iter()
+            .map(|chunk| format!("{prefix}{chunk}"))
+            .collect();
+    }
+    if level == Level::Chars {
+        return split_chars(text, max_chars);
+    }
+
+    let pieces = parts(text, level)
+        .into_iter()
+        .flat_map(|part| split_at(part, max_chars, level.finer()));
+    pack(pieces, level.separator(), max_chars)
+}
+
+/// Joins consecutive pieces with the separator while they fit into `max_chars`.
+fn pack(
+    pieces: impl IntoIterator<Item = String>,
+    separator: &str,
+    max_chars: usize,
+) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+    for piece in pieces {
+        let piece_chars = chars(&piece);
+        match &mut current {
+            Some((chunk, chunk_chars))
+                if *chunk_chars + separator.len() + piece_chars <= max_chars =>
+            {
+                chunk.push_str(separator);
+                chunk.push_str(&piece);
+                *chunk_chars += separator.len() + piece_chars;
+            }
+            _ => chunks.extend(
+                current
+                    .replace((piece, piece_chars))
+                    .map(|(chunk, _)| chunk),
+            ),
+        }
+    }
+    chunks.extend(current.map(|(chunk, _)| chunk));
+    chunks
+}
+
+/// Parts of the text at the boundaries of the level, without the separators.
+fn parts(text: &str, level: Level) -> Vec<&str> {
+    let parts = match level {
+        Level::Sections | Level::Paragraphs | Level::Lines => line_groups(text, level),
+        Level::Sentences => split_spaces(text, true),
+        Level::Words => split_spaces(text, false),
+        Level::Chars => vec![text],
+    };
+    parts
+        .into_iter()
+        .filter(|part| !part.trim().is_empty())
+        .collect()
+}
+
+/// Sections start at headings, paragraphs at blank lines and every line is its own part,
+/// except inside code blocks.
+fn line_groups(text: &str, level: Level) -> Vec<&str> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    let mut in_code_block = false;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let breaks = !in_code_block
+            && match level {
+                Level::Sections => is_heading(trimmed),
+                Level::Paragraphs => trimmed.is_empty(),
+                _ => true,
+            };
+        if breaks {
+            groups.push(&text[start..offset]);
+            start = offset;
+        }
+        if trimmed.starts_with(FENCE) {
+            in_code_block = !in_code_block;
+        }
+        offset += line.len();
+    }
+    groups.push(&text[start..]);
+
+    groups
+        .into_iter()
+        .map(|group| group.trim_start_matches(['\r', '\n']).trim_end())
+        .collect()
+}
+
+fn is_heading(line: &str) -> bool {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    (1..=6).contains(&hashes) && line[hashes..].starts_with(' ')
+}
+
+/// Splits the text at spaces outside of links, code and formatting spans.
+/// With `sentences`, only at spaces after the end of a sentence.
+fn split_spaces(text: &str, sentences: bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut spans: Vec<&str> = Vec::new();
+    let mut link_text_depth = 0;
+    let mut link_target_depth = 0;
+    let mut start = 0;
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+        let mut len = c.len_utf8();
+        if c == '\\' {
+            len += rest[1..].chars().next().map_or(0, char::len_utf8);
+        } else if spans.last() == Some(&"`") {
+            if c == '`' {
+                spans.pop();
+            }
+        } else if link_target_depth > 0 {
+            match c {
+                '(' => link_target_depth += 1,
+                ')' => link_target_depth -= 1,
+                _ => {}
+            }
+        } else if c == '[' {
+            link_text_depth += 1;
+        } else if c == ']' && link_text_depth > 0 {
+            link_text_depth -= 1;
+            if rest[1..].starts_with('(') {
+                link_target_depth = 1;
+                len += 1;
+            }
+        } else if c == '`' {
+            if rest[1..].contains('`') {
+                spans.push("`");
+            }
+        } else if let Some(marker) = SPAN_MARKERS
+            .iter()
+            .find(|marker| rest.starts_with(**marker))
+        {
+            len = marker.len();
+            match spans.iter().rposition(|span| span == marker) {
+                Some(position) => spans.truncate(position),
+                // Unclosed markers are plain text.
+                None if rest[len..].contains(marker) => spans.push(marker),
+                None => {}
+            }
+        } else if c == ' '
+            && spans.is_empty()
+            && link_text_depth == 0
+            && (!sentences || ends_sentence(&text[..i]))
+        {
+            parts.push(&text[start..i]);
+            start = i + 1;
+        }
+        i += len.min(rest.len());
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end_matches(['*', '_', '~', '|', ')', '"', '»', '”'])
+        .ends_with(['.', '!', '?', '…', ':', ';'])
+}
+
+/// Opening fence, body and closing fence of a fenced code block.
+fn code_block(text: &str) -> Option<(&str, &str, &str)> {
+    let (open, rest) = text.split_once('\n')?;
+    let (body, close) = rest.rsplit_once('\n')?;
+    match open.trim_start().starts_with(FENCE) && close.trim() == FENCE {
+        true => Some((open, body, close)),
+        false => None,
+    }
+}
+
+fn split_code_block(open: &str, body: &str, close: &str, max_chars: usize) -> Vec<String> {
+    let body_max_chars = max_chars
+        .saturating_sub(chars(open) + chars(close) + 2)
+        .max(1);
+    let lines = body
+        .lines()
+        .flat_map(|line| match chars(line) <= body_max_chars {
+            true => vec![line.to_owned()],
+            false => split_chars(line, body_max_chars),
+        });
+    pack(lines, "\n", body_max_chars)
+        .into_iter()
+        .map(|chunk| format!("{open}\n{chunk}\n{close}"))
+        .collect()
+}
+
+/// Quote or subtext prefix of a single line.
+fn line_prefix(text: &str) -> Option<&'static str> {
+    match text.contains('\n') {
+        true => None,
+        false => LINE_PREFIXES
+            .into_iter()
+            .find(|prefix| text.starts_with(prefix)),
+    }
+}
+
+fn split_chars(text: &str, max_chars: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .chunks(max_chars)
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+fn chars(text: &str) -> usize {
+    text.chars().count()
+}
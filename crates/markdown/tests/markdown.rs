@@ -4,6 +4,9 @@ fn create_selector(selectors: &str) -> Selector {
     Selector::parse(selectors).unwrap()
 }
 
+/// Page of the posts, relative links of the corpus resolve against it.
+const POE_THREAD_URL: &str = "https://www.pathofexile.com/forum/view-thread/3754474";
+
 #[test]
 fn html_to_markdown() {
     let document = Html::parse_document(
@@ -22,7 +25,88 @@ fn html_to_markdown() {
         .next()
         .unwrap();
 
-    let markdown_content = markdown::html_to_markdown(&el_content);
+    let markdown_content = markdown::html_to_markdown(&el_content, POE_THREAD_URL);
 
     assert_eq!(expected, markdown_content);
 }
+
+/// Every post of `tests/fixtures/corpus/*.html` converts to the markdown next to it.
+/// Run with `UPDATE_SNAPSHOTS=1` to write the markdown after a deliberate change.
+#[test]
+fn converts_corpus() {
+    let update = std::env::var("UPDATE_SNAPSHOTS").is_ok();
+    let mut paths: Vec<_> = std::fs::read_dir("tests/fixtures/corpus")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "html")
+        })
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let fragment = Html::parse_fragment(&std::fs::read_to_string(&path).unwrap());
+        let actual = markdown::html_to_markdown(&fragment.root_element(), POE_THREAD_URL);
+
+        let snapshot = path.with_extension("md");
+        if update {
+            std::fs::write(&snapshot, &actual).unwrap();
+        }
+        let expected = std::fs::read_to_string(&snapshot)
+            .unwrap_or_else(|_| panic!("no snapshot {}", snapshot.display()));
+        assert_eq!(expected, actual, "{}", path.display());
+    }
+}
+
+#[test]
+fn resolves_relative_urls() {
+    let resolve = |url| markdown::resolve_url(POE_THREAD_URL, url);
+
+    assert_eq!(
+        resolve("/forum/view-thread/1"),
+        "https://www.pathofexile.com/forum/view-thread/1"
+    );
+    assert_eq!(
+        resolve("//web.poecdn.com/image.png"),
+        "https://web.poecdn.com/image.png"
+    );
+    assert_eq!(
+        resolve("page/2"),
+        "https://www.pathofexile.com/forum/view-thread/page/2"
+    );
+    // Trackers and redirect wrappers stay as they are.
+    let redirect =
+        "https://www.pathofexile.com/redirect?url=https%3A%2F%2Fexample.com&utm_source=forum";
+    assert_eq!(resolve(redirect), redirect);
+    assert_eq!(resolve("#comments"), "#comments");
+    assert_eq!(
+        resolve("mailto:support@grindinggear.com"),
+        "mailto:support@grindinggear.com"
+    );
+    assert_eq!(
+        markdown::resolve_url("", "/forum/view-thread/1"),
+        "/forum/view-thread/1"
+    );
+}
+
+#[test]
+fn links_videos_of_known_hosts() {
+    assert_eq!(
+        markdown::video_url("https://www.youtube.com/embed/dQw4w9WgXcQ").as_deref(),
+        Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+    );
+    assert_eq!(
+        markdown::video_url("https://player.vimeo.com/video/1072211044").as_deref(),
+        Some("https://vimeo.com/1072211044")
+    );
+    assert_eq!(
+        markdown::video_url("https://www.google.com/maps/embed?pb=1"),
+        None
+    );
+    assert_eq!(
+        markdown::video_url("https://www.twitch.tv/embed/pathofexile/chat"),
+        None
+    );
+}
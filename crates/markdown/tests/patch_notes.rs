@@ -0,0 +1,94 @@
+use markdown::patch_notes::{PatchNotes, Section, version};
+use scraper::{Html, Selector};
+
+fn titles(sections: &[Section]) -> Vec<&str> {
+    sections
+        .iter()
+        .map(|section| section.title.as_str())
+        .collect()
+}
+
+#[test]
+fn builds_patch_notes_tree() {
+    let document = Html::parse_document(
+        &std::fs::read_to_string("tests/fixtures/patch-notes-0.2.0e.html").unwrap(),
+    );
+    let content = document
+        .select(&Selector::parse("tr.staff .content").unwrap())
+        .next()
+        .unwrap();
+
+    let notes = PatchNotes::from_html(&content);
+
+    assert_eq!(notes.version.as_deref(), Some("0.2.0e"));
+    assert_eq!(notes.title.as_deref(), Some("0.2.0e Patch Notes"));
+    assert_eq!(notes.text.len(), 1);
+    assert!(notes.text[0].starts_with("This patch contains many of the changes"));
+    assert_eq!(
+        titles(&notes.sections),
+        [
+            "Changes to Act 3 Areas",
+            "Player Balance Changes",
+            "Delirium Changes",
+            "Monster Speed Changes",
+            "Boss Changes",
+            "Player Minion Changes",
+            "Crafting Changes",
+            "Finding Rare Monsters in Endgame",
+            "Other Improvements",
+            "Bug Fixes"
+        ]
+    );
+
+    let act_3 = &notes.sections[0];
+    assert_eq!(act_3.items.len(), 13);
+    assert_eq!(
+        act_3.items[3].text,
+        "The Azak Bog is now more rectangular, and slightly smaller."
+    );
+
+    // Bold lines are subsections.
+    let balance = &notes.sections[1];
+    assert!(balance.items.is_empty());
+    assert_eq!(
+        titles(&balance.subsections),
+        [
+            "Bleed/Chaos Innoculation Changes",
+            "Sorceress Specific Changes",
+            "Huntress Specific Changes",
+            "Warrior Specific Changes",
+            "Ranger Specific Changes",
+            "Other Player Balance",
+            "New Support Gem"
+        ]
+    );
+    assert_eq!(balance.subsections[0].items.len(), 6);
+    assert_eq!(
+        balance.subsections[0].items[1].text,
+        "Chaos Inoculation now additionally makes you immune to bleeding."
+    );
+
+    let monster_speed = &notes.sections[3];
+    assert_eq!(monster_speed.items.len(), 1);
+    assert_eq!(
+        titles(&monster_speed.subsections),
+        ["Act 1", "Act 2", "Act 3"]
+    );
+
+    assert_eq!(notes.sections[9].items.len(), 29);
+}
+
+#[test]
+fn finds_version() {
+    assert_eq!(version("0.2.0e Patch Notes").as_deref(), Some("0.2.0e"));
+    assert_eq!(
+        version("Last Epoch Patch 1.2.3 Notes").as_deref(),
+        Some("1.2.3")
+    );
+    assert_eq!(
+        version("Content Update 3.26.0 (Secrets of the Atlas)").as_deref(),
+        Some("3.26.0")
+    );
+    assert_eq!(version("Patch Notes for Season 2"), None);
+    assert_eq!(version("Runes 2.5x more common"), None);
+}
@@ -0,0 +1,121 @@
+use markdown::split::split;
+
+fn chars(text: &str) -> usize {
+    text.chars().count()
+}
+
+#[test]
+fn keeps_short_text_whole() {
+    assert_eq!(split("  Short text\n\n", 100), vec!["Short text"]);
+    assert!(split("\n\n", 100).is_empty());
+}
+
+#[test]
+fn counts_chars_not_bytes() {
+    let text = "Сообщение ".repeat(40);
+    let chunks = split(&text, 100);
+
+    assert_eq!(chunks.len(), 4);
+    assert!(chunks.iter().all(|chunk| chars(chunk) <= 100));
+    assert_eq!(chunks.join(" "), text.trim());
+}
+
+#[test]
+fn breaks_before_headings_then_between_paragraphs() {
+    let text = "# First\n\nOne.\n\nTwo.\n\n# Second\n\nThree.";
+
+    assert_eq!(
+        split(text, 30),
+        vec!["# First\n\nOne.\n\nTwo.", "# Second\n\nThree."]
+    );
+    assert_eq!(
+        split(text, 16),
+        vec!["# First\n\nOne.", "Two.", "# Second\n\nThree."]
+    );
+}
+
+#[test]
+fn breaks_after_sentences_then_between_words() {
+    let text = "First sentence is here. Second one is longer than that.";
+
+    assert_eq!(
+        split(text, 40),
+        vec!["First sentence is here.", "Second one is longer than that."]
+    );
+    assert_eq!(
+        split(text, 20),
+        vec![
+            "First sentence is",
+            "here.",
+            "Second one is longer",
+            "than that."
+        ]
+    );
+}
+
+#[test]
+fn keeps_links_and_spans_whole() {
+    let text = "See [the patch notes](https://example.com/notes) and **the new skill gems**, ||a spoiler here||.";
+
+    assert_eq!(
+        split(text, 50),
+        vec![
+            "See [the patch notes](https://example.com/notes)",
+            "and **the new skill gems**, ||a spoiler here||."
+        ]
+    );
+    assert_eq!(
+        split("Both **the new skill gems** and ||a spoiler here||.", 25),
+        vec!["Both", "**the new skill gems**", "and ||a spoiler here||."]
+    );
+}
+
+#[test]
+fn reopens_code_blocks() {
+    let text = "Items:\n\n```ansi\nline one\nline two\nline three\n```";
+    let chunks = split(text, 30);
+
+    assert_eq!(
+        chunks,
+        vec![
+            "Items:",
+            "```ansi\nline one\nline two\n```",
+            "```ansi\nline three\n```"
+        ]
+    );
+}
+
+#[test]
+fn repeats_quote_prefix() {
+    let text = "> First quoted sentence. Second quoted sentence.";
+
+    assert_eq!(
+        split(text, 30),
+        vec!["> First quoted sentence.", "> Second quoted sentence."]
+    );
+}
+
+#[test]
+fn cuts_words_longer_than_a_chunk() {
+    assert_eq!(split("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+}
+
+#[test]
+fn splits_corpus_into_balanced_chunks() {
+    for entry in std::fs::read_dir("tests/fixtures/corpus").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "md") {
+            continue;
+        }
+        let markdown = std::fs::read_to_string(&path).unwrap();
+
+        for chunk in split(&markdown, 300) {
+            assert!(chars(&chunk) <= 300, "{path:?}: {chunk}");
+            let fences = chunk
+                .lines()
+                .filter(|line| line.trim_start().starts_with("```"))
+                .count();
+            assert_eq!(fences % 2, 0, "{path:?}: {chunk}");
+        }
+    }
+}
@@ -1,8 +1,13 @@
-use chrono::{DateTime, FixedOffset, Utc};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use playwright_rs::{Playwright, protocol::page::{GotoOptions, WaitUntil}};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+/// Timezone of printed dates, unless set with `DISPLAY_TIMEZONE` env.
+/// Also the default of dates shown by the bot.
+pub const DEFAULT_DISPLAY_TIMEZONE: Tz = Tz::Europe__Moscow;
+
 pub fn load_history() -> TiersHistory {
     std::fs::read_to_string(paths::tiers_history())
         .ok()
@@ -16,18 +21,17 @@ pub struct TierEntry {
     pub remaining: u32,
 }
 
-impl std::fmt::Display for TierEntry {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {}", self.datetime_moscow(), self.remaining)
+impl TierEntry {
+    /// Date in `timezone` and remaining tiers, e.g. `11.04 18:37: 1326`.
+    pub fn display_in(&self, timezone: Tz) -> String {
+        format!("{}: {}", self.datetime_in(timezone), self.remaining)
     }
-}
 
-impl TierEntry {
-    pub fn datetime_moscow(&self) -> String {
-        let moscow = self
-            .datetime
-            .with_timezone(&FixedOffset::east_opt(3 * 3600).unwrap());
-        moscow.format("%d.%m %H:%M").to_string()
+    pub fn datetime_in(&self, timezone: Tz) -> String {
+        self.datetime
+            .with_timezone(&timezone)
+            .format("%d.%m %H:%M")
+            .to_string()
     }
 
     pub fn hours_since(&self) -> i64 {
@@ -42,12 +46,13 @@ pub struct TiersHistory {
     pub entries: Vec<TierEntry>,
 }
 
-impl std::fmt::Display for TiersHistory {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for entry in &self.entries {
-            writeln!(f, "{}", entry)?;
-        }
-        Ok(())
+impl TiersHistory {
+    /// One entry per line, dates in `timezone`.
+    pub fn display_in(&self, timezone: Tz) -> String {
+        self.entries
+            .iter()
+            .map(|entry| entry.display_in(timezone) + "\n")
+            .collect()
     }
 }
 
@@ -123,7 +128,7 @@ impl<'a> ChallengeExtractor<'a> {
     }
 }
 
-async fn connect_and_extract() -> Result<(), Box<dyn std::error::Error>> {
+async fn connect_and_extract(timezone: Tz) -> Result<(), Box<dyn std::error::Error>> {
     let playwright = Playwright::launch().await?;
 
     let browser = playwright
@@ -161,7 +166,7 @@ async fn connect_and_extract() -> Result<(), Box<dyn std::error::Error>> {
                 datetime: Utc::now(),
                 remaining,
             };
-            println!("{}", entry);
+            println!("{}", entry.display_in(timezone));
 
             if previous_remaining != Some(remaining) {
                 tiers_history.entries.push(entry);
@@ -179,9 +184,10 @@ async fn connect_and_extract() -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
-pub async fn run() {
+/// Extracts tiers until stopped, printing dates in `timezone`.
+pub async fn run(timezone: Tz) {
     loop {
-        if let Err(e) = connect_and_extract().await {
+        if let Err(e) = connect_and_extract(timezone).await {
             println!("\nConnection lost: {}. Reconnecting in 5 seconds...\n", e);
         }
         tokio::time::sleep(Duration::from_secs(5)).await;
@@ -208,6 +214,6 @@ mod tests {
 
         let expected = "11.04 18:37: 1326\n12.04 13:17: 1262\n12.04 20:45: 1246\n13.04 07:09: 1230\n13.04 21:55: 1214\n";
 
-        assert_eq!(expected, history.to_string());
+        assert_eq!(expected, history.display_in(Tz::Europe__Moscow));
     }
 }
@@ -1,4 +1,9 @@
 #[tokio::main]
 async fn main() {
-    poe_challenge_extractor::run().await;
+    // Same variable as the bot uses for displayed dates.
+    let timezone = std::env::var("DISPLAY_TIMEZONE")
+        .ok()
+        .and_then(|timezone| timezone.parse().ok())
+        .unwrap_or(poe_challenge_extractor::DEFAULT_DISPLAY_TIMEZONE);
+    poe_challenge_extractor::run(timezone).await;
 }
\ No newline at end of file
@@ -0,0 +1,290 @@
+//! Localized forum dates, e.g. `May 8, 2024, 4:37:26 PM`, `26 марта 2024 г., 5:10:44`
+//! or `2024年5月8日 下午4:37:26`.
+use crate::WebsiteLanguage;
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Forum date that could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDateError {
+    pub lang: WebsiteLanguage,
+    pub raw: String,
+}
+
+impl std::fmt::Display for ParseDateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "could not parse {} forum date {:?}", self.lang, self.raw)
+    }
+}
+
+impl std::error::Error for ParseDateError {}
+
+/// Parses forum date shown in `timezone`, e.g. `Europe/Moscow`.
+pub fn parse_forum_date(
+    lang: WebsiteLanguage,
+    date_str: &str,
+    timezone: Tz,
+) -> Result<DateTime<Utc>, ParseDateError> {
+    parse_forum_date_at(lang, date_str, timezone, Utc::now())
+}
+
+/// [`parse_forum_date`] with relative dates, e.g. "Today", resolved against `now`.
+pub fn parse_forum_date_at(
+    lang: WebsiteLanguage,
+    date_str: &str,
+    timezone: Tz,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, ParseDateError> {
+    let error = || ParseDateError {
+        lang,
+        raw: date_str.to_owned(),
+    };
+
+    let naive =
+        parse_naive(lang, date_str, now.with_timezone(&timezone).date_naive()).ok_or_else(error)?;
+    Ok(wall_clock_to_utc(naive, timezone))
+}
+
+/// Time shown on a clock of `timezone`, in UTC.
+///
+/// Time repeated when DST ends is read as the earlier one,
+/// time skipped when DST starts is read as an hour later.
+pub fn wall_clock_to_utc(naive: NaiveDateTime, timezone: Tz) -> DateTime<Utc> {
+    timezone
+        .from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| {
+            timezone
+                .from_local_datetime(&(naive + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .map_or_else(|| naive.and_utc(), |date| date.with_timezone(&Utc))
+}
+
+fn parse_naive(lang: WebsiteLanguage, date_str: &str, today: NaiveDate) -> Option<NaiveDateTime> {
+    static TIME: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(\d{1,2}):(\d{2})(?::(\d{2}))?").unwrap());
+    static NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+").unwrap());
+
+    let s = date_str
+        .trim()
+        .trim_start_matches(',')
+        .trim()
+        .to_lowercase();
+
+    let time = TIME.captures(&s)?;
+    let mut hour: u32 = time[1].parse().ok()?;
+    let minute: u32 = time[2].parse().ok()?;
+    let second: u32 = time.get(3).map_or(Some(0), |s| s.as_str().parse().ok())?;
+    match meridiem(&s) {
+        Some(Meridiem::Am) if hour == 12 => hour = 0,
+        Some(Meridiem::Pm) if hour < 12 => hour += 12,
+        _ => {}
+    }
+    let time = NaiveTime::from_hms_opt(hour, minute, second)?;
+
+    if let Some(days_ago) = relative_day(lang, &s) {
+        return Some(today.checked_sub_days(Days::new(days_ago))?.and_time(time));
+    }
+
+    let date_part = TIME.replace(&s, "");
+    let numbers: Vec<&str> = NUMBER
+        .find_iter(&date_part)
+        .map(|number| number.as_str())
+        .collect();
+    let (year, month, day) = match month_by_name(lang, &date_part) {
+        Some(month) => {
+            let [a, b] = numbers.as_slice() else {
+                return None;
+            };
+            match a.len() > 2 {
+                true => (*a, month, *b),
+                false => (*b, month, *a),
+            }
+        }
+        None => {
+            let [a, b, c] = numbers.as_slice() else {
+                return None;
+            };
+            let month = |m: &str| m.parse::<u32>().ok();
+            match (a.len() > 2, lang) {
+                (true, _) => (*a, month(b)?, *c),
+                (false, WebsiteLanguage::En) => (*c, month(a)?, *b),
+                (false, _) => (*c, month(b)?, *a),
+            }
+        }
+    };
+
+    let mut year: i32 = year.parse().ok()?;
+    // Thai dates use Buddhist era.
+    if lang == WebsiteLanguage::Th && year > 2400 {
+        year -= 543;
+    }
+
+    Some(NaiveDate::from_ymd_opt(year, month, day.parse().ok()?)?.and_time(time))
+}
+
+enum Meridiem {
+    Am,
+    Pm,
+}
+
+fn meridiem(s: &str) -> Option<Meridiem> {
+    const AM: [&str; 5] = ["am", "a.m.", "오전", "上午", "午前"];
+    const PM: [&str; 5] = ["pm", "p.m.", "오후", "下午", "午後"];
+
+    let has_word = |words: &[&str]| {
+        s.split_whitespace()
+            .any(|token| words.iter().any(|word| token.starts_with(word)))
+    };
+    match (has_word(&AM), has_word(&PM)) {
+        (true, false) => Some(Meridiem::Am),
+        (false, true) => Some(Meridiem::Pm),
+        _ => None,
+    }
+}
+
+/// Days ago for "Today" and "Yesterday".
+fn relative_day(lang: WebsiteLanguage, s: &str) -> Option<u64> {
+    let (today, yesterday) = match lang {
+        WebsiteLanguage::En => ("today", "yesterday"),
+        WebsiteLanguage::Ru => ("сегодня", "вчера"),
+        WebsiteLanguage::De => ("heute", "gestern"),
+        WebsiteLanguage::Fr => ("aujourd", "hier"),
+        WebsiteLanguage::Es => ("hoy", "ayer"),
+        WebsiteLanguage::Pt => ("hoje", "ontem"),
+        WebsiteLanguage::Th => ("วันนี้", "เมื่อวาน"),
+        WebsiteLanguage::Jp => ("今日", "昨日"),
+        WebsiteLanguage::Kr => ("오늘", "어제"),
+        WebsiteLanguage::Tw => ("今天", "昨天"),
+    };
+
+    match (s.contains(today), s.contains(yesterday)) {
+        (true, _) => Some(0),
+        (_, true) => Some(1),
+        _ => None,
+    }
+}
+
+/// Month, if the date spells it, e.g. `мая` or `พ.ค.`.
+fn month_by_name(lang: WebsiteLanguage, s: &str) -> Option<u32> {
+    let months = month_names(lang)?;
+    s.split(|c: char| c.is_whitespace() || c == ',' || c.is_ascii_digit())
+        .map(|token| token.trim_start_matches('.'))
+        .filter(|token| !token.is_empty())
+        .find_map(|token| {
+            months
+                .iter()
+                .position(|names| names.iter().any(|name| token.starts_with(name)))
+        })
+        .map(|index| index as u32 + 1)
+}
+
+/// Prefixes of month names and abbreviations, in the order of months.
+/// Prefixes that start another month's prefix come first, e.g. `мар` before `ма`.
+fn month_names(lang: WebsiteLanguage) -> Option<[&'static [&'static str]; 12]> {
+    Some(match lang {
+        WebsiteLanguage::En => [
+            &["jan"],
+            &["feb"],
+            &["mar"],
+            &["apr"],
+            &["may"],
+            &["jun"],
+            &["jul"],
+            &["aug"],
+            &["sep"],
+            &["oct"],
+            &["nov"],
+            &["dec"],
+        ],
+        WebsiteLanguage::Ru => [
+            &["янв"],
+            &["фев"],
+            &["мар"],
+            &["апр"],
+            &["ма"],
+            &["июн"],
+            &["июл"],
+            &["авг"],
+            &["сен"],
+            &["окт"],
+            &["ноя"],
+            &["дек"],
+        ],
+        WebsiteLanguage::De => [
+            &["jan"],
+            &["feb"],
+            &["mär", "mar"],
+            &["apr"],
+            &["mai"],
+            &["jun"],
+            &["jul"],
+            &["aug"],
+            &["sep"],
+            &["okt"],
+            &["nov"],
+            &["dez"],
+        ],
+        WebsiteLanguage::Fr => [
+            &["janv"],
+            &["févr", "fevr"],
+            &["mars"],
+            &["avr"],
+            &["mai"],
+            &["juin"],
+            &["juil"],
+            &["août", "aout"],
+            &["sept"],
+            &["oct"],
+            &["nov"],
+            &["déc", "dec"],
+        ],
+        WebsiteLanguage::Es => [
+            &["ene"],
+            &["feb"],
+            &["mar"],
+            &["abr"],
+            &["may"],
+            &["jun"],
+            &["jul"],
+            &["ago"],
+            &["sep", "set"],
+            &["oct"],
+            &["nov"],
+            &["dic"],
+        ],
+        WebsiteLanguage::Pt => [
+            &["jan"],
+            &["fev"],
+            &["mar"],
+            &["abr"],
+            &["mai"],
+            &["jun"],
+            &["jul"],
+            &["ago"],
+            &["set"],
+            &["out"],
+            &["nov"],
+            &["dez"],
+        ],
+        WebsiteLanguage::Th => [
+            &["ม.ค.", "มกรา"],
+            &["ก.พ.", "กุมภา"],
+            &["มี.ค.", "มีนา"],
+            &["เม.ย.", "เมษา"],
+            &["พ.ค.", "พฤษภา"],
+            &["มิ.ย.", "มิถุนา"],
+            &["ก.ค.", "กรกฎา"],
+            &["ส.ค.", "สิงหา"],
+            &["ก.ย.", "กันยา"],
+            &["ต.ค.", "ตุลา"],
+            &["พ.ย.", "พฤศจิกา"],
+            &["ธ.ค.", "ธันวา"],
+        ],
+        // Numeric months, e.g. `2024年5月8日`.
+        WebsiteLanguage::Jp | WebsiteLanguage::Kr | WebsiteLanguage::Tw => return None,
+    })
+}
@@ -0,0 +1,50 @@
+//! Errors of fetching and scraping the forum.
+use crate::ParseDateError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Request failed before getting a response, e.g. timeout or connection reset.
+    #[error("network error: {0}")]
+    Network(#[source] reqwest::Error),
+    #[error("{url} responded with {status}")]
+    Status {
+        url: String,
+        status: reqwest::StatusCode,
+    },
+    /// Page or API response doesn't look as expected, e.g. after a forum redesign.
+    #[error("layout error: {0}")]
+    Layout(#[from] LayoutError),
+}
+
+impl Error {
+    pub fn is_layout(&self) -> bool {
+        matches!(self, Error::Layout(_))
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        match (err.status(), err.is_decode()) {
+            (Some(status), _) => Error::Status {
+                url: err.url().map(|url| url.to_string()).unwrap_or_default(),
+                status,
+            },
+            (None, true) => Error::Layout(LayoutError::Api(err.to_string())),
+            (None, false) => Error::Network(err),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LayoutError {
+    #[error("no thread rows found")]
+    NoRows,
+    /// Rows are there, but none of them has the expected cells.
+    #[error("{rows} rows found, {parsed} parsed")]
+    Unparsed { rows: usize, parsed: usize },
+    #[error(transparent)]
+    Date(#[from] ParseDateError),
+    /// API response could not be decoded.
+    #[error("unexpected API response: {0}")]
+    Api(String),
+}
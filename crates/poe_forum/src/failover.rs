@@ -0,0 +1,193 @@
+//! Fetching from a primary [`ThreadSource`] with a fallback,
+//! e.g. the PoE Forum Reader API backed by direct forum scraping.
+use crate::{Error, LayoutError, NewsThreadInfo, Subforum, ThreadSource, WebsiteLanguage};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::{collections::HashMap, sync::Mutex};
+
+/// Kind of [`ThreadSource`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceKind {
+    Forum,
+    Api,
+}
+
+impl std::fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceKind::Forum => f.write_str("forum"),
+            SourceKind::Api => f.write_str("api"),
+        }
+    }
+}
+
+impl ThreadSource {
+    pub fn kind(&self) -> SourceKind {
+        match self {
+            ThreadSource::Forum { .. } => SourceKind::Forum,
+            ThreadSource::Api { .. } => SourceKind::Api,
+        }
+    }
+}
+
+/// Why the fallback source was used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailoverReason {
+    /// Primary source failed with the error.
+    Error(String),
+    /// Primary source returned no threads, or is missing threads that were already seen
+    /// or that the fallback has, see [`is_behind`].
+    Stale,
+}
+
+impl std::fmt::Display for FailoverReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailoverReason::Error(err) => write!(f, "{err}"),
+            FailoverReason::Stale => f.write_str("stale response"),
+        }
+    }
+}
+
+/// Threads with the source that served them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Served {
+    pub threads: Vec<NewsThreadInfo>,
+    /// Why some rows of the page were skipped, see [`SubforumPage::skipped`](crate::SubforumPage::skipped).
+    pub skipped: Option<LayoutError>,
+    pub source: SourceKind,
+    /// Set if the threads are from the fallback source.
+    pub failover: Option<FailoverReason>,
+}
+
+/// Minutes between fetches of the fallback source that check a primary source
+/// which responds but lags behind.
+pub const CROSS_CHECK_MINUTES: i64 = 60;
+
+#[derive(Debug)]
+pub struct FailoverSource {
+    pub primary: ThreadSource,
+    pub fallback: ThreadSource,
+    /// Newest thread seen per subforum, from any source.
+    newest_seen: Mutex<HashMap<(WebsiteLanguage, Subforum), DateTime<Utc>>>,
+    /// Last time the primary source was checked against the fallback, per subforum.
+    cross_checked: Mutex<HashMap<(WebsiteLanguage, Subforum), DateTime<Utc>>>,
+}
+
+impl FailoverSource {
+    pub fn new(primary: ThreadSource, fallback: ThreadSource) -> Self {
+        Self {
+            primary,
+            fallback,
+            newest_seen: Mutex::default(),
+            cross_checked: Mutex::default(),
+        }
+    }
+
+    /// Fetches from the primary source, and from the fallback if the primary
+    /// fails or is stale. Every [`CROSS_CHECK_MINUTES`] the fallback is fetched anyway,
+    /// and served if the primary is behind it. Fails with the fallback error if both fail.
+    pub async fn fetch(&self, lang: WebsiteLanguage, subforum: Subforum) -> Result<Served, Error> {
+        let failover = match crate::fetch_subforum_threads_list(lang, subforum, &self.primary).await
+        {
+            Ok(page) if !is_stale(&page.threads, self.newest_seen(lang, subforum)) => {
+                let checked = match self.cross_check_due(lang, subforum, Utc::now()) {
+                    true => crate::fetch_subforum_threads_list(lang, subforum, &self.fallback)
+                        .await
+                        .inspect_err(|err| {
+                            eprintln!("{lang} {subforum}: cross-check with fallback failed: {err}")
+                        })
+                        .ok(),
+                    false => None,
+                };
+                if let Some(fallback) =
+                    checked.filter(|fallback| is_behind(&page.threads, &fallback.threads))
+                {
+                    self.see(lang, subforum, &fallback.threads);
+                    return Ok(Served {
+                        threads: fallback.threads,
+                        skipped: fallback.skipped,
+                        source: self.fallback.kind(),
+                        failover: Some(FailoverReason::Stale),
+                    });
+                }
+
+                self.see(lang, subforum, &page.threads);
+                return Ok(Served {
+                    threads: page.threads,
+                    skipped: page.skipped,
+                    source: self.primary.kind(),
+                    failover: None,
+                });
+            }
+            Ok(_) => FailoverReason::Stale,
+            Err(err) => FailoverReason::Error(err.to_string()),
+        };
+
+        let page = crate::fetch_subforum_threads_list(lang, subforum, &self.fallback).await?;
+        self.see(lang, subforum, &page.threads);
+        Ok(Served {
+            threads: page.threads,
+            skipped: page.skipped,
+            source: self.fallback.kind(),
+            failover: Some(failover),
+        })
+    }
+
+    fn newest_seen(&self, lang: WebsiteLanguage, subforum: Subforum) -> Option<DateTime<Utc>> {
+        self.newest_seen
+            .lock()
+            .unwrap()
+            .get(&(lang, subforum))
+            .copied()
+    }
+
+    /// Whether the primary source should be checked against the fallback now.
+    /// Marks the check as done.
+    fn cross_check_due(
+        &self,
+        lang: WebsiteLanguage,
+        subforum: Subforum,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let mut cross_checked = self.cross_checked.lock().unwrap();
+        let due = cross_checked
+            .get(&(lang, subforum))
+            .is_none_or(|checked| now - *checked >= TimeDelta::minutes(CROSS_CHECK_MINUTES));
+        if due {
+            cross_checked.insert((lang, subforum), now);
+        }
+        due
+    }
+
+    fn see(&self, lang: WebsiteLanguage, subforum: Subforum, threads: &[NewsThreadInfo]) {
+        let Some(newest) = newest(threads) else {
+            return;
+        };
+        let mut newest_seen = self.newest_seen.lock().unwrap();
+        let seen = newest_seen.entry((lang, subforum)).or_insert(newest);
+        *seen = newest.max(*seen);
+    }
+}
+
+/// Response is stale if it is empty or older than the newest thread already seen.
+pub fn is_stale(threads: &[NewsThreadInfo], newest_seen: Option<DateTime<Utc>>) -> bool {
+    match (newest(threads), newest_seen) {
+        (None, _) => true,
+        (Some(newest), Some(seen)) => newest < seen,
+        (Some(_), None) => false,
+    }
+}
+
+/// Primary response is behind if the fallback has a thread newer than all of its threads,
+/// e.g. an API that still responds but stopped updating.
+pub fn is_behind(primary: &[NewsThreadInfo], fallback: &[NewsThreadInfo]) -> bool {
+    match (newest(primary), newest(fallback)) {
+        (Some(primary), Some(fallback)) => fallback > primary,
+        (None, Some(_)) => true,
+        (_, None) => false,
+    }
+}
+
+fn newest(threads: &[NewsThreadInfo]) -> Option<DateTime<Utc>> {
+    threads.iter().map(|thread| thread.posted_date).max()
+}
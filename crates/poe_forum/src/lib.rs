@@ -1,90 +1,132 @@
-use chrono::{DateTime, FixedOffset, Utc};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 
-pub use post::get_post_details;
+pub use date::{ParseDateError, parse_forum_date};
+pub use error::{Error, LayoutError};
+pub use failover::{FailoverSource, Served, SourceKind};
+pub use markdown::patch_notes::PatchNotes;
+pub use post::{get_patch_notes, get_post_details};
+pub use thread::{ForumPost, fetch_thread_posts};
 
+pub mod date;
+pub mod error;
+pub mod failover;
 pub mod post;
+pub mod thread;
 
 /// Where to fetch forum thread data from.
+///
+/// Both variants read forum timestamps as wall-clock time of an IANA timezone
+/// and convert them to UTC the same way, DST included, see [`date::wall_clock_to_utc`].
 #[derive(Debug, Clone)]
 pub enum ThreadSource {
     /// Scrape the official Path of Exile forum HTML directly.
     ///
-    /// `time_offset` is used to adjust forum-local timestamps to UTC.
-    Forum { time_offset: Option<FixedOffset> },
+    /// `timezone` is the timezone the forum shows dates in.
+    Forum { timezone: Tz },
     /// Fetch pre-parsed thread list from a PoE Forum Reader API endpoint
     /// (e.g. a Cloudflare Worker running the TanStack Start app).
     ///
     /// The API returns ISO strings that represent wall-clock timestamps
     /// from the forum. If the API was deployed in a different timezone
-    /// than the consumer, `time_offset` can shift the timestamps back
+    /// than the consumer, `timezone` can shift the timestamps back
     /// to true UTC. Pass `None` when the API already returns correct UTC.
-    Api { base_url: String, time_offset: Option<FixedOffset> },
+    Api {
+        base_url: String,
+        timezone: Option<Tz>,
+    },
 }
 
 pub async fn fetch_subforum_threads_list(
     lang: WebsiteLanguage,
     subforum: Subforum,
     source: &ThreadSource,
-) -> Result<Vec<NewsThreadInfo>, reqwest::Error> {
+) -> Result<SubforumPage, Error> {
     match source {
-        ThreadSource::Forum { time_offset } => {
-            let url = match lang {
-                WebsiteLanguage::Ru => {
-                    format!("https://ru.pathofexile.com/forum/view-forum/{subforum}")
-                }
-                WebsiteLanguage::En => {
-                    format!("https://www.pathofexile.com/forum/view-forum/{subforum}")
-                }
-            };
+        ThreadSource::Forum { timezone } => {
+            let url = format!("{}/forum/view-forum/{subforum}", lang.base_url());
             let html = http::text(&url).await?;
-            Ok(html::parse(&html, subforum, lang, time_offset.as_ref()))
+            Ok(html::parse(&html, subforum, lang, *timezone)?)
         }
-        ThreadSource::Api { base_url, time_offset } => {
+        ThreadSource::Api { base_url, timezone } => {
             let api_url = format!(
                 "{}/api/threads?subforum={}&lang={}",
                 base_url.trim_end_matches('/'),
                 subforum,
-                match lang {
-                    WebsiteLanguage::En => "en",
-                    WebsiteLanguage::Ru => "ru",
-                },
+                lang,
             );
-            #[derive(Deserialize)]
-            struct ApiThread {
-                url: String,
-                #[serde(rename = "postedDateISO")]
-                posted_date: DateTime<Utc>,
-                title: String,
-                author: Option<String>,
-            }
-            #[derive(Deserialize)]
-            struct ApiResponse {
-                threads: Vec<ApiThread>,
-            }
-            let resp: ApiResponse = reqwest::get(&api_url).await?.json().await?;
-            Ok(resp
-                .threads
-                .into_iter()
-                .map(|t| NewsThreadInfo {
-                    url: t.url,
-                    posted_date: match time_offset {
-                        Some(offset) => {
-                            t.posted_date
-                                - chrono::Duration::seconds(offset.local_minus_utc() as i64)
-                        }
-                        None => t.posted_date,
-                    },
-                    title: t.title,
-                    author: t.author,
-                    lang,
-                    subforum,
-                })
-                .collect())
+            let json = http::text(&api_url).await?;
+            Ok(SubforumPage {
+                threads: parse_api_threads(&json, subforum, lang, *timezone)?,
+                skipped: None,
+            })
         }
     }
 }
 
+/// Parses threads of a PoE Forum Reader API response, see [`ThreadSource::Api`].
+pub fn parse_api_threads(
+    json: &str,
+    subforum: Subforum,
+    lang: WebsiteLanguage,
+    timezone: Option<Tz>,
+) -> Result<Vec<NewsThreadInfo>, LayoutError> {
+    #[derive(Deserialize)]
+    struct ApiThread {
+        url: String,
+        #[serde(rename = "postedDateISO")]
+        posted_date: DateTime<Utc>,
+        title: String,
+        author: Option<String>,
+    }
+    #[derive(Deserialize)]
+    struct ApiResponse {
+        threads: Vec<ApiThread>,
+    }
+
+    let resp: ApiResponse =
+        serde_json::from_str(json).map_err(|e| LayoutError::Api(e.to_string()))?;
+    Ok(resp
+        .threads
+        .into_iter()
+        .map(|t| NewsThreadInfo {
+            url: t.url,
+            posted_date: match timezone {
+                Some(timezone) => date::wall_clock_to_utc(t.posted_date.naive_utc(), timezone),
+                None => t.posted_date,
+            },
+            title: t.title,
+            author: t.author,
+            lang,
+            subforum,
+        })
+        .collect())
+}
+
+/// Threads of a subforum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubforumPage {
+    pub threads: Vec<NewsThreadInfo>,
+    /// Why some rows were skipped, e.g. a date that could not be parsed.
+    /// Only the first problem is kept.
+    pub skipped: Option<LayoutError>,
+}
+
+/// Parses threads of a `/forum/view-forum/<subforum>` page.
+///
+/// Rows without the expected cells are skipped. Rows with a date that could not be
+/// parsed are skipped too and reported in [`SubforumPage::skipped`].
+/// The page is rejected if none of the rows could be read.
+pub fn parse_subforum_page(
+    html: &str,
+    subforum: Subforum,
+    lang: WebsiteLanguage,
+    timezone: Tz,
+) -> Result<SubforumPage, LayoutError> {
+    html::parse(html, subforum, lang, timezone)
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Subforum {
     News,
@@ -108,18 +150,102 @@ impl std::fmt::Display for Subforum {
     }
 }
 
+/// Official realms of the website.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum WebsiteLanguage {
     Ru,
     En,
+    De,
+    Fr,
+    Es,
+    /// Brazilian Portuguese.
+    Pt,
+    Th,
+    Jp,
+    /// Korean, hosted by Kakao Games.
+    Kr,
+    /// Traditional Chinese, hosted by Garena.
+    Tw,
+}
+
+impl WebsiteLanguage {
+    pub const ALL: [WebsiteLanguage; 10] = [
+        WebsiteLanguage::En,
+        WebsiteLanguage::Ru,
+        WebsiteLanguage::De,
+        WebsiteLanguage::Fr,
+        WebsiteLanguage::Es,
+        WebsiteLanguage::Pt,
+        WebsiteLanguage::Th,
+        WebsiteLanguage::Jp,
+        WebsiteLanguage::Kr,
+        WebsiteLanguage::Tw,
+    ];
+
+    /// Short code, e.g. `ru` or `pt`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WebsiteLanguage::Ru => "ru",
+            WebsiteLanguage::En => "en",
+            WebsiteLanguage::De => "de",
+            WebsiteLanguage::Fr => "fr",
+            WebsiteLanguage::Es => "es",
+            WebsiteLanguage::Pt => "pt",
+            WebsiteLanguage::Th => "th",
+            WebsiteLanguage::Jp => "jp",
+            WebsiteLanguage::Kr => "kr",
+            WebsiteLanguage::Tw => "tw",
+        }
+    }
+
+    /// Website of the language, e.g. `https://ru.pathofexile.com`.
+    pub fn base_url(&self) -> &'static str {
+        match self {
+            WebsiteLanguage::Ru => "https://ru.pathofexile.com",
+            WebsiteLanguage::En => "https://www.pathofexile.com",
+            WebsiteLanguage::De => "https://de.pathofexile.com",
+            WebsiteLanguage::Fr => "https://fr.pathofexile.com",
+            WebsiteLanguage::Es => "https://es.pathofexile.com",
+            WebsiteLanguage::Pt => "https://br.pathofexile.com",
+            WebsiteLanguage::Th => "https://th.pathofexile.com",
+            WebsiteLanguage::Jp => "https://jp.pathofexile.com",
+            WebsiteLanguage::Kr => "https://poe.game.daum.net",
+            WebsiteLanguage::Tw => "https://pathofexile.tw",
+        }
+    }
+
+    /// Language of the website by its host, e.g. `br.pathofexile.com`.
+    pub fn from_host(host: &str) -> Option<Self> {
+        let host = match host {
+            "pathofexile.com" => "www.pathofexile.com",
+            "www.pathofexile.tw" => "pathofexile.tw",
+            host => host,
+        };
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.base_url().strip_prefix("https://") == Some(host))
+    }
 }
 
 impl std::fmt::Display for WebsiteLanguage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            WebsiteLanguage::Ru => f.write_str("ru"),
-            WebsiteLanguage::En => f.write_str("en"),
+        f.write_str(self.code())
+    }
+}
+
+impl std::str::FromStr for WebsiteLanguage {
+    type Err = String;
+
+    /// Parses [`WebsiteLanguage::code`], `br` is accepted for [`WebsiteLanguage::Pt`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        if s == "br" {
+            return Ok(WebsiteLanguage::Pt);
         }
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.code() == s)
+            .ok_or_else(|| format!("Unknown website language {s}"))
     }
 }
 
@@ -136,36 +262,73 @@ pub struct NewsThreadInfo {
 }
 
 mod html {
-    use crate::{NewsThreadInfo, Subforum, WebsiteLanguage};
-    use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, Offset, ParseError, TimeZone, Utc};
+    use crate::{
+        LayoutError, NewsThreadInfo, ParseDateError, Subforum, SubforumPage, WebsiteLanguage,
+        date::parse_forum_date,
+    };
+    use chrono::{DateTime, Utc};
+    use chrono_tz::Tz;
     use scraper::{ElementRef, Html, Selector};
 
     pub fn parse(
         html: &str,
         subforum: Subforum,
         lang: WebsiteLanguage,
-        time_offset: Option<&FixedOffset>,
-    ) -> Vec<NewsThreadInfo> {
-        Html::parse_document(html)
+        timezone: Tz,
+    ) -> Result<SubforumPage, LayoutError> {
+        let document = Html::parse_document(html);
+        let rows: Vec<ElementRef> = document
             .select(&Selector::parse("table tbody tr").unwrap())
-            .filter_map(|row| parse_tr(&row, subforum, lang, time_offset))
-            .collect()
+            .collect();
+        if rows.is_empty() {
+            return Err(LayoutError::NoRows);
+        }
+
+        let mut threads = Vec::new();
+        let mut skipped = None;
+        for row in &rows {
+            match parse_tr(row, subforum, lang, timezone) {
+                Ok(Some(thread)) => threads.push(thread),
+                Ok(None) => {}
+                Err(err) => {
+                    skipped.get_or_insert(LayoutError::Date(err));
+                }
+            }
+        }
+
+        match (threads.is_empty(), skipped) {
+            (true, Some(err)) => Err(err),
+            (true, None) => Err(LayoutError::Unparsed {
+                rows: rows.len(),
+                parsed: 0,
+            }),
+            (false, skipped) => Ok(SubforumPage { threads, skipped }),
+        }
     }
 
+    /// None if the row has no thread cells, e.g. a separator.
     pub fn parse_tr(
         tr: &ElementRef,
         subforum: Subforum,
         lang: WebsiteLanguage,
-        time_offset: Option<&FixedOffset>,
-    ) -> Option<NewsThreadInfo> {
-        Some(NewsThreadInfo {
-            url: get_thread_url(tr, lang)?,
-            posted_date: get_posted_date(tr, lang, time_offset)?,
-            title: get_thread_title(tr)?,
+        timezone: Tz,
+    ) -> Result<Option<NewsThreadInfo>, ParseDateError> {
+        let (Some(url), Some(title), Some(posted_date)) = (
+            get_thread_url(tr, lang),
+            get_thread_title(tr),
+            get_posted_date(tr, lang, timezone),
+        ) else {
+            return Ok(None);
+        };
+
+        Ok(Some(NewsThreadInfo {
+            url,
+            posted_date: posted_date?,
+            title,
             author: get_author(tr),
             lang,
             subforum,
-        })
+        }))
     }
 
     fn get_thread_title(tr: &ElementRef) -> Option<String> {
@@ -183,31 +346,22 @@ mod html {
     fn get_thread_url(tr: &ElementRef, lang: WebsiteLanguage) -> Option<String> {
         let a_selector = &Selector::parse(".title a").ok()?;
         let path = tr.select(a_selector).next()?.attr("href")?.to_owned();
-        let subdomain = match lang {
-            WebsiteLanguage::Ru => "ru.",
-            WebsiteLanguage::En => "www.",
-        };
-        Some(format!("https://{subdomain}pathofexile.com{path}"))
+        Some(format!("{}{path}", lang.base_url()))
     }
 
+    /// None if the row has no date cell.
     fn get_posted_date(
         tr: &ElementRef,
         lang: WebsiteLanguage,
-        time_offset: Option<&FixedOffset>,
-    ) -> Option<DateTime<Utc>> {
+        timezone: Tz,
+    ) -> Option<Result<DateTime<Utc>, ParseDateError>> {
         let date_str = tr
             .select(&Selector::parse(".post_date").ok()?)
             .next()?
             .text()
             .next()?;
 
-        match parse_forum_date(lang, date_str, time_offset) {
-            Ok(date) => Some(date),
-            Err(e) => {
-                dbg!("Could not parse date. ", e, format!("{lang}"), date_str);
-                None
-            }
-        }
+        Some(parse_forum_date(lang, date_str, timezone))
     }
 
     fn get_author(tr: &ElementRef) -> Option<String> {
@@ -218,57 +372,4 @@ mod html {
             .collect::<String>();
         Some(author)
     }
-
-    fn parse_forum_date(
-        lang: WebsiteLanguage,
-        date_str: &str,
-        time_offset: Option<&FixedOffset>,
-    ) -> Result<DateTime<Utc>, ParseError> {
-        let fmt = match lang {
-            WebsiteLanguage::En => "%b %e, %Y, %I:%M:%S %p", // May 8, 2024, 4:37:26 PM
-            WebsiteLanguage::Ru => "%d %m %Y, %H:%M:%S",     // 26 марта 2024 г., 5:10:44
-        };
-        let mut s = match lang {
-            WebsiteLanguage::En => date_str.to_owned(),
-            WebsiteLanguage::Ru => {
-                let mut s = date_str.to_owned();
-                for (index, month) in [
-                    "янв.",
-                    "февр.",
-                    "мар.",
-                    "апр.",
-                    "мая",
-                    "июн.",
-                    "июл.",
-                    "авг.",
-                    "сент.",
-                    "окт.",
-                    "нояб.",
-                    "дек.",
-                ]
-                .iter()
-                .enumerate()
-                {
-                    s = s.replace(month, &format!("{}", index + 1));
-                }
-                s.replace(" г.", "")
-            }
-        };
-        if s.starts_with(", ") {
-            s = s.chars().skip(2).collect();
-        }
-
-        let naive = NaiveDateTime::parse_from_str(&s, fmt)?;
-
-        let local_date_time = match time_offset {
-            Some(offset) => offset.from_local_datetime(&naive).unwrap(),
-            None => Local::now()
-                .offset()
-                .fix()
-                .from_local_datetime(&naive)
-                .unwrap(),
-        };
-
-        Ok(local_date_time.to_utc())
-    }
 }
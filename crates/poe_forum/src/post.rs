@@ -1,23 +1,158 @@
-use scraper::Html;
+use markdown::patch_notes::{self, PatchNotes};
+use scraper::{ElementRef, Html};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PostDetails {
     pub content: String,
+    /// First image of the post.
     pub image_src: Option<String>,
+    /// Images, videos and attachments in the order of the post.
+    pub media: Vec<Media>,
 }
 
-pub fn get_post_details(html: &str) -> Option<PostDetails> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MediaKind {
+    Image,
+    /// YouTube or Vimeo iframe.
+    Video,
+    /// Link to a file, e.g. a pdf.
+    Attachment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Media {
+    pub kind: MediaKind,
+    /// Image source, video page or file.
+    pub url: String,
+    /// Alt text of the image, title of the video or text of the link.
+    pub alt: Option<String>,
+    /// Byte offset of the markdown link of the media in [`PostDetails::content`].
+    /// If the content has no link for it, the offset of the previous media link end.
+    pub position: usize,
+    /// Byte length of the markdown link at `position`, 0 if the content has none.
+    pub link_len: usize,
+}
+
+/// Extensions of links that are attachments rather than pages.
+const ATTACHMENT_EXTENSIONS: [&str; 8] = ["pdf", "zip", "txt", "csv", "xlsx", "mp4", "webm", "7z"];
+
+/// Details of the first post of the thread page at `url`.
+pub fn get_post_details(html: &str, url: &str) -> Option<PostDetails> {
     let document = Html::parse_document(html);
 
     let el_content = selectors::content(&document)?;
+    let content = markdown::html_to_markdown(&el_content, url);
+    let media = get_media(&el_content, &content, url);
 
     Some(PostDetails {
-        content: markdown::html_to_markdown(&el_content),
-        image_src: selectors::post_image_src(&document),
+        content,
+        image_src: selectors::post_image_src(&document).map(|src| markdown::resolve_url(url, &src)),
+        media,
+    })
+}
+
+/// Media of the post content element, positioned in its `markdown`.
+/// Urls are resolved against `base_url` as in [`markdown::html_to_markdown`].
+pub fn get_media(el_content: &ElementRef, markdown: &str, base_url: &str) -> Vec<Media> {
+    let mut media = Vec::new();
+    let mut cursor = 0;
+    for element in el_content.select(&selectors::create_selector("img, iframe, a[href]")) {
+        let Some((kind, url, link_url, alt)) = media_of(&element, base_url) else {
+            continue;
+        };
+        let (position, link_len) = match find_link(markdown, cursor, &link_url) {
+            Some((position, len)) => {
+                cursor = position + len;
+                (position, len)
+            }
+            None => (cursor, 0),
+        };
+        media.push(Media {
+            kind,
+            url,
+            alt: alt
+                .map(|alt| alt.trim().to_owned())
+                .filter(|alt| !alt.is_empty()),
+            position,
+            link_len,
+        });
+    }
+    media
+}
+
+/// Kind, url, url of the markdown link and alt text of the element, if it is media.
+fn media_of(
+    element: &ElementRef,
+    base_url: &str,
+) -> Option<(MediaKind, String, String, Option<String>)> {
+    let alt = |attr| element.attr(attr).map(str::to_owned);
+    let resolve = |url| markdown::resolve_url(base_url, url);
+    match element.value().name() {
+        "img" => {
+            let src = element.attr("src")?;
+            // Image wrapped in a link is converted to a link to the href.
+            let link_url = element
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .find(|ancestor| ancestor.value().name() == "a")
+                .and_then(|a| a.attr("href"))
+                .unwrap_or(src);
+            Some((
+                MediaKind::Image,
+                resolve(src),
+                resolve(link_url),
+                alt("alt"),
+            ))
+        }
+        "iframe" => {
+            let url = markdown::video_url(&resolve(element.attr("src")?))?;
+            Some((MediaKind::Video, url.clone(), url, alt("title")))
+        }
+        _ => {
+            let href = resolve(element.attr("href")?);
+            let has_image = element
+                .select(&selectors::create_selector("img"))
+                .next()
+                .is_some();
+            if has_image || !is_attachment(&href) {
+                return None;
+            }
+            let text = element.text().collect::<String>();
+            Some((MediaKind::Attachment, href.clone(), href, Some(text)))
+        }
+    }
+}
+
+fn is_attachment(href: &str) -> bool {
+    let path = href.split(['?', '#']).next().unwrap_or_default();
+    let file = path.rsplit('/').next().unwrap_or_default();
+    file.rsplit_once('.').is_some_and(|(_, extension)| {
+        ATTACHMENT_EXTENSIONS.contains(&extension.to_lowercase().as_str())
     })
 }
 
+/// Position and length of the first `[text](url)` link to the url after `from`.
+fn find_link(markdown: &str, from: usize, url: &str) -> Option<(usize, usize)> {
+    let target = format!("]({url})");
+    let end = from + markdown.get(from..)?.find(&target)? + target.len();
+    let start = markdown[from..end - target.len()].rfind('[')? + from;
+    Some((start, end - start))
+}
+
+/// Opening post of a patch notes thread as sections and bullets.
+/// Version falls back to the page title if the post has no title heading.
+pub fn get_patch_notes(html: &str) -> Option<PatchNotes> {
+    let document = Html::parse_document(html);
+
+    let mut notes = PatchNotes::from_html(&selectors::content(&document)?);
+    if notes.version.is_none() {
+        notes.version =
+            selectors::page_title(&document).and_then(|title| patch_notes::version(&title));
+    }
+    Some(notes)
+}
+
 mod selectors {
     use scraper::{ElementRef, Html, Selector};
 
@@ -41,6 +176,16 @@ mod selectors {
             .map(|src| src.to_string())
     }
 
+    pub fn page_title(document: &Html) -> Option<String> {
+        Some(
+            document
+                .select(&create_selector("title"))
+                .next()?
+                .text()
+                .collect(),
+        )
+    }
+
     pub fn content(document: &Html) -> Option<ElementRef<'_>> {
         content_post(document).and_then(|post| post.select(&create_selector(".content")).next())
     }
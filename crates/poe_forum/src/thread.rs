@@ -0,0 +1,204 @@
+//! Every post of a forum thread, page by page.
+use crate::{Error, LayoutError, ParseDateError, WebsiteLanguage};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ForumPost {
+    /// Id of the post, as in `/forum/view-post/<id>`.
+    pub id: u64,
+    pub author: Option<String>,
+    /// Posted by GGG staff.
+    pub is_staff: bool,
+    pub posted_date: DateTime<Utc>,
+    /// Markdown of the post.
+    pub content: String,
+    pub images: Vec<String>,
+}
+
+impl ForumPost {
+    /// Link to the post within its thread.
+    pub fn url(&self, lang: WebsiteLanguage) -> String {
+        format!("{}/forum/view-post/{}", lang.base_url(), self.id)
+    }
+}
+
+/// One page of a thread.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ThreadPage {
+    pub posts: Vec<ForumPost>,
+    /// Number of the page, starting from 1.
+    pub page: u32,
+    /// Number of the last page of the thread.
+    pub last_page: u32,
+    /// Why some posts were skipped, e.g. a date that could not be parsed.
+    /// Only the first problem is kept.
+    #[serde(skip)]
+    pub skipped: Option<LayoutError>,
+}
+
+/// Fetches every page of the thread and returns all posts in order.
+/// Skipped posts are not reported, use [`fetch_thread_page`] to see them.
+pub async fn fetch_thread_posts(
+    thread_url: &str,
+    lang: WebsiteLanguage,
+    timezone: Tz,
+) -> Result<Vec<ForumPost>, Error> {
+    let first = fetch_thread_page(thread_url, 1, lang, timezone).await?;
+    let mut posts = first.posts;
+    for page in 2..=first.last_page {
+        let page = fetch_thread_page(thread_url, page, lang, timezone).await?;
+        posts.extend(page.posts);
+    }
+    Ok(posts)
+}
+
+/// Fetches one page of the thread, starting from 1.
+pub async fn fetch_thread_page(
+    thread_url: &str,
+    page: u32,
+    lang: WebsiteLanguage,
+    timezone: Tz,
+) -> Result<ThreadPage, Error> {
+    let html = http::text(&page_url(thread_url, page)).await?;
+    Ok(parse_thread_page(&html, lang, timezone)?)
+}
+
+/// `https://www.pathofexile.com/forum/view-thread/3594080/page/2`
+pub fn page_url(thread_url: &str, page: u32) -> String {
+    let thread_url = thread_url.trim_end_matches('/');
+    let thread_url = match thread_url.rsplit_once("/page/") {
+        Some((thread_url, _)) => thread_url,
+        None => thread_url,
+    };
+    format!("{thread_url}/page/{page}")
+}
+
+/// Parses one page of a thread.
+///
+/// Posts with a date that could not be parsed are skipped and reported in
+/// [`ThreadPage::skipped`]. The page is rejected if none of its posts could be read.
+pub fn parse_thread_page(
+    html: &str,
+    lang: WebsiteLanguage,
+    timezone: Tz,
+) -> Result<ThreadPage, LayoutError> {
+    let document = Html::parse_document(html);
+    let (page, last_page) = pagination(&document);
+
+    // News threads keep the opening post in two rows: content first, then author info.
+    let mut news_post_content = None;
+    let mut posts = Vec::new();
+    let mut skipped = None;
+    for row in document.select(&selector(".forumPostListTable > tbody > tr")) {
+        let classes: Vec<&str> = row.value().classes().collect();
+        if classes.contains(&"newsPost") && !classes.contains(&"newsPostInfo") {
+            news_post_content = row.select(&selector(".content")).next();
+            continue;
+        }
+
+        let content = row
+            .select(&selector(".content-container .content"))
+            .next()
+            .or_else(|| news_post_content.take());
+        let Some(content) = content else {
+            continue;
+        };
+        match parse_post(&row, &content, lang, timezone) {
+            Ok(Some(post)) => posts.push(post),
+            Ok(None) => {}
+            Err(err) => {
+                skipped.get_or_insert(LayoutError::Date(err));
+            }
+        }
+    }
+
+    match (posts.is_empty(), skipped) {
+        (true, Some(err)) => Err(err),
+        (_, skipped) => Ok(ThreadPage {
+            posts,
+            page,
+            last_page,
+            skipped,
+        }),
+    }
+}
+
+/// None if the row has no post cells.
+fn parse_post(
+    row: &ElementRef,
+    content: &ElementRef,
+    lang: WebsiteLanguage,
+    timezone: Tz,
+) -> Result<Option<ForumPost>, ParseDateError> {
+    let Some(posted_by) = row.select(&selector(".posted-by")).next() else {
+        return Ok(None);
+    };
+    let Some(id) = post_id(&posted_by) else {
+        return Ok(None);
+    };
+    let Some(date) = posted_by.select(&selector(".post_date")).next() else {
+        return Ok(None);
+    };
+    let date_str = date.text().collect::<String>();
+    let posted_date = crate::parse_forum_date(lang, date_str.trim(), timezone)?;
+
+    let profile_link = posted_by.select(&selector(".profile-link")).next();
+    let author = profile_link
+        .and_then(|link| link.select(&selector("a")).next())
+        .map(|a| a.text().collect::<String>());
+    let is_staff = row.value().classes().any(|class| class == "staff")
+        || profile_link.is_some_and(|link| link.value().classes().any(|class| class == "staff"));
+
+    let images = content
+        .select(&selector("img"))
+        .filter_map(|img| img.attr("src"))
+        .map(|src| markdown::resolve_url(lang.base_url(), src))
+        .collect();
+
+    Ok(Some(ForumPost {
+        id,
+        author,
+        is_staff,
+        posted_date,
+        content: markdown::html_to_markdown(content, lang.base_url()),
+        images,
+    }))
+}
+
+fn post_id(posted_by: &ElementRef) -> Option<u64> {
+    posted_by
+        .select(&selector(".posted-by-link"))
+        .next()?
+        .attr("href")?
+        .trim_start_matches("#p")
+        .parse()
+        .ok()
+}
+
+/// Current and last page numbers, 1 and 1 for threads without pagination.
+fn pagination(document: &Html) -> (u32, u32) {
+    let Some(pagination) = document.select(&selector(".pagination")).next() else {
+        return (1, 1);
+    };
+
+    let page_number = |a: ElementRef| a.text().collect::<String>().trim().parse::<u32>().ok();
+    let page = pagination
+        .select(&selector("a.current"))
+        .next()
+        .and_then(page_number)
+        .unwrap_or(1);
+    let last_page = pagination
+        .select(&selector("a"))
+        .filter_map(page_number)
+        .max()
+        .unwrap_or(page);
+
+    (page, last_page.max(page))
+}
+
+fn selector(selectors: &str) -> Selector {
+    Selector::parse(selectors).unwrap()
+}
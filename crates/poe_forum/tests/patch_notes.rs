@@ -0,0 +1,10 @@
+#[test]
+fn gets_patch_notes_of_opening_post() {
+    let html =
+        std::fs::read_to_string("../markdown/tests/fixtures/patch-notes-0.2.0e.html").unwrap();
+    let notes = poe_forum::get_patch_notes(&html).unwrap();
+
+    assert_eq!(notes.version.as_deref(), Some("0.2.0e"));
+    assert_eq!(notes.sections.len(), 10);
+    assert_eq!(notes.sections[9].title, "Bug Fixes");
+}
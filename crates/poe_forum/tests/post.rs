@@ -0,0 +1,76 @@
+use poe_forum::post::{Media, MediaKind};
+
+fn link_at<'a>(content: &'a str, media: &Media) -> &'a str {
+    &content[media.position..media.position + media.link_len]
+}
+
+#[test]
+fn extracts_every_media_of_post() {
+    let html = std::fs::read_to_string("tests/fixtures/view-thread-news.html").unwrap();
+    let details = poe_forum::get_post_details(
+        &html,
+        "https://www.pathofexile.com/forum/view-thread/3779025",
+    )
+    .unwrap();
+    let media = &details.media;
+
+    assert_eq!(
+        details.image_src.as_deref(),
+        Some("https://web.poecdn.com/public/news/2025-04-04/DawnOfTheHunt.jpg")
+    );
+    assert_eq!(
+        media
+            .iter()
+            .map(|media| (media.kind, media.url.as_str(), media.alt.as_deref()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                MediaKind::Image,
+                "https://web.poecdn.com/public/news/2025-04-04/DawnOfTheHunt.jpg",
+                Some("Dawn of the Hunt")
+            ),
+            (
+                MediaKind::Video,
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+                Some("Launch Trailer")
+            ),
+            (
+                MediaKind::Image,
+                "https://web.poecdn.com/public/news/2025-04-04/Huntress.png",
+                None
+            ),
+            (MediaKind::Video, "https://vimeo.com/1072211044", None),
+            (
+                MediaKind::Attachment,
+                "https://web.poecdn.com/public/news/2025-04-04/PatchNotes.pdf",
+                Some("patch notes (PDF)")
+            ),
+        ]
+    );
+
+    // Linked image is converted to a link to its href.
+    assert_eq!(
+        link_at(&details.content, &media[0]),
+        "[Image Dawn of the Hunt](https://www.pathofexile.com/dawnofthehunt)"
+    );
+    assert_eq!(
+        link_at(&details.content, &media[1]),
+        "[Video Launch Trailer](https://www.youtube.com/watch?v=dQw4w9WgXcQ)"
+    );
+    assert_eq!(
+        link_at(&details.content, &media[2]),
+        "[Image ](https://web.poecdn.com/public/news/2025-04-04/Huntress.png)"
+    );
+    assert_eq!(
+        link_at(&details.content, &media[4]),
+        "[patch notes (PDF)](https://web.poecdn.com/public/news/2025-04-04/PatchNotes.pdf)"
+    );
+    assert!(
+        details.content[..media[2].position].contains("The Huntress wields a spear and buckler.")
+    );
+    assert!(
+        media
+            .windows(2)
+            .all(|pair| pair[0].position < pair[1].position)
+    );
+}
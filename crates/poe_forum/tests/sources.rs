@@ -0,0 +1,100 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use poe_forum::{
+    LayoutError, Subforum, WebsiteLanguage,
+    failover::{is_behind, is_stale},
+};
+
+/// The API response was made from the same page, with Moscow wall-clock times labeled as UTC.
+#[test]
+fn api_and_forum_agree_on_time() {
+    let json = std::fs::read_to_string("tests/fixtures/api/threads-ru-patch-notes.json").unwrap();
+    let html = std::fs::read_to_string("tests/fixtures/view-forum/ru.html").unwrap();
+
+    let from_api = poe_forum::parse_api_threads(
+        &json,
+        Subforum::PatchNotes,
+        WebsiteLanguage::Ru,
+        Some(Tz::Europe__Moscow),
+    )
+    .unwrap();
+    let from_forum = poe_forum::parse_subforum_page(
+        &html,
+        Subforum::PatchNotes,
+        WebsiteLanguage::Ru,
+        Tz::Europe__Moscow,
+    )
+    .unwrap()
+    .threads;
+
+    assert_eq!(from_api, from_forum);
+    assert_eq!(
+        from_api[0].posted_date,
+        "2024-05-08T16:37:26Z".parse::<DateTime<Utc>>().unwrap()
+    );
+}
+
+#[test]
+fn reports_unexpected_api_response() {
+    let err = poe_forum::parse_api_threads(
+        r#"{"error": "rate limited"}"#,
+        Subforum::News,
+        WebsiteLanguage::En,
+        None,
+    )
+    .unwrap_err();
+    assert!(matches!(err, LayoutError::Api(_)));
+}
+
+#[test]
+fn detects_stale_responses() {
+    let json = std::fs::read_to_string("tests/fixtures/api/threads-ru-patch-notes.json").unwrap();
+    let threads = poe_forum::parse_api_threads(
+        &json,
+        Subforum::PatchNotes,
+        WebsiteLanguage::Ru,
+        Some(Tz::Europe__Moscow),
+    )
+    .unwrap();
+    let newest = threads[0].posted_date;
+
+    assert!(!is_stale(&threads, None));
+    assert!(!is_stale(&threads, Some(newest)));
+    assert!(is_stale(&threads, Some(newest + TimeDelta::minutes(5))));
+    assert!(is_stale(&[], None));
+}
+
+#[test]
+fn detects_api_missing_a_newer_thread() {
+    let json = std::fs::read_to_string("tests/fixtures/api/threads-ru-patch-notes.json").unwrap();
+    let html = std::fs::read_to_string("tests/fixtures/view-forum/ru.html").unwrap();
+    let from_forum = poe_forum::parse_subforum_page(
+        &html,
+        Subforum::PatchNotes,
+        WebsiteLanguage::Ru,
+        Tz::Europe__Moscow,
+    )
+    .unwrap()
+    .threads;
+    // API that stopped updating before the newest thread was posted.
+    let lagging_api: Vec<_> = poe_forum::parse_api_threads(
+        &json,
+        Subforum::PatchNotes,
+        WebsiteLanguage::Ru,
+        Some(Tz::Europe__Moscow),
+    )
+    .unwrap()
+    .into_iter()
+    .skip(1)
+    .collect();
+
+    // Not stale on its own, the API itself is the newest source seen.
+    assert!(!is_stale(
+        &lagging_api,
+        lagging_api.first().map(|thread| thread.posted_date)
+    ));
+    assert!(is_behind(&lagging_api, &from_forum));
+    assert!(!is_behind(&from_forum, &from_forum));
+    assert!(!is_behind(&from_forum, &[]));
+    assert!(is_behind(&[], &from_forum));
+}
@@ -0,0 +1,172 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use poe_forum::{LayoutError, Subforum, SubforumPage, WebsiteLanguage, date::parse_forum_date_at};
+
+fn date(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+/// Every saved page starts with the same thread, posted at 2024-05-08T16:37:26Z
+/// and shown in the local time of the realm.
+#[test]
+fn parses_subforum_page_of_every_language() {
+    let pages = [
+        ("en", WebsiteLanguage::En, Tz::UTC),
+        ("ru", WebsiteLanguage::Ru, Tz::Europe__Moscow),
+        ("de", WebsiteLanguage::De, Tz::Europe__Berlin),
+        ("fr", WebsiteLanguage::Fr, Tz::Europe__Paris),
+        ("es", WebsiteLanguage::Es, Tz::Europe__Madrid),
+        ("br", WebsiteLanguage::Pt, Tz::America__Sao_Paulo),
+        ("th", WebsiteLanguage::Th, Tz::Asia__Bangkok),
+        ("jp", WebsiteLanguage::Jp, Tz::Asia__Tokyo),
+        ("kr", WebsiteLanguage::Kr, Tz::Asia__Seoul),
+        ("tw", WebsiteLanguage::Tw, Tz::Asia__Taipei),
+    ];
+
+    for (name, lang, timezone) in pages {
+        let html =
+            std::fs::read_to_string(format!("tests/fixtures/view-forum/{name}.html")).unwrap();
+        let threads = poe_forum::parse_subforum_page(&html, Subforum::PatchNotes, lang, timezone)
+            .unwrap()
+            .threads;
+
+        assert_eq!(threads.len(), 3, "{name}");
+        let first = &threads[0];
+        assert_eq!(first.posted_date, date("2024-05-08T16:37:26Z"), "{name}");
+        assert!(first.title.contains("3.26.0b"), "{name}");
+        assert!(
+            first
+                .url
+                .starts_with(&format!("{}/forum/view-thread/", lang.base_url())),
+            "{name}: {}",
+            first.url
+        );
+        assert_eq!(first.author.as_deref(), Some("Community_Team"), "{name}");
+        assert_eq!(first.lang, lang);
+    }
+}
+
+#[test]
+fn parses_english_page() {
+    let html = std::fs::read_to_string("tests/fixtures/view-forum/en.html").unwrap();
+    let threads =
+        poe_forum::parse_subforum_page(&html, Subforum::PatchNotes, WebsiteLanguage::En, Tz::UTC)
+            .unwrap()
+            .threads;
+
+    assert_eq!(
+        threads[0].url,
+        "https://www.pathofexile.com/forum/view-thread/3741278"
+    );
+    assert_eq!(threads[1].posted_date, date("2024-03-26T09:05:00Z"));
+    // 12 AM is midnight.
+    assert_eq!(threads[2].posted_date, date("2023-12-12T00:15:44Z"));
+}
+
+#[test]
+fn parses_relative_dates() {
+    let now = date("2024-05-08T20:00:00Z");
+    let cases = [
+        (WebsiteLanguage::En, "Today, 4:37:26 PM", 0),
+        (WebsiteLanguage::En, "Yesterday, 4:37:26 PM", 1),
+        (WebsiteLanguage::Ru, "Сегодня, 16:37:26", 0),
+        (WebsiteLanguage::De, "Gestern, 16:37:26", 1),
+        (WebsiteLanguage::Fr, "Aujourd’hui à 16:37:26", 0),
+        (WebsiteLanguage::Es, "Hoy, 16:37:26", 0),
+        (WebsiteLanguage::Pt, "Ontem 16:37:26", 1),
+        (WebsiteLanguage::Th, "วันนี้ 16:37:26", 0),
+        (WebsiteLanguage::Jp, "今日 16:37:26", 0),
+        (WebsiteLanguage::Kr, "어제 오후 4:37:26", 1),
+        (WebsiteLanguage::Tw, "今天 下午4:37:26", 0),
+    ];
+
+    for (lang, raw, days_ago) in cases {
+        assert_eq!(
+            parse_forum_date_at(lang, raw, Tz::UTC, now),
+            Ok(date("2024-05-08T16:37:26Z") - TimeDelta::days(days_ago)),
+            "{lang}: {raw}"
+        );
+    }
+}
+
+#[test]
+fn reports_unparsed_date() {
+    let err = parse_forum_date_at(WebsiteLanguage::En, "Maybe tomorrow", Tz::UTC, Utc::now())
+        .unwrap_err();
+    assert_eq!(err.lang, WebsiteLanguage::En);
+    assert_eq!(err.raw, "Maybe tomorrow");
+}
+
+fn parse_en(html: &str) -> Result<SubforumPage, LayoutError> {
+    poe_forum::parse_subforum_page(html, Subforum::PatchNotes, WebsiteLanguage::En, Tz::UTC)
+}
+
+#[test]
+fn reports_layout_changes() {
+    let html = std::fs::read_to_string("tests/fixtures/view-forum/en.html").unwrap();
+
+    assert_eq!(
+        parse_en("<html><body><div>Maintenance</div></body></html>"),
+        Err(LayoutError::NoRows)
+    );
+
+    // Renamed title cell.
+    assert_eq!(
+        parse_en(&html.replace("class=\"title\"", "class=\"thread-title\"")),
+        Err(LayoutError::Unparsed { rows: 3, parsed: 0 })
+    );
+
+    // Changed date format of every row.
+    let Err(LayoutError::Date(err)) = parse_en(
+        &html
+            .replace(", May 8, 2024,", ", 8th of May,")
+            .replace(", Mar 26, 2024,", ", 26th of March,")
+            .replace(", Dec 12, 2023,", ", 12th of December,"),
+    ) else {
+        panic!("expected date error");
+    };
+    assert_eq!(err.raw, ", 8th of May, 4:37:26 PM");
+}
+
+#[test]
+fn skips_rows_with_unparsed_dates() {
+    let html = std::fs::read_to_string("tests/fixtures/view-forum/en.html").unwrap();
+
+    let page = parse_en(&html.replace(", May 8, 2024,", ", 8th of May,")).unwrap();
+
+    assert_eq!(page.threads.len(), 2);
+    assert_eq!(
+        page.threads[0].url,
+        "https://www.pathofexile.com/forum/view-thread/3741001"
+    );
+    let Some(LayoutError::Date(err)) = page.skipped else {
+        panic!("expected skipped date");
+    };
+    assert_eq!(err.raw, ", 8th of May, 4:37:26 PM");
+    assert_eq!(parse_en(&html).unwrap().skipped, None);
+}
+
+#[test]
+fn follows_daylight_saving_time() {
+    let berlin =
+        |raw| parse_forum_date_at(WebsiteLanguage::De, raw, Tz::Europe__Berlin, Utc::now());
+
+    assert_eq!(
+        berlin("12.01.2024 16:37:26"),
+        Ok(date("2024-01-12T15:37:26Z"))
+    );
+    assert_eq!(
+        berlin("08.05.2024 16:37:26"),
+        Ok(date("2024-05-08T14:37:26Z"))
+    );
+    // Clocks went from 2:00 to 3:00.
+    assert_eq!(
+        berlin("31.03.2024 02:30:00"),
+        Ok(date("2024-03-31T01:30:00Z"))
+    );
+    // Clocks went from 3:00 back to 2:00, the earlier time is taken.
+    assert_eq!(
+        berlin("27.10.2024 02:30:00"),
+        Ok(date("2024-10-27T00:30:00Z"))
+    );
+}
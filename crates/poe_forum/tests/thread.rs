@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use poe_forum::{LayoutError, WebsiteLanguage, thread};
+
+fn date(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+#[test]
+fn parses_thread_page() {
+    let html = std::fs::read_to_string("tests/fixtures/view-thread-3594080.html").unwrap();
+    let page = thread::parse_thread_page(&html, WebsiteLanguage::En, Tz::UTC).unwrap();
+
+    assert_eq!(page.page, 1);
+    assert_eq!(page.skipped, None);
+    assert_eq!(page.last_page, 43);
+    assert_eq!(page.posts.len(), 10);
+
+    let news_post = &page.posts[0];
+    assert_eq!(news_post.id, 25518724);
+    assert_eq!(news_post.author.as_deref(), Some("Community_Team"));
+    assert!(news_post.is_staff);
+    assert_eq!(news_post.posted_date, date("2024-12-06T21:16:03Z"));
+    assert!(
+        news_post
+            .content
+            .contains("Path of Exile 2 Early Access Launch - Live Updates")
+    );
+    assert_eq!(
+        news_post.url(WebsiteLanguage::En),
+        "https://www.pathofexile.com/forum/view-post/25518724"
+    );
+
+    let reply = &page.posts[1];
+    assert_eq!(reply.id, 25518731);
+    assert_eq!(reply.author.as_deref(), Some("ClumsyParasite"));
+    assert!(!reply.is_staff);
+    assert_eq!(reply.posted_date, date("2024-12-06T21:17:02Z"));
+    assert_eq!(reply.content, "HYPE! LOGIN! BEST GAME OF ALL TIME INCOMING");
+    assert!(reply.images.is_empty());
+
+    assert_eq!(page.posts.iter().filter(|post| post.is_staff).count(), 1);
+}
+
+#[test]
+fn reports_posts_with_unreadable_dates() {
+    let html = std::fs::read_to_string("tests/fixtures/view-thread-3594080.html")
+        .unwrap()
+        .replace("Dec 6, 2024, 9:17:02 PM", "6 déc. 2024, 21:17:02");
+    let page = thread::parse_thread_page(&html, WebsiteLanguage::En, Tz::UTC).unwrap();
+
+    assert_eq!(page.posts.len(), 9);
+    assert!(page.posts.iter().all(|post| post.id != 25518731));
+    assert!(matches!(page.skipped, Some(LayoutError::Date(_))));
+}
+
+#[test]
+fn rejects_page_without_readable_dates() {
+    let html = std::fs::read_to_string("tests/fixtures/view-thread-3594080.html")
+        .unwrap()
+        .replace("Dec 6, 2024", "6 déc. 2024");
+    let err = thread::parse_thread_page(&html, WebsiteLanguage::En, Tz::UTC).unwrap_err();
+
+    assert!(matches!(err, LayoutError::Date(_)));
+}
+
+#[test]
+fn builds_page_urls() {
+    assert_eq!(
+        thread::page_url("https://www.pathofexile.com/forum/view-thread/3594080", 2),
+        "https://www.pathofexile.com/forum/view-thread/3594080/page/2"
+    );
+    assert_eq!(
+        thread::page_url(
+            "https://ru.pathofexile.com/forum/view-thread/3789613/page/4/",
+            1
+        ),
+        "https://ru.pathofexile.com/forum/view-thread/3789613/page/1"
+    );
+}
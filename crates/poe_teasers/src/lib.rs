@@ -33,19 +33,8 @@ pub fn parse_teasers_thread(
             let videos_urls: Vec<String> = spoiler_element
                 .select(&spoiler_content_iframe_selector)
                 .filter_map(|iframe| {
-                    iframe.attr("src").and_then(|src| match src {
-                        src if src.starts_with("//www.youtube.com/") => {
-                            Some(format!("https:{src}").replace("embed", "watch"))
-                        }
-
-                        src if src.starts_with("https://player.vimeo.com/video/") => {
-                            Some(src.replace("player.vimeo.com/video/", "vimeo.com/"))
-                        }
-
-                        src if src.starts_with("https://") => Some(src.replace("embed", "watch")),
-
-                        _ => None,
-                    })
+                    let src = markdown::resolve_url(forum_thread.url(), iframe.attr("src")?);
+                    markdown::video_url(&src)
                 })
                 .collect();
 
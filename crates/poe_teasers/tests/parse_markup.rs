@@ -32,25 +32,25 @@ fn parse_old_3_25_teasers_thread() {
     Teaser {
         heading: "В дополнении Поселенцы Калгуура вы сможете начать схватки в Жатве всего одним действием.".to_owned(),
         images_urls: vec![],
-        videos_urls: vec!["https://www.youtube.com/watch/7CwpLN5ryw4".to_owned()],
+        videos_urls: vec!["https://www.youtube.com/watch?v=7CwpLN5ryw4".to_owned()],
         forum_thread
     },
     Teaser {
         heading: "В Path of Exile: Поселенцы Калгуура вам больше не нужно нажимать на порталы в областях для их активации.".to_owned(),
         images_urls: vec![],
-        videos_urls: vec!["https://www.youtube.com/watch/0Wd0mLXtteg".to_owned()],
+        videos_urls: vec!["https://www.youtube.com/watch?v=0Wd0mLXtteg".to_owned()],
         forum_thread
     },
      Teaser {
         heading: "Мы переработали качество предметов! Редкость предмета больше не имеет значения при использовании валюты для качества на неуникальные предметы. Вместо этого повышение качества теперь зависит от уровня предмета.".to_owned(),
         images_urls: vec![],
-        videos_urls: vec!["https://www.youtube.com/watch/FlgP5NEQWbs".to_owned()],
+        videos_urls: vec!["https://www.youtube.com/watch?v=FlgP5NEQWbs".to_owned()],
         forum_thread
     },
     Teaser {
         heading: "Прибавки от качества на броне и оружии теперь мультипликативные!".to_owned(),
         images_urls: vec![],
-        videos_urls: vec!["https://www.youtube.com/watch/T2bX9xXQOL8".to_owned()],
+        videos_urls: vec!["https://www.youtube.com/watch?v=T2bX9xXQOL8".to_owned()],
         forum_thread
     },
 ]);